@@ -0,0 +1,5 @@
+-- Keyset pagination orders by (created_at, id); make created_at total and index the pair.
+UPDATE reviews SET created_at = NOW() WHERE created_at IS NULL;
+ALTER TABLE reviews ALTER COLUMN created_at SET NOT NULL;
+
+CREATE INDEX IF NOT EXISTS idx_reviews_created_at_id ON reviews(created_at DESC, id DESC);
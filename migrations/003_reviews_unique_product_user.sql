@@ -0,0 +1,20 @@
+-- One review per user per product. Existing duplicates are not dropped: all but the newest
+-- review of each pair move to `reviews_duplicate_archive`, where they can be inspected or
+-- restored by hand, before the constraint is added.
+CREATE TABLE IF NOT EXISTS reviews_duplicate_archive (
+    LIKE reviews INCLUDING DEFAULTS,
+    archived_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+WITH ranked AS (
+    SELECT id,
+           ROW_NUMBER() OVER (PARTITION BY product_id, user_id ORDER BY created_at DESC, id DESC) AS position
+    FROM reviews
+), moved AS (
+    DELETE FROM reviews WHERE id IN (SELECT id FROM ranked WHERE position > 1)
+    RETURNING id, product_id, user_id, rating, body, created_at
+)
+INSERT INTO reviews_duplicate_archive (id, product_id, user_id, rating, body, created_at)
+SELECT id, product_id, user_id, rating, body, created_at FROM moved;
+
+ALTER TABLE reviews ADD CONSTRAINT reviews_product_user_key UNIQUE (product_id, user_id);
@@ -0,0 +1,22 @@
+-- Per-product rating totals, kept in step with `reviews` by the repository on every write.
+CREATE TABLE IF NOT EXISTS product_rating_aggregates (
+    product_id UUID PRIMARY KEY,
+    review_count BIGINT NOT NULL DEFAULT 0,
+    rating_sum BIGINT NOT NULL DEFAULT 0,
+    star_1 BIGINT NOT NULL DEFAULT 0,
+    star_2 BIGINT NOT NULL DEFAULT 0,
+    star_3 BIGINT NOT NULL DEFAULT 0,
+    star_4 BIGINT NOT NULL DEFAULT 0,
+    star_5 BIGINT NOT NULL DEFAULT 0,
+    latest_review_at TIMESTAMPTZ
+);
+
+INSERT INTO product_rating_aggregates
+    (product_id, review_count, rating_sum, star_1, star_2, star_3, star_4, star_5, latest_review_at)
+SELECT product_id, COUNT(*), SUM(rating),
+       COUNT(*) FILTER (WHERE rating = 1), COUNT(*) FILTER (WHERE rating = 2),
+       COUNT(*) FILTER (WHERE rating = 3), COUNT(*) FILTER (WHERE rating = 4),
+       COUNT(*) FILTER (WHERE rating = 5), MAX(created_at)
+FROM reviews
+GROUP BY product_id
+ON CONFLICT (product_id) DO NOTHING;
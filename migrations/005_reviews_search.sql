@@ -0,0 +1,6 @@
+-- Full-text search over review bodies.
+ALTER TABLE reviews
+    ADD COLUMN IF NOT EXISTS body_tsv tsvector
+    GENERATED ALWAYS AS (to_tsvector('english', COALESCE(body, ''))) STORED;
+
+CREATE INDEX IF NOT EXISTS idx_reviews_body_tsv ON reviews USING GIN (body_tsv);
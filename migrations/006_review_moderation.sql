@@ -0,0 +1,13 @@
+-- Moderation state. Reviews written before moderation existed were already public, so they
+-- start approved; new reviews default to pending.
+ALTER TABLE reviews
+    ADD COLUMN IF NOT EXISTS status TEXT NOT NULL DEFAULT 'approved'
+        CONSTRAINT reviews_status_check CHECK (status IN ('pending', 'approved', 'rejected', 'hidden')),
+    ADD COLUMN IF NOT EXISTS moderation_reason TEXT,
+    ADD COLUMN IF NOT EXISTS moderated_by UUID,
+    ADD COLUMN IF NOT EXISTS moderated_at TIMESTAMPTZ;
+
+ALTER TABLE reviews ALTER COLUMN status SET DEFAULT 'pending';
+
+-- The moderation queue reads pending reviews oldest first.
+CREATE INDEX IF NOT EXISTS idx_reviews_pending ON reviews(created_at, id) WHERE status = 'pending';
@@ -0,0 +1,17 @@
+-- Shopper reports against reviews. One report per reporter per review; reports go with their review.
+CREATE TABLE IF NOT EXISTS review_reports (
+    id UUID PRIMARY KEY,
+    review_id UUID NOT NULL REFERENCES reviews(id) ON DELETE CASCADE,
+    reporter_id UUID NOT NULL,
+    reason TEXT NOT NULL
+        CONSTRAINT review_reports_reason_check
+        CHECK (reason IN ('spam', 'offensive', 'off_topic', 'fake_review', 'personal_info', 'other')),
+    comment TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT review_reports_review_reporter_key UNIQUE (review_id, reporter_id)
+);
+
+-- The moderator listing reads newest first.
+CREATE INDEX IF NOT EXISTS idx_review_reports_created ON review_reports(created_at DESC, id DESC);
+-- Hidden reviews are moderated from the same queue as pending ones.
+CREATE INDEX IF NOT EXISTS idx_reviews_hidden ON reviews(created_at, id) WHERE status = 'hidden';
@@ -0,0 +1,18 @@
+-- Helpful / unhelpful votes, one per voter per review. `reviews` keeps running totals and
+-- the Wilson lower bound of the helpful share so `sort=most_helpful` can page by keyset.
+CREATE TABLE IF NOT EXISTS review_votes (
+    review_id UUID NOT NULL REFERENCES reviews(id) ON DELETE CASCADE,
+    voter_id UUID NOT NULL,
+    helpful BOOLEAN NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (review_id, voter_id)
+);
+
+ALTER TABLE reviews
+    ADD COLUMN IF NOT EXISTS helpful_count INT NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS unhelpful_count INT NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS helpful_score DOUBLE PRECISION NOT NULL DEFAULT 0;
+
+CREATE INDEX IF NOT EXISTS idx_reviews_most_helpful
+    ON reviews(product_id, helpful_score DESC, created_at DESC, id DESC) WHERE status = 'approved';
@@ -0,0 +1,17 @@
+-- A merchant's public reply to a review, at most one per review. Replies are moderated on
+-- their own, independently of the review they answer, and go with their review.
+CREATE TABLE IF NOT EXISTS review_replies (
+    review_id UUID PRIMARY KEY REFERENCES reviews(id) ON DELETE CASCADE,
+    merchant_id UUID NOT NULL,
+    body TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending'
+        CONSTRAINT review_replies_status_check CHECK (status IN ('pending', 'approved', 'rejected', 'hidden')),
+    moderation_reason TEXT,
+    moderated_by UUID,
+    moderated_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- The reply moderation queue reads oldest first.
+CREATE INDEX IF NOT EXISTS idx_review_replies_status ON review_replies(status, created_at, review_id);
@@ -0,0 +1,16 @@
+-- Earlier versions of each review. `reviews.revision` numbers the current version from 1;
+-- every edit that changes the rating or body copies the version it replaces here first.
+CREATE TABLE IF NOT EXISTS review_revisions (
+    review_id UUID NOT NULL REFERENCES reviews(id) ON DELETE CASCADE,
+    revision INT NOT NULL,
+    rating INT NOT NULL,
+    body TEXT,
+    -- When this version was written, and when the next one replaced it.
+    created_at TIMESTAMPTZ NOT NULL,
+    replaced_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (review_id, revision)
+);
+
+ALTER TABLE reviews
+    ADD COLUMN IF NOT EXISTS revision INT NOT NULL DEFAULT 1,
+    ADD COLUMN IF NOT EXISTS edited_at TIMESTAMPTZ;
@@ -0,0 +1,9 @@
+-- Soft delete. Deleted reviews keep their row, and everything hanging off it, until the
+-- retention purge removes them; only live reviews are listed, counted or held unique.
+ALTER TABLE reviews ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ;
+
+ALTER TABLE reviews DROP CONSTRAINT IF EXISTS reviews_product_user_key;
+CREATE UNIQUE INDEX IF NOT EXISTS reviews_product_user_key ON reviews(product_id, user_id) WHERE deleted_at IS NULL;
+
+-- The purge reads deleted rows oldest first.
+CREATE INDEX IF NOT EXISTS idx_reviews_deleted ON reviews(deleted_at) WHERE deleted_at IS NOT NULL;
@@ -0,0 +1,14 @@
+-- Keys for backend services that call the API on their own behalf rather than a user's.
+-- Only a SHA-256 hash of each key is stored; `prefix` is its first characters, for recognising
+-- a key in listings.
+CREATE TABLE IF NOT EXISTS api_keys (
+    id UUID PRIMARY KEY,
+    name TEXT NOT NULL,
+    prefix TEXT NOT NULL,
+    key_hash TEXT NOT NULL UNIQUE,
+    -- Any of `read`, `write` and `stats`.
+    scopes TEXT[] NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ,
+    revoked_at TIMESTAMPTZ
+);
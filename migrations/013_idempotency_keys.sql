@@ -0,0 +1,17 @@
+-- Responses kept so that `POST /reviews` retries sent with the same `Idempotency-Key` are not
+-- applied twice. A row is claimed before the request runs (`response_status` NULL) and completed
+-- with the response to replay; it is ignored once `expires_at` passes and purged later.
+CREATE TABLE IF NOT EXISTS idempotency_keys (
+    -- The caller the key belongs to: `user:<uuid>` or `key:<api key id>`.
+    scope TEXT NOT NULL,
+    key TEXT NOT NULL,
+    -- Hex SHA-256 of the request payload.
+    request_hash TEXT NOT NULL,
+    response_status INTEGER,
+    response_body TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (scope, key)
+);
+
+CREATE INDEX IF NOT EXISTS idx_idempotency_keys_expires_at ON idempotency_keys(expires_at);
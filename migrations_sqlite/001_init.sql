@@ -0,0 +1,13 @@
+-- SQLite schema equivalent to migrations/. UUIDs are 16-byte blobs; timestamps are
+-- microseconds since the Unix epoch, so they sort numerically and round-trip exactly.
+CREATE TABLE IF NOT EXISTS reviews (
+    id BLOB PRIMARY KEY,
+    product_id BLOB NOT NULL,
+    user_id BLOB NOT NULL,
+    rating INTEGER NOT NULL CHECK (rating >= 1 AND rating <= 5),
+    body TEXT,
+    created_at INTEGER NOT NULL DEFAULT (CAST(unixepoch('subsec') * 1000000 AS INTEGER))
+);
+
+CREATE INDEX IF NOT EXISTS idx_reviews_product_id ON reviews(product_id);
+CREATE INDEX IF NOT EXISTS idx_reviews_user_id ON reviews(user_id);
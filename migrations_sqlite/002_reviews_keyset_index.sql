@@ -0,0 +1 @@
+CREATE INDEX IF NOT EXISTS idx_reviews_created_at_id ON reviews(created_at DESC, id DESC);
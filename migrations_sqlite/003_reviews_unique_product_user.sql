@@ -0,0 +1,23 @@
+-- One review per user per product. As in migrations/, older duplicates move to
+-- `reviews_duplicate_archive` instead of being dropped.
+CREATE TABLE IF NOT EXISTS reviews_duplicate_archive (
+    id BLOB PRIMARY KEY,
+    product_id BLOB NOT NULL,
+    user_id BLOB NOT NULL,
+    rating INTEGER NOT NULL,
+    body TEXT,
+    created_at INTEGER NOT NULL,
+    archived_at INTEGER NOT NULL DEFAULT (CAST(unixepoch('subsec') * 1000000 AS INTEGER))
+);
+
+INSERT INTO reviews_duplicate_archive (id, product_id, user_id, rating, body, created_at)
+SELECT id, product_id, user_id, rating, body, created_at
+FROM (
+    SELECT *, ROW_NUMBER() OVER (PARTITION BY product_id, user_id ORDER BY created_at DESC, id DESC) AS position
+    FROM reviews
+)
+WHERE position > 1;
+
+DELETE FROM reviews WHERE id IN (SELECT id FROM reviews_duplicate_archive);
+
+CREATE UNIQUE INDEX IF NOT EXISTS reviews_product_user_key ON reviews(product_id, user_id);
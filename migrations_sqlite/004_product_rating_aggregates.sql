@@ -0,0 +1,12 @@
+-- Per-product rating totals, kept in step with `reviews` by the repository on every write.
+CREATE TABLE IF NOT EXISTS product_rating_aggregates (
+    product_id BLOB PRIMARY KEY,
+    review_count INTEGER NOT NULL DEFAULT 0,
+    rating_sum INTEGER NOT NULL DEFAULT 0,
+    star_1 INTEGER NOT NULL DEFAULT 0,
+    star_2 INTEGER NOT NULL DEFAULT 0,
+    star_3 INTEGER NOT NULL DEFAULT 0,
+    star_4 INTEGER NOT NULL DEFAULT 0,
+    star_5 INTEGER NOT NULL DEFAULT 0,
+    latest_review_at INTEGER
+);
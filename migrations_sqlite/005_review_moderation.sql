@@ -0,0 +1,11 @@
+-- Moderation state; see migrations/006_review_moderation.sql. SQLite cannot change a column
+-- default afterwards, so existing rows are approved explicitly.
+ALTER TABLE reviews ADD COLUMN status TEXT NOT NULL DEFAULT 'pending'
+    CHECK (status IN ('pending', 'approved', 'rejected', 'hidden'));
+ALTER TABLE reviews ADD COLUMN moderation_reason TEXT;
+ALTER TABLE reviews ADD COLUMN moderated_by BLOB;
+ALTER TABLE reviews ADD COLUMN moderated_at INTEGER;
+
+UPDATE reviews SET status = 'approved';
+
+CREATE INDEX IF NOT EXISTS idx_reviews_pending ON reviews(created_at, id) WHERE status = 'pending';
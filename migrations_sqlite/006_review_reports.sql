@@ -0,0 +1,14 @@
+-- See migrations/007_review_reports.sql.
+CREATE TABLE IF NOT EXISTS review_reports (
+    id BLOB PRIMARY KEY,
+    review_id BLOB NOT NULL REFERENCES reviews(id) ON DELETE CASCADE,
+    reporter_id BLOB NOT NULL,
+    reason TEXT NOT NULL
+        CHECK (reason IN ('spam', 'offensive', 'off_topic', 'fake_review', 'personal_info', 'other')),
+    comment TEXT,
+    created_at INTEGER NOT NULL DEFAULT (CAST(unixepoch('subsec') * 1000000 AS INTEGER)),
+    UNIQUE (review_id, reporter_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_review_reports_created ON review_reports(created_at DESC, id DESC);
+CREATE INDEX IF NOT EXISTS idx_reviews_hidden ON reviews(created_at, id) WHERE status = 'hidden';
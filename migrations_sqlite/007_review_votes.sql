@@ -0,0 +1,16 @@
+-- See migrations/008_review_votes.sql.
+CREATE TABLE IF NOT EXISTS review_votes (
+    review_id BLOB NOT NULL REFERENCES reviews(id) ON DELETE CASCADE,
+    voter_id BLOB NOT NULL,
+    helpful INTEGER NOT NULL CHECK (helpful IN (0, 1)),
+    created_at INTEGER NOT NULL DEFAULT (CAST(unixepoch('subsec') * 1000000 AS INTEGER)),
+    updated_at INTEGER NOT NULL DEFAULT (CAST(unixepoch('subsec') * 1000000 AS INTEGER)),
+    PRIMARY KEY (review_id, voter_id)
+);
+
+ALTER TABLE reviews ADD COLUMN helpful_count INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE reviews ADD COLUMN unhelpful_count INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE reviews ADD COLUMN helpful_score REAL NOT NULL DEFAULT 0;
+
+CREATE INDEX IF NOT EXISTS idx_reviews_most_helpful
+    ON reviews(product_id, helpful_score DESC, created_at DESC, id DESC) WHERE status = 'approved';
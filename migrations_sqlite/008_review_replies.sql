@@ -0,0 +1,15 @@
+-- See migrations/009_review_replies.sql.
+CREATE TABLE IF NOT EXISTS review_replies (
+    review_id BLOB PRIMARY KEY REFERENCES reviews(id) ON DELETE CASCADE,
+    merchant_id BLOB NOT NULL,
+    body TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'approved', 'rejected', 'hidden')),
+    moderation_reason TEXT,
+    moderated_by BLOB,
+    moderated_at INTEGER,
+    created_at INTEGER NOT NULL DEFAULT (CAST(unixepoch('subsec') * 1000000 AS INTEGER)),
+    updated_at INTEGER NOT NULL DEFAULT (CAST(unixepoch('subsec') * 1000000 AS INTEGER))
+);
+
+CREATE INDEX IF NOT EXISTS idx_review_replies_status ON review_replies(status, created_at, review_id);
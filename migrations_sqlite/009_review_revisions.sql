@@ -0,0 +1,13 @@
+-- See migrations/010_review_revisions.sql.
+CREATE TABLE IF NOT EXISTS review_revisions (
+    review_id BLOB NOT NULL REFERENCES reviews(id) ON DELETE CASCADE,
+    revision INTEGER NOT NULL,
+    rating INTEGER NOT NULL,
+    body TEXT,
+    created_at INTEGER NOT NULL,
+    replaced_at INTEGER NOT NULL,
+    PRIMARY KEY (review_id, revision)
+);
+
+ALTER TABLE reviews ADD COLUMN revision INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE reviews ADD COLUMN edited_at INTEGER;
@@ -0,0 +1,7 @@
+-- See migrations/011_reviews_soft_delete.sql.
+ALTER TABLE reviews ADD COLUMN deleted_at INTEGER;
+
+DROP INDEX IF EXISTS reviews_product_user_key;
+CREATE UNIQUE INDEX IF NOT EXISTS reviews_product_user_key ON reviews(product_id, user_id) WHERE deleted_at IS NULL;
+
+CREATE INDEX IF NOT EXISTS idx_reviews_deleted ON reviews(deleted_at) WHERE deleted_at IS NOT NULL;
@@ -0,0 +1,11 @@
+-- See migrations/012_api_keys.sql. Scopes are stored space-separated.
+CREATE TABLE IF NOT EXISTS api_keys (
+    id BLOB PRIMARY KEY,
+    name TEXT NOT NULL,
+    prefix TEXT NOT NULL,
+    key_hash TEXT NOT NULL UNIQUE,
+    scopes TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    expires_at INTEGER,
+    revoked_at INTEGER
+);
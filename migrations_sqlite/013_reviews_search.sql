@@ -0,0 +1,30 @@
+-- Full-text search over review bodies; see migrations/005_reviews_search.sql.
+-- `reviews` has no INTEGER PRIMARY KEY, so VACUUM may renumber its rowids. The index is keyed
+-- by `review_search_ids.doc_id` instead, which stays put.
+CREATE TABLE IF NOT EXISTS review_search_ids (
+    doc_id INTEGER PRIMARY KEY,
+    review_id BLOB NOT NULL UNIQUE
+);
+
+-- Words as `text_match` splits them: alphanumeric runs, case-folded, accents kept.
+CREATE VIRTUAL TABLE IF NOT EXISTS reviews_search USING fts5(body, tokenize = 'unicode61 remove_diacritics 0');
+
+INSERT INTO review_search_ids (review_id) SELECT id FROM reviews WHERE id NOT IN (SELECT review_id FROM review_search_ids);
+INSERT INTO reviews_search (rowid, body)
+SELECT s.doc_id, r.body FROM review_search_ids s JOIN reviews r ON r.id = s.review_id;
+
+CREATE TRIGGER IF NOT EXISTS reviews_search_insert AFTER INSERT ON reviews BEGIN
+    INSERT INTO review_search_ids (review_id) VALUES (new.id);
+    INSERT INTO reviews_search (rowid, body)
+    SELECT doc_id, new.body FROM review_search_ids WHERE review_id = new.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS reviews_search_update AFTER UPDATE OF body ON reviews BEGIN
+    UPDATE reviews_search SET body = new.body
+    WHERE rowid = (SELECT doc_id FROM review_search_ids WHERE review_id = new.id);
+END;
+
+CREATE TRIGGER IF NOT EXISTS reviews_search_delete AFTER DELETE ON reviews BEGIN
+    DELETE FROM reviews_search WHERE rowid = (SELECT doc_id FROM review_search_ids WHERE review_id = old.id);
+    DELETE FROM review_search_ids WHERE review_id = old.id;
+END;
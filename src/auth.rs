@@ -0,0 +1,491 @@
+//! Caller authentication. Verifies HS256 and RS256 JWTs and records the user as a [`Principal`]
+//! in the request extensions; backend services present an `X-API-Key` instead and are recorded
+//! as an [`ApiClient`](crate::extractors::ApiClient).
+
+use std::fmt;
+use std::sync::Arc;
+
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use rsa::pkcs1::DecodeRsaPublicKey;
+use rsa::pkcs8::DecodePublicKey;
+use rsa::signature::Verifier;
+use rsa::{BigUint, RsaPublicKey};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::error::ProblemDetails;
+use crate::extractors::{Principal, API_KEY_CHALLENGE, API_KEY_HEADER};
+use crate::service::ReviewService;
+
+/// Clock skew tolerated on `exp` and `nbf`, unless configured otherwise.
+pub const DEFAULT_LEEWAY_SECS: u64 = 60;
+
+/// Marks the start of every API key, so a leaked one is easy to recognise.
+const API_KEY_TAG: &str = "rvk_";
+/// Leading characters of an API key stored in the clear, tag included.
+pub const API_KEY_PREFIX_CHARS: usize = 12;
+
+/// Why a key could not be loaded or a token was refused.
+#[derive(Debug, thiserror::Error)]
+pub enum AuthError {
+    /// Key material in the configuration is unusable. Raised at startup, never per request.
+    #[error("invalid JWT key: {0}")]
+    Key(String),
+    #[error("Malformed bearer token")]
+    Malformed,
+    #[error("Unsupported token algorithm `{0}`")]
+    UnsupportedAlgorithm(String),
+    #[error("No key matches the token")]
+    UnknownKey,
+    #[error("Invalid token signature")]
+    BadSignature,
+    #[error("Token has expired")]
+    Expired,
+    #[error("Token is not valid yet")]
+    NotYetValid,
+    #[error("Token issuer is not accepted")]
+    WrongIssuer,
+    #[error("Token audience is not accepted")]
+    WrongAudience,
+    #[error("Token subject must be a user UUID")]
+    InvalidSubject,
+    #[error("Unknown, revoked or expired API key")]
+    InvalidApiKey,
+}
+
+impl IntoResponse for AuthError {
+    fn into_response(self) -> Response {
+        tracing::debug!(error = %self, "credentials refused");
+        let challenge = match self {
+            Self::InvalidApiKey => API_KEY_CHALLENGE,
+            _ => "Bearer error=\"invalid_token\"",
+        };
+        let mut response =
+            ProblemDetails::new(StatusCode::UNAUTHORIZED, self.to_string()).into_response();
+        response.headers_mut().insert(
+            header::WWW_AUTHENTICATE,
+            HeaderValue::from_static(challenge),
+        );
+        response
+    }
+}
+
+/// What a user may do, carried in the token's `roles` claim. Admins may do everything.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Role {
+    Shopper,
+    Merchant,
+    Moderator,
+    Admin,
+}
+
+impl Role {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Shopper => "shopper",
+            Self::Merchant => "merchant",
+            Self::Moderator => "moderator",
+            Self::Admin => "admin",
+        }
+    }
+
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "shopper" => Some(Self::Shopper),
+            "merchant" => Some(Self::Merchant),
+            "moderator" => Some(Self::Moderator),
+            "admin" => Some(Self::Admin),
+            _ => None,
+        }
+    }
+}
+
+/// Signature algorithms accepted in the token header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Algorithm {
+    Hs256,
+    Rs256,
+}
+
+impl Algorithm {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "HS256" => Some(Self::Hs256),
+            "RS256" => Some(Self::Rs256),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone)]
+enum KeyMaterial {
+    Hs256(Vec<u8>),
+    Rs256(rsa::pkcs1v15::VerifyingKey<Sha256>),
+}
+
+/// One verification key, optionally named by a JWKS `kid`.
+#[derive(Clone)]
+struct JwtKey {
+    kid: Option<String>,
+    material: KeyMaterial,
+}
+
+impl JwtKey {
+    fn algorithm(&self) -> Algorithm {
+        match self.material {
+            KeyMaterial::Hs256(_) => Algorithm::Hs256,
+            KeyMaterial::Rs256(_) => Algorithm::Rs256,
+        }
+    }
+
+    fn verifies(&self, message: &[u8], signature: &[u8]) -> bool {
+        match &self.material {
+            KeyMaterial::Hs256(secret) => {
+                let Ok(mut mac) = Hmac::<Sha256>::new_from_slice(secret) else {
+                    return false;
+                };
+                mac.update(message);
+                mac.verify_slice(signature).is_ok()
+            }
+            KeyMaterial::Rs256(key) => rsa::pkcs1v15::Signature::try_from(signature)
+                .is_ok_and(|signature| key.verify(message, &signature).is_ok()),
+        }
+    }
+}
+
+/// Secrets are never printed.
+impl fmt::Debug for JwtKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JwtKey")
+            .field("kid", &self.kid)
+            .field("algorithm", &self.algorithm())
+            .finish()
+    }
+}
+
+/// Keys and claim checks for bearer tokens. With no keys, every token is refused.
+#[derive(Debug, Clone)]
+pub struct AuthConfig {
+    keys: Vec<JwtKey>,
+    /// Required `iss` claim, if set.
+    pub issuer: Option<String>,
+    /// Value that must appear in the `aud` claim, if set.
+    pub audience: Option<String>,
+    pub leeway_secs: u64,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            keys: Vec::new(),
+            issuer: None,
+            audience: None,
+            leeway_secs: DEFAULT_LEEWAY_SECS,
+        }
+    }
+}
+
+impl AuthConfig {
+    /// Load keys and claim checks from the environment. Unlike [`crate::config::Config::from_env`],
+    /// unusable key material is an error, so a misconfigured service fails at startup.
+    ///
+    /// - `JWT_HS256_SECRET`: shared secret for HS256 tokens
+    /// - `JWT_RS256_PUBLIC_KEY`: PEM public key (SPKI or PKCS#1) for RS256 tokens
+    /// - `JWT_JWKS_FILE`: path to a local JWKS file with `RSA` and/or `oct` keys
+    /// - `JWT_ISSUER`, `JWT_AUDIENCE`: required `iss` and `aud` claims
+    /// - `JWT_LEEWAY_SECS`: clock skew tolerated on `exp` and `nbf`
+    pub fn from_env() -> Result<Self, AuthError> {
+        let mut config = Self::default();
+        if let Ok(secret) = std::env::var("JWT_HS256_SECRET") {
+            config = config.with_hs256_secret(secret.as_bytes());
+        }
+        if let Ok(pem) = std::env::var("JWT_RS256_PUBLIC_KEY") {
+            config = config.with_rs256_pem(&pem)?;
+        }
+        if let Ok(path) = std::env::var("JWT_JWKS_FILE") {
+            let json = std::fs::read_to_string(&path)
+                .map_err(|e| AuthError::Key(format!("cannot read {}: {}", path, e)))?;
+            config = config.with_jwks(&json)?;
+        }
+        config.issuer = std::env::var("JWT_ISSUER").ok();
+        config.audience = std::env::var("JWT_AUDIENCE").ok();
+        if let Some(v) = std::env::var("JWT_LEEWAY_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+        {
+            config.leeway_secs = v;
+        }
+        Ok(config)
+    }
+
+    /// Accept HS256 tokens signed with `secret`.
+    pub fn with_hs256_secret(mut self, secret: &[u8]) -> Self {
+        self.keys.push(JwtKey {
+            kid: None,
+            material: KeyMaterial::Hs256(secret.to_vec()),
+        });
+        self
+    }
+
+    /// Accept RS256 tokens signed by the private half of this PEM public key.
+    pub fn with_rs256_pem(mut self, pem: &str) -> Result<Self, AuthError> {
+        let key = RsaPublicKey::from_public_key_pem(pem)
+            .or_else(|_| RsaPublicKey::from_pkcs1_pem(pem))
+            .map_err(|e| AuthError::Key(format!("RS256 public key: {}", e)))?;
+        self.keys.push(JwtKey {
+            kid: None,
+            material: KeyMaterial::Rs256(rsa::pkcs1v15::VerifyingKey::new(key)),
+        });
+        Ok(self)
+    }
+
+    /// Accept tokens signed by the keys of a JWKS document. Keys for other algorithms or
+    /// uses are skipped; a document with no usable key is an error.
+    pub fn with_jwks(mut self, json: &str) -> Result<Self, AuthError> {
+        let jwks: Jwks =
+            serde_json::from_str(json).map_err(|e| AuthError::Key(format!("JWKS: {}", e)))?;
+        let before = self.keys.len();
+        for jwk in jwks.keys {
+            if jwk.key_use.as_deref().is_some_and(|u| u != "sig") {
+                continue;
+            }
+            let material = match (jwk.kty.as_str(), jwk.alg.as_deref()) {
+                ("RSA", None | Some("RS256")) => {
+                    let (Some(n), Some(e)) = (jwk.n, jwk.e) else {
+                        return Err(AuthError::Key(
+                            "JWKS: RSA key without `n` and `e`".to_string(),
+                        ));
+                    };
+                    let key = RsaPublicKey::new(
+                        BigUint::from_bytes_be(&decode_jwk_part(&n)?),
+                        BigUint::from_bytes_be(&decode_jwk_part(&e)?),
+                    )
+                    .map_err(|e| AuthError::Key(format!("JWKS: {}", e)))?;
+                    KeyMaterial::Rs256(rsa::pkcs1v15::VerifyingKey::new(key))
+                }
+                ("oct", None | Some("HS256")) => {
+                    let Some(k) = jwk.k else {
+                        return Err(AuthError::Key("JWKS: oct key without `k`".to_string()));
+                    };
+                    KeyMaterial::Hs256(decode_jwk_part(&k)?)
+                }
+                _ => continue,
+            };
+            self.keys.push(JwtKey {
+                kid: jwk.kid,
+                material,
+            });
+        }
+        if self.keys.len() == before {
+            return Err(AuthError::Key(
+                "JWKS has no HS256 or RS256 signing keys".to_string(),
+            ));
+        }
+        Ok(self)
+    }
+
+    /// Check `token`'s signature and claims and return who it speaks for.
+    pub fn verify(&self, token: &str) -> Result<Principal, AuthError> {
+        let mut parts = token.split('.');
+        let (Some(encoded_header), Some(payload), Some(signature), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(AuthError::Malformed);
+        };
+        let header: TokenHeader = decode_segment(encoded_header)?;
+        let alg =
+            Algorithm::parse(&header.alg).ok_or(AuthError::UnsupportedAlgorithm(header.alg))?;
+        let signature = URL_SAFE_NO_PAD
+            .decode(signature)
+            .map_err(|_| AuthError::Malformed)?;
+        let signed = &token[..encoded_header.len() + 1 + payload.len()];
+
+        let mut candidates = self
+            .keys
+            .iter()
+            .filter(|k| {
+                k.algorithm() == alg
+                    && (header.kid.is_none() || k.kid.is_none() || k.kid == header.kid)
+            })
+            .peekable();
+        if candidates.peek().is_none() {
+            return Err(AuthError::UnknownKey);
+        }
+        if !candidates.any(|k| k.verifies(signed.as_bytes(), &signature)) {
+            return Err(AuthError::BadSignature);
+        }
+
+        let claims: Claims = decode_segment(payload)?;
+        let now = chrono::Utc::now().timestamp();
+        let leeway = i64::try_from(self.leeway_secs).unwrap_or(i64::MAX);
+        if claims.exp.saturating_add(leeway) < now {
+            return Err(AuthError::Expired);
+        }
+        if claims
+            .nbf
+            .is_some_and(|nbf| nbf.saturating_sub(leeway) > now)
+        {
+            return Err(AuthError::NotYetValid);
+        }
+        if self.issuer.is_some() && claims.iss != self.issuer {
+            return Err(AuthError::WrongIssuer);
+        }
+        if let Some(audience) = &self.audience {
+            if !claims.aud.is_some_and(|aud| aud.contains(audience)) {
+                return Err(AuthError::WrongAudience);
+            }
+        }
+        let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AuthError::InvalidSubject)?;
+        // Roles this service does not know are ignored rather than refused.
+        let roles = claims.roles.iter().filter_map(|r| Role::parse(r)).collect();
+        Ok(Principal { user_id, roles })
+    }
+}
+
+/// A fresh random API key: [`API_KEY_TAG`] and 32 random bytes, base64url-encoded.
+pub fn generate_api_key() -> String {
+    let mut secret = [0u8; 32];
+    rand::rngs::OsRng.fill_bytes(&mut secret);
+    format!("{}{}", API_KEY_TAG, URL_SAFE_NO_PAD.encode(secret))
+}
+
+/// Hex SHA-256 of an API key, as stored in `api_keys.key_hash`. Keys are random enough that
+/// a plain hash is safe to store without salting or stretching.
+pub fn hash_api_key(key: &str) -> String {
+    format!("{:x}", Sha256::digest(key.as_bytes()))
+}
+
+/// State for [`authenticate`]: bearer-token keys, and the service that looks up API keys.
+#[derive(Clone)]
+pub struct Authenticator {
+    config: Arc<AuthConfig>,
+    service: ReviewService,
+}
+
+impl Authenticator {
+    pub fn new(config: AuthConfig, service: ReviewService) -> Self {
+        Self {
+            config: Arc::new(config),
+            service,
+        }
+    }
+}
+
+/// Middleware: verify any `Authorization: Bearer` token and record its [`Principal`], and any
+/// `X-API-Key` and record its [`ApiClient`](crate::extractors::ApiClient). Requests without
+/// either pass through; handlers that need one reject them. Invalid credentials of either kind
+/// are refused with 401.
+pub async fn authenticate(
+    State(auth): State<Authenticator>,
+    mut req: Request,
+    next: Next,
+) -> Response {
+    if let Some(token) = bearer_token(req.headers()) {
+        match token.map(|t| auth.config.verify(t)) {
+            Some(Ok(principal)) => {
+                req.extensions_mut().insert(principal);
+            }
+            Some(Err(e)) => return e.into_response(),
+            None => return AuthError::Malformed.into_response(),
+        }
+    }
+    if let Some(key) = req.headers().get(API_KEY_HEADER) {
+        let client = match key.to_str() {
+            Ok(key) => auth.service.authenticate_api_key(key.trim()).await,
+            Err(_) => Ok(None),
+        };
+        match client {
+            Ok(Some(client)) => {
+                req.extensions_mut().insert(client);
+            }
+            Ok(None) => return AuthError::InvalidApiKey.into_response(),
+            Err(e) => return e.into_response(),
+        }
+    }
+    next.run(req).await
+}
+
+/// `None` without a bearer `Authorization` header; `Some(None)` if the header is not valid text.
+fn bearer_token(headers: &HeaderMap) -> Option<Option<&str>> {
+    let value = headers.get(header::AUTHORIZATION)?;
+    let Ok(value) = value.to_str() else {
+        return Some(None);
+    };
+    let (scheme, token) = value.split_once(' ')?;
+    scheme
+        .eq_ignore_ascii_case("bearer")
+        .then_some(Some(token.trim()))
+}
+
+fn decode_segment<T: serde::de::DeserializeOwned>(segment: &str) -> Result<T, AuthError> {
+    let bytes = URL_SAFE_NO_PAD
+        .decode(segment)
+        .map_err(|_| AuthError::Malformed)?;
+    serde_json::from_slice(&bytes).map_err(|_| AuthError::Malformed)
+}
+
+fn decode_jwk_part(value: &str) -> Result<Vec<u8>, AuthError> {
+    URL_SAFE_NO_PAD
+        .decode(value)
+        .map_err(|_| AuthError::Key("JWKS: key part is not base64url".to_string()))
+}
+
+#[derive(Deserialize)]
+struct TokenHeader {
+    alg: String,
+    kid: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Claims {
+    sub: String,
+    exp: i64,
+    nbf: Option<i64>,
+    iss: Option<String>,
+    aud: Option<Audience>,
+    #[serde(default)]
+    roles: Vec<String>,
+}
+
+/// `aud` may be a single string or a list.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Audience {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Audience {
+    fn contains(&self, audience: &str) -> bool {
+        match self {
+            Self::One(aud) => aud == audience,
+            Self::Many(auds) => auds.iter().any(|aud| aud == audience),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Jwks {
+    keys: Vec<Jwk>,
+}
+
+#[derive(Deserialize)]
+struct Jwk {
+    kty: String,
+    kid: Option<String>,
+    alg: Option<String>,
+    #[serde(rename = "use")]
+    key_use: Option<String>,
+    n: Option<String>,
+    e: Option<String>,
+    k: Option<String>,
+}
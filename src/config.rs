@@ -0,0 +1,120 @@
+//! Runtime configuration. Values come from environment variables, falling back to defaults.
+
+use std::str::FromStr;
+
+use crate::auth::AuthConfig;
+use crate::rate_limit::{RateLimit, RateLimits};
+use crate::service::{
+    RatingPrior, ScreeningConfig, ValidationLimits, DEFAULT_IDEMPOTENCY_TTL_HOURS,
+    DEFAULT_REPORT_THRESHOLD, DEFAULT_RETENTION_DAYS,
+};
+
+/// Service-wide settings, built once at startup.
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub validation: ValidationLimits,
+    pub prior: RatingPrior,
+    /// Publish reviews without waiting for a moderator.
+    pub auto_approve: bool,
+    pub screening: ScreeningConfig,
+    /// Reports after which a published review is hidden.
+    pub report_threshold: u32,
+    /// Days a soft-deleted review is kept before it is purged.
+    pub deleted_retention_days: u32,
+    /// Seconds between purge runs; 0 disables purging.
+    pub purge_interval_secs: u64,
+    /// Hours a `POST /reviews` response is kept for replay under its `Idempotency-Key`.
+    pub idempotency_ttl_hours: u32,
+    /// Per-caller limits for the throttled route groups.
+    pub rate_limits: RateLimits,
+    /// Bearer-token keys; loaded by [`AuthConfig::from_env`], not [`Config::from_env`].
+    pub auth: AuthConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            validation: ValidationLimits::default(),
+            prior: RatingPrior::default(),
+            auto_approve: false,
+            screening: ScreeningConfig::default(),
+            report_threshold: DEFAULT_REPORT_THRESHOLD,
+            deleted_retention_days: DEFAULT_RETENTION_DAYS,
+            purge_interval_secs: 3600,
+            idempotency_ttl_hours: DEFAULT_IDEMPOTENCY_TTL_HOURS,
+            rate_limits: RateLimits::default(),
+            auth: AuthConfig::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Read settings from the environment. Unset or unparsable variables keep their defaults.
+    ///
+    /// - `REVIEW_MAX_BODY_CHARS`: maximum review body length in characters
+    /// - `RATING_PRIOR_MEAN`, `RATING_PRIOR_WEIGHT`: Bayesian prior for weighted scores
+    /// - `REVIEW_AUTO_APPROVE`: `true` to skip the moderation queue
+    /// - `REVIEW_BANNED_WORDS`: comma-separated words that get a review rejected
+    /// - `REVIEW_MAX_CAPS_RATIO`: share of capital letters above which a review is flagged
+    /// - `REVIEW_MAX_REPEATED_CHARS`: longest run of one character before a review is flagged
+    /// - `REVIEW_REPORT_THRESHOLD`: reports after which a published review is hidden
+    /// - `REVIEW_RETENTION_DAYS`: days a deleted review is kept before it is purged
+    /// - `REVIEW_PURGE_INTERVAL_SECS`: seconds between purge runs, `0` to disable
+    /// - `IDEMPOTENCY_TTL_HOURS`: hours a response is kept for `Idempotency-Key` retries
+    /// - `RATE_LIMIT_REVIEWS`, `RATE_LIMIT_VOTES`: per-caller limits such as `10/1m`, or `off`
+    pub fn from_env() -> Self {
+        let mut config = Self::default();
+        if let Some(v) = env_parse("REVIEW_MAX_BODY_CHARS") {
+            config.validation.max_body_chars = v;
+        }
+        if let Some(v) = env_parse("RATING_PRIOR_MEAN") {
+            config.prior.mean = v;
+        }
+        if let Some(v) = env_parse("RATING_PRIOR_WEIGHT") {
+            config.prior.weight = v;
+        }
+        if let Some(v) = env_parse("REVIEW_AUTO_APPROVE") {
+            config.auto_approve = v;
+        }
+        if let Ok(v) = std::env::var("REVIEW_BANNED_WORDS") {
+            config.screening.banned_words = v.split(',').map(str::to_string).collect();
+        }
+        if let Some(v) = env_parse("REVIEW_MAX_CAPS_RATIO") {
+            config.screening.caps.max_ratio = v;
+        }
+        if let Some(v) = env_parse("REVIEW_MAX_REPEATED_CHARS") {
+            config.screening.repeated.max_run = v;
+        }
+        if let Some(v) = env_parse("REVIEW_REPORT_THRESHOLD") {
+            config.report_threshold = v;
+        }
+        if let Some(v) = env_parse("REVIEW_RETENTION_DAYS") {
+            config.deleted_retention_days = v;
+        }
+        if let Some(v) = env_parse("REVIEW_PURGE_INTERVAL_SECS") {
+            config.purge_interval_secs = v;
+        }
+        if let Some(v) = env_parse("IDEMPOTENCY_TTL_HOURS") {
+            config.idempotency_ttl_hours = v;
+        }
+        if let Some(v) = env_rate_limit("RATE_LIMIT_REVIEWS") {
+            config.rate_limits.reviews = v;
+        }
+        if let Some(v) = env_rate_limit("RATE_LIMIT_VOTES") {
+            config.rate_limits.votes = v;
+        }
+        config
+    }
+}
+
+fn env_parse<T: FromStr>(key: &str) -> Option<T> {
+    std::env::var(key).ok()?.parse().ok()
+}
+
+/// A rate limit such as `10/1m`, or `off` for `Some(None)`.
+fn env_rate_limit(key: &str) -> Option<Option<RateLimit>> {
+    match std::env::var(key).ok()?.trim() {
+        "off" => Some(None),
+        v => v.parse().ok().map(Some),
+    }
+}
@@ -0,0 +1,198 @@
+//! Service error type and its HTTP mapping. Errors render as RFC 7807 `application/problem+json`.
+
+use axum::{
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::error::ErrorKind;
+use utoipa::ToSchema;
+
+/// Failure modes of the service layer.
+#[derive(Debug, thiserror::Error)]
+pub enum ReviewError {
+    /// The named resource does not exist.
+    #[error("{0} not found")]
+    NotFound(&'static str),
+    /// The request is well-formed but violates a business rule or constraint.
+    /// `errors` lists individual field problems when they are known.
+    #[error("{detail}")]
+    Validation {
+        detail: String,
+        errors: Vec<FieldError>,
+    },
+    /// The request conflicts with existing state (e.g. a uniqueness constraint).
+    #[error("{0}")]
+    Conflict(String),
+    /// The caller may not act on this resource.
+    #[error("{0}")]
+    Forbidden(String),
+    /// Unexpected storage failure. Details are logged, not returned.
+    #[error("database error: {0}")]
+    Database(#[source] sqlx::Error),
+}
+
+impl ReviewError {
+    /// A validation failure not tied to specific fields.
+    pub fn validation(detail: impl Into<String>) -> Self {
+        Self::Validation {
+            detail: detail.into(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// A validation failure listing the offending fields.
+    pub fn invalid_fields(errors: Vec<FieldError>) -> Self {
+        Self::Validation {
+            detail: "Request has invalid fields".to_string(),
+            errors,
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::NotFound(_) => StatusCode::NOT_FOUND,
+            Self::Validation { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::Conflict(_) => StatusCode::CONFLICT,
+            Self::Forbidden(_) => StatusCode::FORBIDDEN,
+            Self::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// Classify driver errors by Postgres SQLSTATE so constraint violations surface as 4xx.
+/// Other backends fall back to the driver's portable error kind.
+impl From<sqlx::Error> for ReviewError {
+    fn from(e: sqlx::Error) -> Self {
+        let Some(db) = e.as_database_error() else {
+            return Self::Database(e);
+        };
+        let constraint = db.constraint().unwrap_or("unknown");
+        match db.code().as_deref() {
+            // unique_violation
+            Some("23505") => Self::Conflict(unique_violation_detail(constraint)),
+            // foreign_key_violation
+            Some("23503") => Self::Conflict(format!(
+                "Referenced row is missing or still in use ({})",
+                constraint
+            )),
+            // check_violation
+            Some("23514") => Self::validation(format!("Value violates constraint {}", constraint)),
+            // not_null_violation
+            Some("23502") => Self::validation("A required field is missing"),
+            // invalid_parameter_value, e.g. an unknown time zone name
+            Some("22023") => Self::validation(db.message().to_string()),
+            // numeric_value_out_of_range, string_data_right_truncation
+            Some("22003") | Some("22001") => Self::validation("Value out of range"),
+            _ => match db.kind() {
+                ErrorKind::UniqueViolation => Self::Conflict(unique_violation_detail(constraint)),
+                ErrorKind::ForeignKeyViolation => {
+                    Self::Conflict("Referenced row is missing or still in use".to_string())
+                }
+                ErrorKind::CheckViolation => Self::validation("Value violates a constraint"),
+                ErrorKind::NotNullViolation => Self::validation("A required field is missing"),
+                _ => Self::Database(e),
+            },
+        }
+    }
+}
+
+/// Conflict detail when a user reviews the same product twice.
+pub(crate) const DUPLICATE_REVIEW: &str = "This user has already reviewed this product";
+
+/// Conflict detail when a user reports the same review twice.
+pub(crate) const DUPLICATE_REPORT: &str = "This user has already reported this review";
+
+/// Conflict detail when a review is replied to twice.
+pub(crate) const DUPLICATE_REPLY: &str = "This review already has a reply";
+
+fn unique_violation_detail(constraint: &str) -> String {
+    match constraint {
+        "reviews_product_user_key" => DUPLICATE_REVIEW.to_string(),
+        "review_reports_review_reporter_key" => DUPLICATE_REPORT.to_string(),
+        "review_replies_pkey" => DUPLICATE_REPLY.to_string(),
+        other => format!("Duplicate value violates constraint {}", other),
+    }
+}
+
+/// One invalid input field, reported in the `errors` member of a 422 problem.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct FieldError {
+    /// JSON field name.
+    pub field: String,
+    /// Machine-readable reason, e.g. `out_of_range`, `too_long`, `blank`, `nil_uuid`.
+    pub code: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(
+        field: impl Into<String>,
+        code: impl Into<String>,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            field: field.into(),
+            code: code.into(),
+            message: message.into(),
+        }
+    }
+}
+
+/// RFC 7807 problem details body.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ProblemDetails {
+    /// Problem type URI; `about:blank` means the status code says it all.
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    /// Short summary; the HTTP reason phrase for `about:blank`.
+    pub title: String,
+    pub status: u16,
+    /// Human-readable explanation of this occurrence.
+    pub detail: String,
+    /// Field-level validation problems (extension member; 422 only).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+}
+
+impl ProblemDetails {
+    pub fn new(status: StatusCode, detail: impl Into<String>) -> Self {
+        Self {
+            problem_type: "about:blank".to_string(),
+            title: status.canonical_reason().unwrap_or("Error").to_string(),
+            status: status.as_u16(),
+            detail: detail.into(),
+            errors: Vec::new(),
+        }
+    }
+}
+
+impl IntoResponse for ProblemDetails {
+    fn into_response(self) -> Response {
+        let status = StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        (
+            status,
+            [(header::CONTENT_TYPE, "application/problem+json")],
+            Json(self),
+        )
+            .into_response()
+    }
+}
+
+impl IntoResponse for ReviewError {
+    fn into_response(self) -> Response {
+        let status = self.status();
+        let mut problem = match &self {
+            Self::Database(e) => {
+                tracing::error!(error = %e, "database error");
+                ProblemDetails::new(status, "Internal server error")
+            }
+            other => ProblemDetails::new(status, other.to_string()),
+        };
+        if let Self::Validation { errors, .. } = self {
+            problem.errors = errors;
+        }
+        problem.into_response()
+    }
+}
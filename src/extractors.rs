@@ -0,0 +1,277 @@
+//! Request extractors shared by handlers.
+
+use std::marker::PhantomData;
+
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{header, request::Parts, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use uuid::Uuid;
+
+use crate::auth::Role;
+use crate::error::{ProblemDetails, ReviewError};
+use crate::models::ApiScope;
+
+/// Header carrying a backend service's API key.
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// `WWW-Authenticate` challenge for a missing bearer token.
+pub const BEARER_CHALLENGE: &str = "Bearer";
+/// `WWW-Authenticate` challenge for a missing or refused [`API_KEY_HEADER`].
+pub const API_KEY_CHALLENGE: &str = "ApiKey header=\"X-API-Key\"";
+
+/// Header making a `POST /reviews` safe to retry.
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+/// The user making the request, taken from the verified bearer token's [`Principal`].
+/// Rejects like [`Principal`] without one, so API keys never act as a user.
+#[derive(Debug, Clone, Copy)]
+pub struct CallerId(pub Uuid);
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for CallerId {
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        Principal::from_request_parts(parts, state)
+            .await
+            .map(|p| CallerId(p.user_id))
+    }
+}
+
+/// The user a verified bearer token speaks for, recorded by [`crate::auth::authenticate`].
+/// Rejects with 401 without a bearer token, or 403 for a backend service with only an API key:
+/// user routes are not covered by any key scope.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Principal {
+    pub user_id: Uuid,
+    pub roles: Vec<Role>,
+}
+
+impl Principal {
+    /// Whether the principal holds `role`. Admins hold every role.
+    pub fn has_role(&self, role: Role) -> bool {
+        self.roles.iter().any(|r| *r == role || *r == Role::Admin)
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Principal {
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        if let Some(principal) = parts.extensions.get::<Principal>() {
+            return Ok(principal.clone());
+        }
+        if parts.extensions.get::<ApiClient>().is_some() {
+            return Err(keys_refused());
+        }
+        Err(unauthorized("Missing bearer token", &[BEARER_CHALLENGE]))
+    }
+}
+
+/// The backend service a valid [`API_KEY_HEADER`] belongs to, recorded by [`crate::auth::authenticate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiClient {
+    pub key_id: Uuid,
+    /// Name the key was issued under.
+    pub name: String,
+    pub scopes: Vec<ApiScope>,
+}
+
+impl ApiClient {
+    /// Fails with 403 unless the key was granted `scope`.
+    pub fn require(&self, scope: ApiScope) -> Result<(), ReviewError> {
+        if self.scopes.contains(&scope) {
+            Ok(())
+        } else {
+            Err(ReviewError::Forbidden(format!(
+                "API key lacks the `{}` scope",
+                scope.as_str()
+            )))
+        }
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for ApiClient {
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<ApiClient>()
+            .cloned()
+            .ok_or_else(|| unauthorized("Missing X-API-Key header", &[API_KEY_CHALLENGE]))
+    }
+}
+
+/// The request's [`IDEMPOTENCY_KEY_HEADER`], if it has one. The value is checked by the service.
+#[derive(Debug, Clone)]
+pub struct IdempotencyKey(pub Option<String>);
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for IdempotencyKey {
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let value = parts.headers.get(IDEMPOTENCY_KEY_HEADER);
+        Ok(IdempotencyKey(value.map(|v| {
+            String::from_utf8_lossy(v.as_bytes()).into_owned()
+        })))
+    }
+}
+
+/// Who is calling: a user with a bearer token, or else a backend service with an API key.
+/// Rejects with 401, challenging for either, when the request carries neither.
+#[derive(Debug, Clone)]
+pub enum Caller {
+    User(Principal),
+    Service(ApiClient),
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Caller {
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        if let Some(principal) = parts.extensions.get::<Principal>() {
+            return Ok(Caller::User(principal.clone()));
+        }
+        if let Some(client) = parts.extensions.get::<ApiClient>() {
+            return Ok(Caller::Service(client.clone()));
+        }
+        Err(unauthorized(
+            "Missing bearer token or X-API-Key header",
+            &[BEARER_CHALLENGE, API_KEY_CHALLENGE],
+        ))
+    }
+}
+
+/// Public read routes: open to anonymous callers and users, and to backend services whose
+/// API key has the `read` scope.
+#[derive(Debug, Clone, Copy)]
+pub struct ReadAccess;
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for ReadAccess {
+    type Rejection = ReviewError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        match parts.extensions.get::<ApiClient>() {
+            Some(client) if parts.extensions.get::<Principal>().is_none() => {
+                client.require(ApiScope::Read).map(|()| ReadAccess)
+            }
+            _ => Ok(ReadAccess),
+        }
+    }
+}
+
+/// Roles admitted by an [`Authorized`] guard, besides admins who are always admitted.
+pub trait RolePolicy {
+    const ROLES: &'static [Role];
+    /// API-key scope that admits a backend service to a [`Permitted`] guard, if any.
+    const SCOPE: Option<ApiScope> = None;
+}
+
+/// Moderation: moderators and admins.
+pub struct Moderators;
+
+impl RolePolicy for Moderators {
+    const ROLES: &'static [Role] = &[Role::Moderator];
+}
+
+/// Merchant replies: merchants and admins.
+pub struct Merchants;
+
+impl RolePolicy for Merchants {
+    const ROLES: &'static [Role] = &[Role::Merchant];
+}
+
+/// Analytics: merchants, moderators and admins.
+pub struct Analysts;
+
+impl RolePolicy for Analysts {
+    const ROLES: &'static [Role] = &[Role::Merchant, Role::Moderator];
+    const SCOPE: Option<ApiScope> = Some(ApiScope::Stats);
+}
+
+/// Operational maintenance: admins only.
+pub struct Admins;
+
+impl RolePolicy for Admins {
+    const ROLES: &'static [Role] = &[];
+}
+
+/// A [`Principal`] holding one of the roles `P` admits. Rejects with 401 without a bearer
+/// token and 403 without a matching role.
+#[derive(Debug, Clone)]
+pub struct Authorized<P>(pub Principal, pub PhantomData<P>);
+
+#[async_trait]
+impl<S: Send + Sync, P: RolePolicy> FromRequestParts<S> for Authorized<P> {
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let principal = Principal::from_request_parts(parts, state).await?;
+        admit::<P>(&principal).map_err(IntoResponse::into_response)?;
+        Ok(Authorized(principal, PhantomData))
+    }
+}
+
+/// Like [`Authorized`], but also admits a backend service whose API key has `P::SCOPE`.
+/// Rejects with 401 without credentials and 403 without a matching role or scope.
+#[derive(Debug, Clone)]
+pub struct Permitted<P>(pub Caller, pub PhantomData<P>);
+
+#[async_trait]
+impl<S: Send + Sync, P: RolePolicy> FromRequestParts<S> for Permitted<P> {
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let caller = Caller::from_request_parts(parts, state).await?;
+        match &caller {
+            Caller::User(principal) => {
+                admit::<P>(principal).map_err(IntoResponse::into_response)?
+            }
+            Caller::Service(client) => match P::SCOPE {
+                Some(scope) => client.require(scope).map_err(IntoResponse::into_response)?,
+                None => return Err(keys_refused()),
+            },
+        }
+        Ok(Permitted(caller, PhantomData))
+    }
+}
+
+/// 401 with a `WWW-Authenticate` header for each of `challenges`, as every auth extractor rejects.
+pub(crate) fn unauthorized(detail: &str, challenges: &[&'static str]) -> Response {
+    let mut response = ProblemDetails::new(StatusCode::UNAUTHORIZED, detail).into_response();
+    for challenge in challenges {
+        response.headers_mut().append(
+            header::WWW_AUTHENTICATE,
+            HeaderValue::from_static(challenge),
+        );
+    }
+    response
+}
+
+/// 403 for a backend service on a route no API key scope covers.
+fn keys_refused() -> Response {
+    ReviewError::Forbidden("API keys cannot use this route".to_string()).into_response()
+}
+
+/// 403 unless `principal` holds one of the roles `P` admits.
+fn admit<P: RolePolicy>(principal: &Principal) -> Result<(), ProblemDetails> {
+    if principal.has_role(Role::Admin) || P::ROLES.iter().any(|r| principal.has_role(*r)) {
+        return Ok(());
+    }
+    let roles: Vec<&str> = P::ROLES
+        .iter()
+        .chain([&Role::Admin])
+        .map(|r| r.as_str())
+        .collect();
+    let detail = format!("Requires one of the roles: {}", roles.join(", "));
+    Err(ProblemDetails::new(StatusCode::FORBIDDEN, detail))
+}
@@ -1,13 +1,26 @@
 //! HTTP handlers. Thin layer: extract input, call service, map to response.
 
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
 use uuid::Uuid;
 
-use crate::models::{CreateReview, DashboardStats, ReviewResponse};
+use crate::error::{FieldError, ProblemDetails, ReviewError};
+use crate::extractors::{
+    Admins, Analysts, Authorized, Caller, CallerId, IdempotencyKey, Merchants, Moderators,
+    Permitted, ReadAccess,
+};
+use crate::models::{
+    ApiScope, CastVote, CreateReport, CreateReview, DashboardStats, Idempotent, LeaderboardPage,
+    LeaderboardQuery, ListReviewsQuery, ModerationDecision, ModerationQueueQuery, NewReview,
+    ProductStats, PurgeReport, ReconcileQuery, ReconcileReport, ReplyInput, ReplyPage,
+    ReplyQueueQuery, ReplyResponse, ReportListQuery, ReportPage, ReportResponse, ReviewFilter,
+    ReviewPage, ReviewResponse, RevisionResponse, SearchPage, SearchQuery, Timeseries,
+    TimeseriesQuery, UpdateReview, UpsertReview,
+};
 use crate::service::ReviewService;
 
 /// Health check endpoint.
@@ -24,71 +37,777 @@ pub async fn health() -> Json<serde_json::Value> {
     }))
 }
 
-/// List all reviews.
+/// List reviews matching optional filters, one page at a time.
 #[utoipa::path(
     get,
     path = "/reviews",
     tag = "Reviews",
+    security((), ("api_key" = [])),
+    params(ReviewFilter, ListReviewsQuery),
     responses(
-        (status = 200, description = "List of reviews", body = [ReviewResponse]),
-        (status = 500, description = "Internal server error")
+        (status = 200, description = "Page of reviews", body = ReviewPage),
+        (status = 400, description = "Malformed query parameter or cursor"),
+        (status = 403, description = "API key lacks the `read` scope", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json")
     )
 )]
-pub async fn list_reviews(State(service): State<ReviewService>) -> Result<Json<Vec<ReviewResponse>>, (StatusCode, String)> {
-    let list = service.list_reviews().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
-    Ok(Json(list))
+pub async fn list_reviews(
+    State(service): State<ReviewService>,
+    _: ReadAccess,
+    Query(filter): Query<ReviewFilter>,
+    Query(query): Query<ListReviewsQuery>,
+) -> Result<Json<ReviewPage>, ReviewError> {
+    let page = service.list_reviews(filter, query).await?;
+    Ok(Json(page))
 }
 
-/// Get a review by ID.
+/// Search review text. Words must all match; `"quoted phrases"` and `prefix*` terms are supported.
+#[utoipa::path(
+    get,
+    path = "/reviews/search",
+    tag = "Reviews",
+    security((), ("api_key" = [])),
+    params(SearchQuery),
+    responses(
+        (status = 200, description = "Page of matching reviews, best match first", body = SearchPage),
+        (status = 400, description = "Missing, empty or malformed query parameter"),
+        (status = 403, description = "API key lacks the `read` scope", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json")
+    )
+)]
+pub async fn search_reviews(
+    State(service): State<ReviewService>,
+    _: ReadAccess,
+    Query(query): Query<SearchQuery>,
+) -> Result<Json<SearchPage>, ReviewError> {
+    let page = service.search_reviews(query).await?;
+    Ok(Json(page))
+}
+
+/// Get a review by ID. Reviews that are not approved are visible only to their author.
 #[utoipa::path(
     get,
     path = "/reviews/{id}",
     tag = "Reviews",
-    params(("id" = Uuid, Path, description = "Review UUID")),
+    security((), ("bearer_auth" = []), ("api_key" = [])),
+    params(
+        ("id" = Uuid, Path, description = "Review UUID")
+    ),
     responses(
         (status = 200, description = "Review found", body = ReviewResponse),
-        (status = 404, description = "Review not found"),
-        (status = 500, description = "Internal server error")
+        (status = 403, description = "API key lacks the `read` scope", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Review not found or not visible to the caller", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json")
     )
 )]
-pub async fn get_review(State(service): State<ReviewService>, Path(id): Path<Uuid>) -> Result<Json<ReviewResponse>, (StatusCode, String)> {
-    let r = service
-        .get_review(id)
-        .await
-        .map_err(|e| if e == "Not found" { (StatusCode::NOT_FOUND, e) } else { (StatusCode::INTERNAL_SERVER_ERROR, e) })?;
+pub async fn get_review(
+    State(service): State<ReviewService>,
+    _: ReadAccess,
+    caller: Option<CallerId>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ReviewResponse>, ReviewError> {
+    let r = service.get_review(id, caller.map(|CallerId(c)| c)).await?;
     Ok(Json(r))
 }
 
-/// Create a new review.
+/// Earlier versions of a review, newest first. The current version is the review itself.
+#[utoipa::path(
+    get,
+    path = "/reviews/{id}/revisions",
+    tag = "Reviews",
+    security((), ("bearer_auth" = []), ("api_key" = [])),
+    params(
+        ("id" = Uuid, Path, description = "Review UUID")
+    ),
+    responses(
+        (status = 200, description = "Superseded versions", body = [RevisionResponse]),
+        (status = 403, description = "API key lacks the `read` scope", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Review not found or not visible to the caller", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json")
+    )
+)]
+pub async fn list_revisions(
+    State(service): State<ReviewService>,
+    _: ReadAccess,
+    caller: Option<CallerId>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<RevisionResponse>>, ReviewError> {
+    let revisions = service
+        .list_revisions(id, caller.map(|CallerId(c)| c))
+        .await?;
+    Ok(Json(revisions))
+}
+
+/// Create a new review by the bearer token's user, or by `user_id` for a backend service with an API key.
+/// With an `Idempotency-Key`, a retry of the same request returns the original response.
 #[utoipa::path(
     post,
     path = "/reviews",
     tag = "Reviews",
-    request_body = CreateReview,
+    security(("bearer_auth" = []), ("api_key" = [])),
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Client-chosen key, unique per request, that makes retries safe")
+    ),
+    request_body = NewReview,
+    responses(
+        (status = 201, description = "Review created, or the stored response replayed (`Idempotent-Replayed: true`)", body = ReviewResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "API key lacks the `write` scope", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "User already reviewed this product, or a request with this Idempotency-Key is still in progress", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid fields, listed in `errors`; includes an Idempotency-Key reused for a different request", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 429, description = "Rate limit exceeded; see `Retry-After`", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json")
+    )
+)]
+pub async fn create_review(
+    State(service): State<ReviewService>,
+    caller: Caller,
+    IdempotencyKey(idempotency_key): IdempotencyKey,
+    Json(body): Json<NewReview>,
+) -> Result<Response, ReviewError> {
+    // Idempotency keys belong to whoever sent them: the user, or the backend service.
+    let (author, scope) = match caller {
+        Caller::User(principal) => (principal.user_id, format!("user:{}", principal.user_id)),
+        Caller::Service(client) => {
+            client.require(ApiScope::Write)?;
+            let author = body.user_id.ok_or_else(|| {
+                ReviewError::invalid_fields(vec![FieldError::new(
+                    "user_id",
+                    "missing",
+                    "is required when posting with an API key",
+                )])
+            })?;
+            (author, format!("key:{}", client.key_id))
+        }
+    };
+    let body = CreateReview {
+        product_id: body.product_id,
+        user_id: author,
+        rating: body.rating,
+        body: body.body,
+    };
+    let Some(key) = idempotency_key else {
+        let r = service.create_review(body).await?;
+        return Ok((StatusCode::CREATED, Json(r)).into_response());
+    };
+    match service.create_review_idempotent(&scope, &key, body).await? {
+        Idempotent::Fresh(r) => Ok((StatusCode::CREATED, Json(r)).into_response()),
+        Idempotent::Replayed { status, body } => {
+            let status = StatusCode::from_u16(status).unwrap_or(StatusCode::OK);
+            let headers = [
+                (header::CONTENT_TYPE, "application/json"),
+                (
+                    header::HeaderName::from_static("idempotent-replayed"),
+                    "true",
+                ),
+            ];
+            Ok((status, headers, body).into_response())
+        }
+    }
+}
+
+/// Create or replace the caller's review of a product.
+#[utoipa::path(
+    put,
+    path = "/products/{product_id}/reviews/mine",
+    tag = "Reviews",
+    security(("bearer_auth" = [])),
+    params(
+        ("product_id" = Uuid, Path, description = "Product UUID")
+    ),
+    request_body = UpsertReview,
     responses(
+        (status = 200, description = "Existing review replaced", body = ReviewResponse),
         (status = 201, description = "Review created", body = ReviewResponse),
-        (status = 500, description = "Internal server error")
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "API keys cannot use this route", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid fields, listed in `errors`", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 429, description = "Rate limit exceeded; see `Retry-After`", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json")
+    )
+)]
+pub async fn upsert_my_review(
+    State(service): State<ReviewService>,
+    CallerId(caller): CallerId,
+    Path(product_id): Path<Uuid>,
+    Json(body): Json<UpsertReview>,
+) -> Result<(StatusCode, Json<ReviewResponse>), ReviewError> {
+    let (r, created) = service.upsert_review(product_id, caller, body).await?;
+    let status = if created {
+        StatusCode::CREATED
+    } else {
+        StatusCode::OK
+    };
+    Ok((status, Json(r)))
+}
+
+/// Partially update a review. Only its author may do this.
+#[utoipa::path(
+    patch,
+    path = "/reviews/{id}",
+    tag = "Reviews",
+    security(("bearer_auth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "Review UUID")
+    ),
+    request_body = UpdateReview,
+    responses(
+        (status = 200, description = "Review updated", body = ReviewResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Caller is not the review's author, or used an API key", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Review not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid fields, listed in `errors`", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json")
+    )
+)]
+pub async fn update_review(
+    State(service): State<ReviewService>,
+    CallerId(caller): CallerId,
+    Path(id): Path<Uuid>,
+    Json(body): Json<UpdateReview>,
+) -> Result<Json<ReviewResponse>, ReviewError> {
+    let r = service.update_review(id, caller, body).await?;
+    Ok(Json(r))
+}
+
+/// Delete a review. Only its author may do this.
+#[utoipa::path(
+    delete,
+    path = "/reviews/{id}",
+    tag = "Reviews",
+    security(("bearer_auth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "Review UUID")
+    ),
+    responses(
+        (status = 204, description = "Review deleted"),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Caller is not the review's author, or used an API key", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Review not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json")
+    )
+)]
+pub async fn delete_review(
+    State(service): State<ReviewService>,
+    CallerId(caller): CallerId,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, ReviewError> {
+    service.delete_review(id, caller).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Vote a published review helpful or unhelpful. Voting again replaces the caller's vote.
+#[utoipa::path(
+    post,
+    path = "/reviews/{id}/votes",
+    tag = "Reviews",
+    security(("bearer_auth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "Review UUID")
+    ),
+    request_body = CastVote,
+    responses(
+        (status = 200, description = "Vote recorded; the review with updated counts", body = ReviewResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Caller wrote the review, or used an API key", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Review not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 429, description = "Rate limit exceeded; see `Retry-After`", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json")
+    )
+)]
+pub async fn vote_review(
+    State(service): State<ReviewService>,
+    CallerId(caller): CallerId,
+    Path(id): Path<Uuid>,
+    Json(body): Json<CastVote>,
+) -> Result<Json<ReviewResponse>, ReviewError> {
+    let r = service.vote_review(id, caller, body).await?;
+    Ok(Json(r))
+}
+
+/// Withdraw the caller's vote on a review. Succeeds whether or not a vote existed.
+#[utoipa::path(
+    delete,
+    path = "/reviews/{id}/votes/mine",
+    tag = "Reviews",
+    security(("bearer_auth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "Review UUID")
+    ),
+    responses(
+        (status = 204, description = "Vote withdrawn"),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "API keys cannot use this route", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Review not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 429, description = "Rate limit exceeded; see `Retry-After`", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json")
+    )
+)]
+pub async fn retract_vote(
+    State(service): State<ReviewService>,
+    CallerId(caller): CallerId,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, ReviewError> {
+    service.retract_vote(id, caller).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Report a published review for moderation. Each user may report a review once.
+#[utoipa::path(
+    post,
+    path = "/reviews/{id}/reports",
+    tag = "Reviews",
+    security(("bearer_auth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "Review UUID")
+    ),
+    request_body = CreateReport,
+    responses(
+        (status = 201, description = "Report recorded", body = ReportResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "API keys cannot use this route", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Review not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "Caller already reported this review", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid fields, listed in `errors`", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json")
+    )
+)]
+pub async fn report_review(
+    State(service): State<ReviewService>,
+    CallerId(caller): CallerId,
+    Path(id): Path<Uuid>,
+    Json(body): Json<CreateReport>,
+) -> Result<(StatusCode, Json<ReportResponse>), ReviewError> {
+    let r = service.report_review(id, caller, body).await?;
+    Ok((StatusCode::CREATED, Json(r)))
+}
+
+/// Reply publicly to a published review as a merchant. A review takes one reply.
+#[utoipa::path(
+    post,
+    path = "/reviews/{id}/reply",
+    tag = "Reviews",
+    security(("bearer_auth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "Review UUID")
+    ),
+    request_body = ReplyInput,
+    responses(
+        (status = 201, description = "Reply created, pending moderation unless auto-approved", body = ReplyResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Caller is not a merchant, wrote the review, or used an API key", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Review not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "Review already has a reply", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid fields, listed in `errors`", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json")
     )
 )]
-pub async fn create_review(State(service): State<ReviewService>, Json(body): Json<CreateReview>) -> Result<(StatusCode, Json<ReviewResponse>), (StatusCode, String)> {
-    let r = service.create_review(body).await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+pub async fn reply_to_review(
+    State(service): State<ReviewService>,
+    Authorized(merchant, _): Authorized<Merchants>,
+    Path(id): Path<Uuid>,
+    Json(body): Json<ReplyInput>,
+) -> Result<(StatusCode, Json<ReplyResponse>), ReviewError> {
+    let r = service.reply_to_review(id, merchant.user_id, body).await?;
     Ok((StatusCode::CREATED, Json(r)))
 }
 
+/// Edit a reply. Only the merchant who wrote it may do this; the edit is moderated again.
+#[utoipa::path(
+    patch,
+    path = "/reviews/{id}/reply",
+    tag = "Reviews",
+    security(("bearer_auth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "Review UUID")
+    ),
+    request_body = ReplyInput,
+    responses(
+        (status = 200, description = "Reply updated", body = ReplyResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Caller is not a merchant, did not write the reply, or used an API key", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Reply not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid fields, listed in `errors`", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json")
+    )
+)]
+pub async fn update_reply(
+    State(service): State<ReviewService>,
+    Authorized(merchant, _): Authorized<Merchants>,
+    Path(id): Path<Uuid>,
+    Json(body): Json<ReplyInput>,
+) -> Result<Json<ReplyResponse>, ReviewError> {
+    let r = service.update_reply(id, merchant.user_id, body).await?;
+    Ok(Json(r))
+}
+
+/// Delete a reply. Only the merchant who wrote it may do this.
+#[utoipa::path(
+    delete,
+    path = "/reviews/{id}/reply",
+    tag = "Reviews",
+    security(("bearer_auth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "Review UUID")
+    ),
+    responses(
+        (status = 204, description = "Reply deleted"),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Caller is not a merchant, did not write the reply, or used an API key", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Reply not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json")
+    )
+)]
+pub async fn delete_reply(
+    State(service): State<ReviewService>,
+    Authorized(merchant, _): Authorized<Merchants>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, ReviewError> {
+    service.delete_reply(id, merchant.user_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
 /// Get dashboard statistics (total reviews, average rating).
 #[utoipa::path(
     get,
     path = "/stats/dashboard",
     tag = "Stats",
+    security(("bearer_auth" = []), ("api_key" = [])),
     responses(
         (status = 200, description = "Dashboard stats", body = DashboardStats),
-        (status = 500, description = "Internal server error")
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Caller is not a merchant, moderator or admin, or API key lacks the `stats` scope", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json")
     )
 )]
-pub async fn dashboard_stats(State(service): State<ReviewService>) -> Result<Json<DashboardStats>, (StatusCode, String)> {
-    let stats = service
-        .get_dashboard_stats()
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+pub async fn dashboard_stats(
+    State(service): State<ReviewService>,
+    _: Permitted<Analysts>,
+) -> Result<Json<DashboardStats>, ReviewError> {
+    let stats = service.get_dashboard_stats().await?;
     Ok(Json(stats))
 }
+
+/// Review volume and average rating over time, bucketed by day, week or month.
+#[utoipa::path(
+    get,
+    path = "/stats/timeseries",
+    tag = "Stats",
+    security(("bearer_auth" = []), ("api_key" = [])),
+    params(TimeseriesQuery),
+    responses(
+        (status = 200, description = "Zero-filled time series", body = Timeseries),
+        (status = 400, description = "Malformed query parameter"),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Caller is not a merchant, moderator or admin, or API key lacks the `stats` scope", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid range or time zone", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json")
+    )
+)]
+pub async fn timeseries(
+    State(service): State<ReviewService>,
+    _: Permitted<Analysts>,
+    Query(query): Query<TimeseriesQuery>,
+) -> Result<Json<Timeseries>, ReviewError> {
+    let series = service.get_timeseries(query).await?;
+    Ok(Json(series))
+}
+
+/// Rank products by Bayesian score or review volume.
+#[utoipa::path(
+    get,
+    path = "/stats/products/top",
+    tag = "Stats",
+    security(("bearer_auth" = []), ("api_key" = [])),
+    params(LeaderboardQuery),
+    responses(
+        (status = 200, description = "Page of ranked products", body = LeaderboardPage),
+        (status = 400, description = "Malformed query parameter or window"),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Caller is not a merchant, moderator or admin, or API key lacks the `stats` scope", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json")
+    )
+)]
+pub async fn top_products(
+    State(service): State<ReviewService>,
+    _: Permitted<Analysts>,
+    Query(query): Query<LeaderboardQuery>,
+) -> Result<Json<LeaderboardPage>, ReviewError> {
+    let page = service.get_leaderboard(query).await?;
+    Ok(Json(page))
+}
+
+/// Get the rating summary for one product.
+#[utoipa::path(
+    get,
+    path = "/products/{product_id}/stats",
+    tag = "Stats",
+    security((), ("api_key" = [])),
+    params(("product_id" = Uuid, Path, description = "Product UUID")),
+    responses(
+        (status = 200, description = "Product rating summary", body = ProductStats),
+        (status = 403, description = "API key lacks the `read` scope", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json")
+    )
+)]
+pub async fn product_stats(
+    State(service): State<ReviewService>,
+    _: ReadAccess,
+    Path(product_id): Path<Uuid>,
+) -> Result<Json<ProductStats>, ReviewError> {
+    let stats = service.get_product_stats(product_id).await?;
+    Ok(Json(stats))
+}
+
+/// Reviews awaiting moderation, oldest first.
+#[utoipa::path(
+    get,
+    path = "/moderation/queue",
+    tag = "Moderation",
+    security(("bearer_auth" = [])),
+    params(ModerationQueueQuery),
+    responses(
+        (status = 200, description = "Page of pending reviews", body = ReviewPage),
+        (status = 400, description = "Malformed query parameter or cursor"),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Caller is not a moderator", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json")
+    )
+)]
+pub async fn moderation_queue(
+    State(service): State<ReviewService>,
+    _: Authorized<Moderators>,
+    Query(query): Query<ModerationQueueQuery>,
+) -> Result<Json<ReviewPage>, ReviewError> {
+    let page = service.moderation_queue(query).await?;
+    Ok(Json(page))
+}
+
+/// Approve a review, publishing it and counting it in stats.
+#[utoipa::path(
+    post,
+    path = "/moderation/reviews/{id}/approve",
+    tag = "Moderation",
+    security(("bearer_auth" = [])),
+    params(("id" = Uuid, Path, description = "Review UUID")),
+    request_body = ModerationDecision,
+    responses(
+        (status = 200, description = "Review approved", body = ReviewResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Caller is not a moderator", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Review not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json")
+    )
+)]
+pub async fn approve_review(
+    State(service): State<ReviewService>,
+    Authorized(moderator, _): Authorized<Moderators>,
+    Path(id): Path<Uuid>,
+    body: Option<Json<ModerationDecision>>,
+) -> Result<Json<ReviewResponse>, ReviewError> {
+    let decision = body.map(|Json(d)| d).unwrap_or_default();
+    let r = service
+        .approve_review(id, moderator.user_id, decision)
+        .await?;
+    Ok(Json(r))
+}
+
+/// Reject a review with a reason, hiding it from listings and stats.
+#[utoipa::path(
+    post,
+    path = "/moderation/reviews/{id}/reject",
+    tag = "Moderation",
+    security(("bearer_auth" = [])),
+    params(("id" = Uuid, Path, description = "Review UUID")),
+    request_body = ModerationDecision,
+    responses(
+        (status = 200, description = "Review rejected", body = ReviewResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Caller is not a moderator", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Review not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Missing reason", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json")
+    )
+)]
+pub async fn reject_review(
+    State(service): State<ReviewService>,
+    Authorized(moderator, _): Authorized<Moderators>,
+    Path(id): Path<Uuid>,
+    Json(body): Json<ModerationDecision>,
+) -> Result<Json<ReviewResponse>, ReviewError> {
+    let r = service.reject_review(id, moderator.user_id, body).await?;
+    Ok(Json(r))
+}
+
+/// Reports against reviews, newest first.
+#[utoipa::path(
+    get,
+    path = "/moderation/reports",
+    tag = "Moderation",
+    security(("bearer_auth" = [])),
+    params(ReportListQuery),
+    responses(
+        (status = 200, description = "Page of reports", body = ReportPage),
+        (status = 400, description = "Malformed query parameter"),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Caller is not a moderator", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json")
+    )
+)]
+pub async fn list_reports(
+    State(service): State<ReviewService>,
+    _: Authorized<Moderators>,
+    Query(query): Query<ReportListQuery>,
+) -> Result<Json<ReportPage>, ReviewError> {
+    let page = service.list_reports(query).await?;
+    Ok(Json(page))
+}
+
+/// Merchant replies awaiting moderation, oldest first.
+#[utoipa::path(
+    get,
+    path = "/moderation/replies",
+    tag = "Moderation",
+    security(("bearer_auth" = [])),
+    params(ReplyQueueQuery),
+    responses(
+        (status = 200, description = "Page of replies", body = ReplyPage),
+        (status = 400, description = "Malformed query parameter"),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Caller is not a moderator", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json")
+    )
+)]
+pub async fn reply_queue(
+    State(service): State<ReviewService>,
+    _: Authorized<Moderators>,
+    Query(query): Query<ReplyQueueQuery>,
+) -> Result<Json<ReplyPage>, ReviewError> {
+    let page = service.reply_queue(query).await?;
+    Ok(Json(page))
+}
+
+/// Approve a merchant reply, publishing it under its review.
+#[utoipa::path(
+    post,
+    path = "/moderation/reviews/{id}/reply/approve",
+    tag = "Moderation",
+    security(("bearer_auth" = [])),
+    params(("id" = Uuid, Path, description = "Review UUID")),
+    request_body = ModerationDecision,
+    responses(
+        (status = 200, description = "Reply approved", body = ReplyResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Caller is not a moderator", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Reply not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json")
+    )
+)]
+pub async fn approve_reply(
+    State(service): State<ReviewService>,
+    Authorized(moderator, _): Authorized<Moderators>,
+    Path(id): Path<Uuid>,
+    body: Option<Json<ModerationDecision>>,
+) -> Result<Json<ReplyResponse>, ReviewError> {
+    let decision = body.map(|Json(d)| d).unwrap_or_default();
+    let r = service
+        .approve_reply(id, moderator.user_id, decision)
+        .await?;
+    Ok(Json(r))
+}
+
+/// Reject a merchant reply with a reason, keeping it off its review.
+#[utoipa::path(
+    post,
+    path = "/moderation/reviews/{id}/reply/reject",
+    tag = "Moderation",
+    security(("bearer_auth" = [])),
+    params(("id" = Uuid, Path, description = "Review UUID")),
+    request_body = ModerationDecision,
+    responses(
+        (status = 200, description = "Reply rejected", body = ReplyResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Caller is not a moderator", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Reply not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Missing reason", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json")
+    )
+)]
+pub async fn reject_reply(
+    State(service): State<ReviewService>,
+    Authorized(moderator, _): Authorized<Moderators>,
+    Path(id): Path<Uuid>,
+    Json(body): Json<ModerationDecision>,
+) -> Result<Json<ReplyResponse>, ReviewError> {
+    let r = service.reject_reply(id, moderator.user_id, body).await?;
+    Ok(Json(r))
+}
+
+/// Recompute per-product rating aggregates from reviews and report any drift.
+#[utoipa::path(
+    post,
+    path = "/admin/aggregates/reconcile",
+    tag = "Admin",
+    security(("bearer_auth" = [])),
+    params(ReconcileQuery),
+    responses(
+        (status = 200, description = "Reconcile report", body = ReconcileReport),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Caller is not an admin", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json")
+    )
+)]
+pub async fn reconcile_aggregates(
+    State(service): State<ReviewService>,
+    _: Authorized<Admins>,
+    Query(query): Query<ReconcileQuery>,
+) -> Result<Json<ReconcileReport>, ReviewError> {
+    let report = service.reconcile_aggregates(query.dry_run).await?;
+    Ok(Json(report))
+}
+
+/// Restore a soft-deleted review.
+#[utoipa::path(
+    post,
+    path = "/reviews/{id}/restore",
+    tag = "Admin",
+    security(("bearer_auth" = [])),
+    params(("id" = Uuid, Path, description = "Review UUID")),
+    responses(
+        (status = 200, description = "Review restored", body = ReviewResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Caller is not an admin", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "No deleted review with this id", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "The author has reviewed the product again since", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json")
+    )
+)]
+pub async fn restore_review(
+    State(service): State<ReviewService>,
+    _: Authorized<Admins>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ReviewResponse>, ReviewError> {
+    let r = service.restore_review(id).await?;
+    Ok(Json(r))
+}
+
+/// Permanently remove reviews deleted longer ago than the retention period.
+#[utoipa::path(
+    post,
+    path = "/admin/reviews/purge",
+    tag = "Admin",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Purge report", body = PurgeReport),
+        (status = 401, description = "Missing or invalid credentials", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Caller is not an admin", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json")
+    )
+)]
+pub async fn purge_deleted(
+    State(service): State<ReviewService>,
+    _: Authorized<Admins>,
+) -> Result<Json<PurgeReport>, ReviewError> {
+    let report = service.purge_deleted().await?;
+    Ok(Json(report))
+}
@@ -1,13 +1,22 @@
 //! Library entry point for the review service. Exposes the app router for testing and reuse.
 
-use axum::Router;
+use std::sync::Arc;
+
+use axum::{middleware, Router};
 use sqlx::PgPool;
 use tower_http::cors::CorsLayer;
-use utoipa::OpenApi;
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
 use utoipa_swagger_ui::SwaggerUi;
 
+pub mod auth;
+pub mod config;
+pub mod error;
+pub mod extractors;
 pub mod handlers;
 pub mod models;
+pub mod pagination;
+pub mod rate_limit;
 pub mod repository;
 pub mod routes;
 pub mod service;
@@ -17,15 +26,73 @@ pub mod service;
     paths(
         handlers::health,
         handlers::list_reviews,
+        handlers::search_reviews,
         handlers::get_review,
+        handlers::list_revisions,
         handlers::create_review,
+        handlers::upsert_my_review,
+        handlers::update_review,
+        handlers::delete_review,
+        handlers::vote_review,
+        handlers::retract_vote,
+        handlers::report_review,
+        handlers::reply_to_review,
+        handlers::update_reply,
+        handlers::delete_reply,
         handlers::dashboard_stats,
+        handlers::timeseries,
+        handlers::top_products,
+        handlers::product_stats,
+        handlers::reconcile_aggregates,
+        handlers::restore_review,
+        handlers::purge_deleted,
+        handlers::moderation_queue,
+        handlers::approve_review,
+        handlers::reject_review,
+        handlers::list_reports,
+        handlers::reply_queue,
+        handlers::approve_reply,
+        handlers::reject_reply,
     ),
     components(schemas(
-        crate::models::CreateReview,
+        crate::models::NewReview,
+        crate::models::UpsertReview,
+        crate::models::UpdateReview,
         crate::models::ReviewResponse,
+        crate::models::ReviewStatus,
+        crate::models::RevisionResponse,
+        crate::models::ModerationInfo,
+        crate::models::ModerationDecision,
+        crate::models::VoteKind,
+        crate::models::CastVote,
+        crate::models::ReportReason,
+        crate::models::CreateReport,
+        crate::models::ReportResponse,
+        crate::models::ReportPage,
+        crate::models::ReplyInput,
+        crate::models::ReplyResponse,
+        crate::models::ReplyPage,
+        crate::models::ReviewPage,
+        crate::models::ReviewSort,
+        crate::models::SearchHit,
+        crate::models::SearchPage,
         crate::models::DashboardStats,
+        crate::models::ProductStats,
+        crate::models::StarHistogram,
+        crate::models::TimeBucket,
+        crate::models::TimeseriesPoint,
+        crate::models::Timeseries,
+        crate::models::LeaderboardOrder,
+        crate::models::ProductRanking,
+        crate::models::LeaderboardPage,
+        crate::models::AggregateCounts,
+        crate::models::AggregateDrift,
+        crate::models::ReconcileReport,
+        crate::models::PurgeReport,
+        crate::error::ProblemDetails,
+        crate::error::FieldError,
     )),
+    modifiers(&SecurityAddon),
     info(
         title = "My EX Review Service API",
         version = "1.0.0",
@@ -35,16 +102,70 @@ pub mod service;
         (name = "Health", description = "Health check"),
         (name = "Reviews", description = "Review CRUD"),
         (name = "Stats", description = "Dashboard statistics"),
+        (name = "Moderation", description = "Review moderation queue and decisions"),
+        (name = "Admin", description = "Operational maintenance"),
     )
 )]
 struct ApiDoc;
 
-/// Build the application router with the given database pool.
-/// Used by the binary and by integration tests.
+/// Declares the `bearer_auth` and `api_key` schemes referenced by each route's `security`.
+struct SecurityAddon;
+
+impl Modify for SecurityAddon {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer_auth",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .build(),
+            ),
+        );
+        components.add_security_scheme(
+            "api_key",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("X-API-Key"))),
+        );
+    }
+}
+
+/// Build the application router with the given database pool and default configuration.
+/// Used by integration tests.
 pub fn app(pool: PgPool) -> Router<()> {
-    let review_service = service::ReviewService::new(pool);
-    routes::api_routes()
-        .merge(Router::<service::ReviewService>::from(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", ApiDoc::openapi())))
+    app_with_config(pool, config::Config::default())
+}
+
+/// Build the application router with explicit configuration. Used by the binary.
+pub fn app_with_config(pool: PgPool, config: config::Config) -> Router<()> {
+    app_with_store(Arc::new(repository::ReviewRepository::new(pool)), config)
+}
+
+/// Build the application router over any review store, e.g. the in-memory one for tests
+/// that should run without a database.
+pub fn app_with_store(
+    store: Arc<dyn repository::ReviewStore>,
+    config: config::Config,
+) -> Router<()> {
+    let review_service = service::ReviewService::with_store(store)
+        .with_limits(config.validation)
+        .with_prior(config.prior)
+        .with_auto_approve(config.auto_approve)
+        .with_screening(service::ScreeningChain::from_config(&config.screening))
+        .with_report_threshold(config.report_threshold)
+        .with_deleted_retention(chrono::Duration::days(config.deleted_retention_days.into()))
+        .with_idempotency_ttl(chrono::Duration::hours(config.idempotency_ttl_hours.into()));
+    let authenticator = auth::Authenticator::new(config.auth, review_service.clone());
+    let rate_limit_store: Arc<dyn rate_limit::RateLimitStore> =
+        Arc::new(rate_limit::InMemoryRateLimitStore::new());
+    routes::api_routes(&config.rate_limits, rate_limit_store)
+        .merge(Router::<service::ReviewService>::from(
+            SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", ApiDoc::openapi()),
+        ))
+        .layer(middleware::from_fn_with_state(
+            authenticator,
+            auth::authenticate,
+        ))
         .layer(CorsLayer::permissive())
         .with_state(review_service)
 }
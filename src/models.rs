@@ -1,20 +1,123 @@
-use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
 use sqlx::FromRow;
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
-#[derive(Debug, FromRow)]
+use crate::pagination::Cursor;
+
+#[derive(Debug, Clone, FromRow)]
 pub struct Review {
     pub id: Uuid,
     pub product_id: Uuid,
     pub user_id: Uuid,
     pub rating: i32,
     pub body: Option<String>,
-    pub created_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    #[sqlx(try_from = "String")]
+    pub status: ReviewStatus,
+    pub moderation_reason: Option<String>,
+    pub moderated_by: Option<Uuid>,
+    pub moderated_at: Option<DateTime<Utc>>,
+    pub helpful_count: i32,
+    pub unhelpful_count: i32,
+    /// Wilson lower bound of the helpful share; see [`wilson_lower_bound`](crate::service::wilson_lower_bound).
+    pub helpful_score: f64,
+    /// Current version, from 1. Earlier versions are kept as [`ReviewRevision`]s.
+    pub revision: i32,
+    /// When the rating or body last changed; `None` if never edited.
+    pub edited_at: Option<DateTime<Utc>>,
+}
+
+/// Moderation state of a review. Only `approved` reviews are public and counted in stats.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ReviewStatus {
+    /// Awaiting a moderator.
+    #[default]
+    Pending,
+    Approved,
+    Rejected,
+    /// Withdrawn from public view after publication.
+    Hidden,
+}
+
+impl ReviewStatus {
+    /// Value stored in `reviews.status`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Approved => "approved",
+            Self::Rejected => "rejected",
+            Self::Hidden => "hidden",
+        }
+    }
 }
 
+impl TryFrom<String> for ReviewStatus {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        match s.as_str() {
+            "pending" => Ok(Self::Pending),
+            "approved" => Ok(Self::Approved),
+            "rejected" => Ok(Self::Rejected),
+            "hidden" => Ok(Self::Hidden),
+            _ => Err(format!("unknown review status `{}`", s)),
+        }
+    }
+}
+
+/// What screening made of a review's text: the status it allows, and whether it was flagged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Screened {
+    pub status: ReviewStatus,
+    pub flagged: bool,
+}
+
+impl Screened {
+    /// Text screening let through; new reviews start in `status`.
+    pub fn accepted(status: ReviewStatus) -> Self {
+        Self {
+            status,
+            flagged: false,
+        }
+    }
+
+    /// Text screening sent to the moderation queue.
+    pub fn flagged() -> Self {
+        Self {
+            status: ReviewStatus::Pending,
+            flagged: true,
+        }
+    }
+
+    /// Status of a review in `current` once its author rewrites it. Rejected and hidden reviews
+    /// stay that way, and an approved one returns to moderation only if its new body is flagged.
+    pub fn after_edit(self, current: ReviewStatus, body_changed: bool) -> ReviewStatus {
+        match current {
+            ReviewStatus::Rejected | ReviewStatus::Hidden => current,
+            ReviewStatus::Approved if !(body_changed && self.flagged) => current,
+            ReviewStatus::Approved | ReviewStatus::Pending => self.status,
+        }
+    }
+}
+
+/// A new review (`POST /reviews`). The author is the authenticated caller.
 #[derive(Debug, Deserialize, ToSchema)]
+pub struct NewReview {
+    /// Author, required when a backend service posts with an API key. Ignored for users,
+    /// who always write as themselves.
+    pub user_id: Option<Uuid>,
+    pub product_id: Uuid,
+    pub rating: i32,
+    pub body: Option<String>,
+}
+
+/// A review to store, with its author resolved.
+#[derive(Debug)]
 pub struct CreateReview {
     pub product_id: Uuid,
     pub user_id: Uuid,
@@ -22,6 +125,37 @@ pub struct CreateReview {
     pub body: Option<String>,
 }
 
+/// Full replacement of the caller's review of a product (`PUT /products/{product_id}/reviews/mine`).
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpsertReview {
+    pub rating: i32,
+    pub body: Option<String>,
+}
+
+/// Partial update of a review. Omitted fields are left unchanged; `"body": null` clears the text.
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct UpdateReview {
+    pub rating: Option<i32>,
+    #[serde(default, deserialize_with = "present")]
+    #[schema(value_type = Option<String>)]
+    pub body: Option<Option<String>>,
+}
+
+impl UpdateReview {
+    pub fn is_empty(&self) -> bool {
+        self.rating.is_none() && self.body.is_none()
+    }
+}
+
+/// Wrap a field that was present in the payload in `Some`, so `null` and absent stay distinct.
+fn present<'de, D, T>(de: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(de).map(Some)
+}
+
 #[derive(Debug, Serialize, ToSchema)]
 pub struct ReviewResponse {
     pub id: Uuid,
@@ -29,7 +163,385 @@ pub struct ReviewResponse {
     pub user_id: Uuid,
     pub rating: i32,
     pub body: Option<String>,
-    pub created_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub status: ReviewStatus,
+    pub helpful_count: i32,
+    pub unhelpful_count: i32,
+    /// Current version, from 1; see `GET /reviews/{id}/revisions` for earlier ones.
+    pub revision: i32,
+    /// When the rating or body last changed; null if never edited.
+    pub edited_at: Option<DateTime<Utc>>,
+    /// The latest moderator decision; absent until a moderator has acted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub moderation: Option<ModerationInfo>,
+    /// The merchant's reply; absent until one has been approved.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reply: Option<ReplyResponse>,
+}
+
+/// Who last approved or rejected a review, when, and why.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ModerationInfo {
+    pub reason: Option<String>,
+    pub moderated_by: Uuid,
+    pub moderated_at: DateTime<Utc>,
+}
+
+/// Body of a moderator's approve or reject decision.
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct ModerationDecision {
+    /// Required when rejecting; shown to the author.
+    pub reason: Option<String>,
+}
+
+/// Paging parameters for the moderation queue.
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ModerationQueueQuery {
+    /// Which reviews to list, oldest first. Defaults to `pending`; `hidden` lists reported reviews.
+    pub status: Option<ReviewStatus>,
+    /// Opaque token from a previous page's `next_cursor`.
+    #[param(value_type = Option<String>)]
+    pub cursor: Option<Cursor>,
+    /// Page size (default 20, max 100).
+    pub limit: Option<u32>,
+}
+
+/// Why a shopper reported a review.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportReason {
+    Spam,
+    Offensive,
+    OffTopic,
+    FakeReview,
+    PersonalInfo,
+    Other,
+}
+
+impl ReportReason {
+    /// Value stored in `review_reports.reason`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Spam => "spam",
+            Self::Offensive => "offensive",
+            Self::OffTopic => "off_topic",
+            Self::FakeReview => "fake_review",
+            Self::PersonalInfo => "personal_info",
+            Self::Other => "other",
+        }
+    }
+}
+
+impl TryFrom<String> for ReportReason {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        match s.as_str() {
+            "spam" => Ok(Self::Spam),
+            "offensive" => Ok(Self::Offensive),
+            "off_topic" => Ok(Self::OffTopic),
+            "fake_review" => Ok(Self::FakeReview),
+            "personal_info" => Ok(Self::PersonalInfo),
+            "other" => Ok(Self::Other),
+            _ => Err(format!("unknown report reason `{}`", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct ReviewReport {
+    pub id: Uuid,
+    pub review_id: Uuid,
+    pub reporter_id: Uuid,
+    #[sqlx(try_from = "String")]
+    pub reason: ReportReason,
+    pub comment: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Body of `POST /reviews/{id}/reports`.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateReport {
+    pub reason: ReportReason,
+    /// Optional free text for moderators.
+    pub comment: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReportResponse {
+    pub id: Uuid,
+    pub review_id: Uuid,
+    pub reporter_id: Uuid,
+    pub reason: ReportReason,
+    pub comment: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Query parameters for `GET /moderation/reports`.
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ReportListQuery {
+    /// Only reports against this review.
+    pub review_id: Option<Uuid>,
+    /// Page size (default 20, max 100).
+    pub limit: Option<u32>,
+    /// Number of reports to skip.
+    pub offset: Option<u32>,
+}
+
+/// A page of reports, newest first. `next_offset` is absent on the last page.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReportPage {
+    pub items: Vec<ReportResponse>,
+    pub next_offset: Option<u64>,
+}
+
+/// Order of a review listing. Ties are broken newest first.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ReviewSort {
+    #[default]
+    Newest,
+    Oldest,
+    Highest,
+    Lowest,
+    /// Best evidenced helpful share first (Wilson lower bound of helpful votes).
+    #[serde(rename = "most_helpful")]
+    MostHelpful,
+}
+
+/// A shopper's verdict on whether a review helped them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum VoteKind {
+    Helpful,
+    Unhelpful,
+}
+
+/// Body of `POST /reviews/{id}/votes`. Voting again replaces the caller's earlier vote.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CastVote {
+    pub vote: VoteKind,
+}
+
+/// A superseded version of a review's rating and body.
+#[derive(Debug, Clone, FromRow)]
+pub struct ReviewRevision {
+    pub review_id: Uuid,
+    pub revision: i32,
+    pub rating: i32,
+    pub body: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub replaced_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RevisionResponse {
+    pub revision: i32,
+    pub rating: i32,
+    pub body: Option<String>,
+    /// When this version was written.
+    pub created_at: DateTime<Utc>,
+    /// When the next version replaced it.
+    pub replaced_at: DateTime<Utc>,
+}
+
+/// A merchant's public reply to a review. Each review has at most one, moderated on its own.
+#[derive(Debug, Clone, FromRow)]
+pub struct ReviewReply {
+    pub review_id: Uuid,
+    pub merchant_id: Uuid,
+    pub body: String,
+    #[sqlx(try_from = "String")]
+    pub status: ReviewStatus,
+    pub moderation_reason: Option<String>,
+    pub moderated_by: Option<Uuid>,
+    pub moderated_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Body of `POST` and `PATCH /reviews/{id}/reply`.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ReplyInput {
+    pub body: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReplyResponse {
+    pub review_id: Uuid,
+    pub merchant_id: Uuid,
+    pub body: String,
+    pub status: ReviewStatus,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// The latest moderator decision; absent until a moderator has acted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub moderation: Option<ModerationInfo>,
+}
+
+/// Query parameters for `GET /moderation/replies`.
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ReplyQueueQuery {
+    /// Which replies to list, oldest first. Defaults to `pending`.
+    pub status: Option<ReviewStatus>,
+    /// Page size (default 20, max 100).
+    pub limit: Option<u32>,
+    /// Number of replies to skip.
+    pub offset: Option<u32>,
+}
+
+/// A page of replies, oldest first. `next_offset` is absent on the last page.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReplyPage {
+    pub items: Vec<ReplyResponse>,
+    pub next_offset: Option<u64>,
+}
+
+/// Predicates for review listings. All fields are optional and combine with AND.
+#[derive(Debug, Default, Clone, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ReviewFilter {
+    pub product_id: Option<Uuid>,
+    pub user_id: Option<Uuid>,
+    /// Inclusive lower bound on rating.
+    pub min_rating: Option<i32>,
+    /// Inclusive upper bound on rating.
+    pub max_rating: Option<i32>,
+    /// Only reviews created at or after this instant.
+    pub created_after: Option<DateTime<Utc>>,
+    /// Only reviews created strictly before this instant.
+    pub created_before: Option<DateTime<Utc>>,
+    /// `true` for reviews with non-empty text, `false` for rating-only reviews.
+    pub has_body: Option<bool>,
+}
+
+/// Paging and ordering parameters for `GET /reviews`.
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListReviewsQuery {
+    /// Opaque token from a previous page's `next_cursor`. Only valid with the same `sort`.
+    #[param(value_type = Option<String>)]
+    pub cursor: Option<Cursor>,
+    /// Page size (default 20, max 100).
+    pub limit: Option<u32>,
+    /// Defaults to `newest`.
+    pub sort: Option<ReviewSort>,
+}
+
+/// A page of reviews. `next_cursor` is absent on the last page.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReviewPage {
+    pub items: Vec<ReviewResponse>,
+    pub next_cursor: Option<String>,
+}
+
+/// Most terms accepted in one search query.
+pub const MAX_SEARCH_TERMS: usize = 16;
+
+/// One term of a search query, normalised to lowercase alphanumeric words.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SearchTerm {
+    /// Matches the word (after stemming, on Postgres).
+    Word(String),
+    /// `word*`: matches any word starting with the prefix.
+    Prefix(String),
+    /// `"two words"`: matches the words adjacent and in order.
+    Phrase(Vec<String>),
+}
+
+/// A parsed search query: words, `"quoted phrases"` and `prefix*` terms, all of which must match.
+/// Punctuation is ignored, so user input never reaches the query syntax of the backend.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct SearchTerms(pub Vec<SearchTerm>);
+
+impl TryFrom<String> for SearchTerms {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        let words = |text: &str| -> Vec<String> {
+            text.split(|c: char| !c.is_alphanumeric())
+                .filter(|w| !w.is_empty())
+                .map(str::to_lowercase)
+                .collect()
+        };
+        let mut terms = Vec::new();
+        // Even-numbered segments lie outside quotes; an unclosed quote runs to the end.
+        for (i, segment) in s.split('"').enumerate() {
+            if i % 2 == 1 {
+                let mut phrase = words(segment);
+                match phrase.len() {
+                    0 => {}
+                    1 => terms.push(SearchTerm::Word(phrase.remove(0))),
+                    _ => terms.push(SearchTerm::Phrase(phrase)),
+                }
+                continue;
+            }
+            for token in segment.split_whitespace() {
+                let mut parts = words(token);
+                match parts.len() {
+                    0 => {}
+                    1 if token.ends_with('*') => terms.push(SearchTerm::Prefix(parts.remove(0))),
+                    1 => terms.push(SearchTerm::Word(parts.remove(0))),
+                    _ => terms.push(SearchTerm::Phrase(parts)),
+                }
+            }
+        }
+        if terms.is_empty() {
+            return Err("search query must contain at least one word".to_string());
+        }
+        if terms.len() > MAX_SEARCH_TERMS {
+            return Err(format!(
+                "search query may contain at most {} terms",
+                MAX_SEARCH_TERMS
+            ));
+        }
+        Ok(Self(terms))
+    }
+}
+
+/// Query parameters for `GET /reviews/search`.
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SearchQuery {
+    /// Words to find in review text. `"quoted phrases"` match adjacent words; `word*` matches a prefix.
+    #[param(value_type = String)]
+    pub q: SearchTerms,
+    pub product_id: Option<Uuid>,
+    /// Page size (default 20, max 100).
+    pub limit: Option<u32>,
+    /// Number of hits to skip.
+    pub offset: Option<u32>,
+}
+
+/// A matching review as read from the store.
+#[derive(Debug, FromRow)]
+pub struct SearchRow {
+    #[sqlx(flatten)]
+    pub review: Review,
+    pub rank: f32,
+    pub snippet: String,
+}
+
+/// A review matching a search, with its relevance and highlighted excerpt.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SearchHit {
+    #[serde(flatten)]
+    pub review: ReviewResponse,
+    /// Relevance; higher is better. Only comparable within one result set.
+    pub rank: f32,
+    /// Excerpt of the body with matches wrapped in `<mark>` and `</mark>`.
+    pub snippet: String,
+}
+
+/// A page of search hits, best match first. `next_offset` is absent on the last page.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SearchPage {
+    pub items: Vec<SearchHit>,
+    pub next_offset: Option<u64>,
 }
 
 #[derive(Debug, Serialize, ToSchema)]
@@ -37,3 +549,378 @@ pub struct DashboardStats {
     pub total_reviews: u64,
     pub avg_rating: f64,
 }
+
+/// Row of `product_rating_aggregates`: running totals for one product.
+#[derive(Debug, Clone, FromRow)]
+pub struct ProductAggregate {
+    pub product_id: Uuid,
+    pub review_count: i64,
+    pub rating_sum: i64,
+    pub star_1: i64,
+    pub star_2: i64,
+    pub star_3: i64,
+    pub star_4: i64,
+    pub star_5: i64,
+    pub latest_review_at: Option<DateTime<Utc>>,
+}
+
+impl ProductAggregate {
+    /// The counters that must agree with `reviews`; `latest_review_at` is informational.
+    pub fn counts(&self) -> [i64; 7] {
+        [
+            self.review_count,
+            self.rating_sum,
+            self.star_1,
+            self.star_2,
+            self.star_3,
+            self.star_4,
+            self.star_5,
+        ]
+    }
+
+    pub fn histogram(&self) -> StarHistogram {
+        let n = |v: i64| v.max(0) as u64;
+        StarHistogram {
+            one: n(self.star_1),
+            two: n(self.star_2),
+            three: n(self.star_3),
+            four: n(self.star_4),
+            five: n(self.star_5),
+        }
+    }
+}
+
+/// Number of reviews at each star rating.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct StarHistogram {
+    #[serde(rename = "1")]
+    pub one: u64,
+    #[serde(rename = "2")]
+    pub two: u64,
+    #[serde(rename = "3")]
+    pub three: u64,
+    #[serde(rename = "4")]
+    pub four: u64,
+    #[serde(rename = "5")]
+    pub five: u64,
+}
+
+/// Rating summary for a single product.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ProductStats {
+    pub product_id: Uuid,
+    pub review_count: u64,
+    /// Arithmetic mean rating; 0 when there are no reviews.
+    pub avg_rating: f64,
+    pub histogram: StarHistogram,
+    pub latest_review_at: Option<DateTime<Utc>>,
+    /// Mean shrunk towards the configured prior; ranks sparsely reviewed products fairly.
+    pub bayesian_score: f64,
+}
+
+/// Width of a time-series bucket. Weeks start on Monday (ISO 8601).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeBucket {
+    #[default]
+    Day,
+    Week,
+    Month,
+}
+
+impl TimeBucket {
+    /// Postgres `date_trunc` field name; also valid as an interval unit.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Day => "day",
+            Self::Week => "week",
+            Self::Month => "month",
+        }
+    }
+
+    /// Shortest possible bucket length in days, for bounding the number of buckets.
+    pub fn min_days(self) -> i64 {
+        match self {
+            Self::Day => 1,
+            Self::Week => 7,
+            Self::Month => 28,
+        }
+    }
+}
+
+/// Query parameters for `GET /stats/timeseries`.
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TimeseriesQuery {
+    /// Defaults to `day`.
+    pub bucket: Option<TimeBucket>,
+    /// Inclusive start. Defaults to 30 days before `to`.
+    pub from: Option<DateTime<Utc>>,
+    /// Exclusive end. Defaults to now.
+    pub to: Option<DateTime<Utc>>,
+    pub product_id: Option<Uuid>,
+    /// IANA time zone that bucket boundaries follow, e.g. `Europe/Berlin`. Defaults to `UTC`.
+    pub tz: Option<String>,
+}
+
+/// One bucket as read from the database.
+#[derive(Debug, FromRow)]
+pub struct TimeseriesRow {
+    pub bucket_start: DateTime<Utc>,
+    pub review_count: i64,
+    pub avg_rating: Option<f64>,
+}
+
+/// Review volume and mean rating within one bucket.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TimeseriesPoint {
+    /// Bucket start: local midnight (or week/month start) in `tz`, expressed in UTC.
+    pub bucket_start: DateTime<Utc>,
+    pub review_count: u64,
+    /// Absent for buckets with no reviews.
+    pub avg_rating: Option<f64>,
+}
+
+/// Review counts and average ratings over time; every bucket in range is present.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Timeseries {
+    pub bucket: TimeBucket,
+    pub tz: String,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub points: Vec<TimeseriesPoint>,
+}
+
+/// Ranking used by the product leaderboard.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LeaderboardOrder {
+    /// Highest Bayesian score first.
+    #[default]
+    Best,
+    /// Lowest Bayesian score first.
+    Worst,
+    /// Most reviews first.
+    MostReviewed,
+}
+
+/// A trailing time window such as `30d`, `12h` or `2w`, of at most [`TimeWindow::MAX_DAYS`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct TimeWindow {
+    pub amount: u32,
+    pub unit: char,
+}
+
+impl TimeWindow {
+    /// Longest window accepted: ten years.
+    pub const MAX_DAYS: i64 = 3650;
+
+    /// The window's length, or `None` if it is longer than [`MAX_DAYS`](Self::MAX_DAYS).
+    pub fn duration(&self) -> Option<Duration> {
+        let amount = i64::from(self.amount);
+        let duration = match self.unit {
+            'h' => Duration::try_hours(amount),
+            'w' => Duration::try_weeks(amount),
+            _ => Duration::try_days(amount),
+        }?;
+        (duration <= Duration::days(Self::MAX_DAYS)).then_some(duration)
+    }
+}
+
+impl TryFrom<String> for TimeWindow {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        let invalid = || format!("invalid window `{}`; expected e.g. 24h, 30d or 4w", s);
+        let unit = s
+            .chars()
+            .last()
+            .filter(|c| matches!(c, 'h' | 'd' | 'w'))
+            .ok_or_else(invalid)?;
+        let amount: u32 = s[..s.len() - 1].parse().map_err(|_| invalid())?;
+        if amount == 0 {
+            return Err(invalid());
+        }
+        Ok(Self { amount, unit })
+    }
+}
+
+impl fmt::Display for TimeWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.amount, self.unit)
+    }
+}
+
+/// Query parameters for `GET /stats/products/top`.
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct LeaderboardQuery {
+    /// Defaults to `best`.
+    pub order: Option<LeaderboardOrder>,
+    /// Products with fewer reviews (within the window) are left out. Defaults to 1.
+    pub min_reviews: Option<u32>,
+    /// Only count reviews from this trailing window, e.g. `30d`. All time when omitted.
+    #[param(value_type = Option<String>)]
+    pub window: Option<TimeWindow>,
+    /// Page size (default 20, max 100).
+    pub limit: Option<u32>,
+    /// Number of ranked products to skip.
+    pub offset: Option<u32>,
+}
+
+/// One product's position on the leaderboard.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ProductRanking {
+    /// 1-based position in the full ranking.
+    pub rank: u64,
+    pub product_id: Uuid,
+    pub review_count: u64,
+    pub avg_rating: f64,
+    pub bayesian_score: f64,
+    pub histogram: StarHistogram,
+    pub latest_review_at: Option<DateTime<Utc>>,
+}
+
+/// A page of the product leaderboard. `next_offset` is absent on the last page.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LeaderboardPage {
+    pub order: LeaderboardOrder,
+    /// The window applied, e.g. `30d`; absent for all-time rankings.
+    pub window: Option<String>,
+    pub items: Vec<ProductRanking>,
+    pub next_offset: Option<u64>,
+}
+
+/// Query parameters for the aggregate reconcile operation.
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ReconcileQuery {
+    /// Report drift without rewriting the table.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Aggregate counters for one product as either recomputed or stored.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AggregateCounts {
+    pub review_count: i64,
+    pub rating_sum: i64,
+    pub histogram: StarHistogram,
+}
+
+/// What a backend service's API key lets it do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ApiScope {
+    /// Public review listings, lookups and search.
+    Read,
+    /// Creating reviews on a user's behalf.
+    Write,
+    /// The analytics routes under `/stats`.
+    Stats,
+}
+
+impl ApiScope {
+    /// Value stored in `api_keys.scopes`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Read => "read",
+            Self::Write => "write",
+            Self::Stats => "stats",
+        }
+    }
+}
+
+impl std::str::FromStr for ApiScope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "read" => Ok(Self::Read),
+            "write" => Ok(Self::Write),
+            "stats" => Ok(Self::Stats),
+            _ => Err(format!("unknown API key scope `{}`", s)),
+        }
+    }
+}
+
+/// An API key to issue, from the `api-keys create` admin command.
+#[derive(Debug, Clone)]
+pub struct NewApiKey {
+    pub name: String,
+    pub scopes: Vec<ApiScope>,
+    /// When the key stops working; `None` for a key that never expires.
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// A backend service's API key. Only a hash of the secret is kept.
+#[derive(Debug, Clone)]
+pub struct ApiKey {
+    pub id: Uuid,
+    /// Who the key was issued to, e.g. `order-service`.
+    pub name: String,
+    /// Leading characters of the secret, enough to recognise it.
+    pub prefix: String,
+    /// Hex SHA-256 of the secret.
+    pub key_hash: String,
+    pub scopes: Vec<ApiScope>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl ApiKey {
+    /// Whether the key may be used at `now`: not revoked and not expired.
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.revoked_at.is_none() && self.expires_at.is_none_or(|at| at > now)
+    }
+}
+
+/// An `Idempotency-Key` in use: the request first sent with it and, once that request finished,
+/// the response to replay.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdempotencyRecord {
+    /// The caller the key belongs to, `user:<id>` or `key:<api key id>`; callers never share keys.
+    pub scope: String,
+    pub key: String,
+    /// Hex SHA-256 of the request payload.
+    pub request_hash: String,
+    /// `None` while the first request is still running.
+    pub response_status: Option<i32>,
+    pub response_body: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Result of a retry-safe operation: a new result, or the stored response of an earlier attempt.
+#[derive(Debug)]
+pub enum Idempotent<T> {
+    Fresh(T),
+    Replayed { status: u16, body: String },
+}
+
+/// A product whose stored aggregate disagreed with `reviews`.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AggregateDrift {
+    pub product_id: Uuid,
+    /// Recomputed from `reviews`.
+    pub expected: AggregateCounts,
+    /// As found in `product_rating_aggregates`.
+    pub stored: AggregateCounts,
+}
+
+/// Outcome of purging soft-deleted reviews past their retention period.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PurgeReport {
+    /// Reviews permanently removed.
+    pub purged: u64,
+}
+
+/// Outcome of comparing (and optionally rebuilding) `product_rating_aggregates`.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReconcileReport {
+    pub products_checked: u64,
+    pub drift: Vec<AggregateDrift>,
+    /// Whether the table was rebuilt. False for dry runs.
+    pub repaired: bool,
+}
@@ -0,0 +1,106 @@
+//! Keyset pagination helpers. Cursors are opaque to clients; only this module knows their layout.
+
+use std::fmt;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use uuid::Uuid;
+
+/// Page size used when the client does not pass `limit`.
+pub const DEFAULT_PAGE_SIZE: u32 = 20;
+/// Upper bound on `limit`; larger values are clamped.
+pub const MAX_PAGE_SIZE: u32 = 100;
+
+/// Resolve a client-supplied `limit` to a page size within `1..=MAX_PAGE_SIZE`.
+pub fn clamp_limit(limit: Option<u32>) -> u32 {
+    limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE)
+}
+
+/// Sort key of the last row on a page. Carries every column any listing order may key on,
+/// so the same cursor layout serves all sorts.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Cursor {
+    pub rating: i32,
+    pub created_at: DateTime<Utc>,
+    pub id: Uuid,
+    pub helpful_score: f64,
+}
+
+impl Cursor {
+    pub fn new(rating: i32, created_at: DateTime<Utc>, id: Uuid) -> Self {
+        Self {
+            rating,
+            created_at,
+            id,
+            helpful_score: 0.0,
+        }
+    }
+
+    /// Also key on the helpfulness score, for `most_helpful` listings.
+    pub fn with_helpful_score(mut self, helpful_score: f64) -> Self {
+        self.helpful_score = helpful_score;
+        self
+    }
+
+    /// Encode as a URL-safe token suitable for a query string. `f64` formatting round-trips exactly.
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(format!(
+            "{}:{}:{}:{}",
+            self.rating,
+            self.created_at.timestamp_micros(),
+            self.id,
+            self.helpful_score
+        ))
+    }
+
+    pub fn decode(token: &str) -> Result<Self, InvalidCursor> {
+        let raw = URL_SAFE_NO_PAD.decode(token).map_err(|_| InvalidCursor)?;
+        let raw = String::from_utf8(raw).map_err(|_| InvalidCursor)?;
+        let mut parts = raw.splitn(4, ':');
+        let (Some(rating), Some(micros), Some(id)) = (parts.next(), parts.next(), parts.next())
+        else {
+            return Err(InvalidCursor);
+        };
+        // Tokens issued before `most_helpful` existed have no score.
+        let helpful_score = match parts.next() {
+            Some(score) => score
+                .parse::<f64>()
+                .ok()
+                .filter(|s| s.is_finite())
+                .ok_or(InvalidCursor)?,
+            None => 0.0,
+        };
+        let rating: i32 = rating.parse().map_err(|_| InvalidCursor)?;
+        let micros: i64 = micros.parse().map_err(|_| InvalidCursor)?;
+        let created_at = DateTime::from_timestamp_micros(micros).ok_or(InvalidCursor)?;
+        let id = Uuid::parse_str(id).map_err(|_| InvalidCursor)?;
+        Ok(Self {
+            rating,
+            created_at,
+            id,
+            helpful_score,
+        })
+    }
+}
+
+impl TryFrom<String> for Cursor {
+    type Error = InvalidCursor;
+
+    fn try_from(token: String) -> Result<Self, Self::Error> {
+        Self::decode(&token)
+    }
+}
+
+/// A cursor token that was not produced by [`Cursor::encode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidCursor;
+
+impl fmt::Display for InvalidCursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid cursor")
+    }
+}
+
+impl std::error::Error for InvalidCursor {}
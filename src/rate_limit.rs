@@ -0,0 +1,260 @@
+//! Per-caller request throttling. Each caller gets a token bucket per route group: a request
+//! takes a token, and tokens refill evenly over the limit's window, so a caller may burst up to
+//! the limit and then continue at the sustained rate. Callers are keyed by bearer-token user,
+//! API key, or else client IP; unverified headers never pick the bucket.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{header, HeaderName, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use crate::error::ProblemDetails;
+use crate::extractors::{ApiClient, Principal};
+
+/// Buckets the in-memory store tracks before it sweeps out full (idle) ones.
+const MAX_BUCKETS: usize = 100_000;
+
+const RATELIMIT_LIMIT: HeaderName = HeaderName::from_static("ratelimit-limit");
+const RATELIMIT_REMAINING: HeaderName = HeaderName::from_static("ratelimit-remaining");
+const RATELIMIT_RESET: HeaderName = HeaderName::from_static("ratelimit-reset");
+const RATELIMIT_POLICY: HeaderName = HeaderName::from_static("ratelimit-policy");
+
+/// `requests` per `window`, e.g. `10/1m`. Up to `requests` may be made at once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub requests: u32,
+    pub window: Duration,
+}
+
+impl RateLimit {
+    /// Tokens regained per second.
+    fn refill_rate(&self) -> f64 {
+        f64::from(self.requests) / self.window.as_secs_f64()
+    }
+}
+
+impl FromStr for RateLimit {
+    type Err = String;
+
+    /// `<requests>/<window>`, the window a number with unit `s`, `m` or `h`: `10/1m`, `100/30s`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "invalid rate limit `{}`; expected e.g. 10/1m, 100/30s or 500/1h",
+                s
+            )
+        };
+        let (requests, window) = s.split_once('/').ok_or_else(invalid)?;
+        let requests: u32 = requests.trim().parse().map_err(|_| invalid())?;
+        let window = window.trim();
+        let unit = match window.chars().last() {
+            Some('s') => 1,
+            Some('m') => 60,
+            Some('h') => 3600,
+            _ => return Err(invalid()),
+        };
+        let amount: u64 = window[..window.len() - 1].parse().map_err(|_| invalid())?;
+        if requests == 0 || amount == 0 {
+            return Err(invalid());
+        }
+        let secs = amount.checked_mul(unit).ok_or_else(invalid)?;
+        Ok(Self {
+            requests,
+            window: Duration::from_secs(secs),
+        })
+    }
+}
+
+/// Limits for each throttled route group in [`crate::routes`]; `None` leaves a group unthrottled.
+#[derive(Debug, Clone)]
+pub struct RateLimits {
+    /// Creating and replacing reviews.
+    pub reviews: Option<RateLimit>,
+    /// Casting and withdrawing helpful votes.
+    pub votes: Option<RateLimit>,
+}
+
+impl Default for RateLimits {
+    fn default() -> Self {
+        Self {
+            reviews: Some(RateLimit {
+                requests: 10,
+                window: Duration::from_secs(60),
+            }),
+            votes: Some(RateLimit {
+                requests: 60,
+                window: Duration::from_secs(60),
+            }),
+        }
+    }
+}
+
+/// Outcome of asking a [`RateLimitStore`] for a token.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Decision {
+    pub allowed: bool,
+    /// Whole tokens left after this request.
+    pub remaining: u32,
+    /// Until the bucket is full again.
+    pub reset_after: Duration,
+    /// Until the next token is available; zero while tokens remain.
+    pub retry_after: Duration,
+}
+
+/// Where token buckets live. The in-process [`InMemoryRateLimitStore`] throttles each replica on
+/// its own; a shared implementation (e.g. over Redis) would throttle across replicas.
+#[async_trait]
+pub trait RateLimitStore: Send + Sync {
+    /// Take one token from `key`'s bucket under `limit`, starting a full bucket for a new key.
+    /// A shared store that cannot be reached should allow the request rather than fail it.
+    async fn acquire(&self, key: &str, limit: RateLimit) -> Decision;
+}
+
+/// Token buckets in process memory.
+#[derive(Debug, Default)]
+pub struct InMemoryRateLimitStore {
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+    limit: RateLimit,
+}
+
+impl Bucket {
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens =
+            (self.tokens + elapsed * self.limit.refill_rate()).min(f64::from(self.limit.requests));
+        self.updated = now;
+    }
+
+    fn is_full(&self, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens + elapsed * self.limit.refill_rate() >= f64::from(self.limit.requests)
+    }
+}
+
+impl InMemoryRateLimitStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl RateLimitStore for InMemoryRateLimitStore {
+    async fn acquire(&self, key: &str, limit: RateLimit) -> Decision {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        if buckets.len() >= MAX_BUCKETS && !buckets.contains_key(key) {
+            // A full bucket behaves exactly like a missing one, so dropping it loses nothing.
+            buckets.retain(|_, b| !b.is_full(now));
+        }
+        let bucket = buckets.entry(key.to_string()).or_insert_with(|| Bucket {
+            tokens: f64::from(limit.requests),
+            updated: now,
+            limit,
+        });
+        bucket.limit = limit;
+        bucket.refill(now);
+        let allowed = bucket.tokens >= 1.0;
+        if allowed {
+            bucket.tokens -= 1.0;
+        }
+        let rate = limit.refill_rate();
+        Decision {
+            allowed,
+            remaining: bucket.tokens.floor() as u32,
+            reset_after: Duration::from_secs_f64(
+                (f64::from(limit.requests) - bucket.tokens) / rate,
+            ),
+            retry_after: Duration::from_secs_f64((1.0 - bucket.tokens).max(0.0) / rate),
+        }
+    }
+}
+
+/// State for [`throttle`]: one route group's limit and the store holding its buckets.
+#[derive(Clone)]
+pub struct RateLimiter {
+    group: &'static str,
+    limit: RateLimit,
+    store: Arc<dyn RateLimitStore>,
+}
+
+impl RateLimiter {
+    /// Throttle a route group. `group` namespaces its buckets, so a caller's quota in one group
+    /// does not touch another.
+    pub fn new(group: &'static str, limit: RateLimit, store: Arc<dyn RateLimitStore>) -> Self {
+        Self {
+            group,
+            limit,
+            store,
+        }
+    }
+}
+
+/// Middleware: take a token for the caller and answer 429 with `Retry-After` when none is left.
+/// Every response reports the caller's quota in `RateLimit-*` headers. Must run after
+/// [`crate::auth::authenticate`], which identifies the caller.
+pub async fn throttle(State(limiter): State<RateLimiter>, req: Request, next: Next) -> Response {
+    let key = format!("{}:{}", limiter.group, caller_key(&req));
+    let decision = limiter.store.acquire(&key, limiter.limit).await;
+    let mut response = if decision.allowed {
+        next.run(req).await
+    } else {
+        let retry_after = whole_secs(decision.retry_after).max(1);
+        tracing::debug!(%key, retry_after, "rate limit exceeded");
+        let detail = format!("Rate limit exceeded; retry in {} seconds", retry_after);
+        let mut response =
+            ProblemDetails::new(StatusCode::TOO_MANY_REQUESTS, detail).into_response();
+        response
+            .headers_mut()
+            .insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+        response
+    };
+    let headers = response.headers_mut();
+    headers.insert(RATELIMIT_LIMIT, HeaderValue::from(limiter.limit.requests));
+    headers.insert(RATELIMIT_REMAINING, HeaderValue::from(decision.remaining));
+    headers.insert(
+        RATELIMIT_RESET,
+        HeaderValue::from(whole_secs(decision.reset_after)),
+    );
+    if let Ok(policy) = HeaderValue::from_str(&format!(
+        "{};w={}",
+        limiter.limit.requests,
+        limiter.limit.window.as_secs()
+    )) {
+        headers.insert(RATELIMIT_POLICY, policy);
+    }
+    response
+}
+
+/// Who the request counts against, most specific identity first.
+fn caller_key(req: &Request) -> String {
+    if let Some(principal) = req.extensions().get::<Principal>() {
+        return format!("user:{}", principal.user_id);
+    }
+    if let Some(client) = req.extensions().get::<ApiClient>() {
+        return format!("key:{}", client.key_id);
+    }
+    match req.extensions().get::<ConnectInfo<SocketAddr>>() {
+        Some(ConnectInfo(addr)) => format!("ip:{}", addr.ip()),
+        None => "ip:unknown".to_string(),
+    }
+}
+
+/// Seconds rounded up, so a client that waits that long is never early.
+fn whole_secs(duration: Duration) -> u64 {
+    duration.as_secs() + u64::from(duration.subsec_nanos() > 0)
+}
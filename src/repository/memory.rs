@@ -0,0 +1,766 @@
+//! In-memory [`ReviewStore`] for tests and local development. Nothing is persisted.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use super::{text_match, utc_buckets, ReviewStore};
+use crate::error::{ReviewError, DUPLICATE_REPLY, DUPLICATE_REPORT, DUPLICATE_REVIEW};
+use crate::models::{
+    ApiKey, CreateReport, CreateReview, IdempotencyRecord, LeaderboardOrder, ProductAggregate,
+    Review, ReviewFilter, ReviewReply, ReviewReport, ReviewRevision, ReviewSort, ReviewStatus,
+    Screened, SearchRow, SearchTerms, TimeBucket, TimeseriesRow, UpdateReview,
+};
+use crate::pagination::Cursor;
+use crate::service::wilson_lower_bound;
+
+/// Thread-safe review store backed by a map. Aggregates are computed from the reviews on
+/// every read, so they can never drift and reconciling always reports a clean table.
+///
+/// Time series support only the `UTC` time zone; search matches whole words without stemming.
+#[derive(Debug, Default)]
+pub struct InMemoryReviewStore {
+    reviews: RwLock<HashMap<Uuid, Review>>,
+    /// Soft-deleted reviews with their deletion time, kept apart so reads never see them.
+    /// Lock after `reviews`.
+    deleted: RwLock<HashMap<Uuid, (Review, DateTime<Utc>)>>,
+    /// Oldest first. Lock after `reviews` when holding both.
+    revisions: RwLock<Vec<ReviewRevision>>,
+    /// Oldest first. Lock after `reviews` when holding both.
+    reports: RwLock<Vec<ReviewReport>>,
+    /// `(review_id, voter_id)` to whether the vote was helpful. Lock after `reviews`.
+    votes: RwLock<HashMap<(Uuid, Uuid), bool>>,
+    /// Keyed by review. Lock after `reviews`.
+    replies: RwLock<HashMap<Uuid, ReviewReply>>,
+    /// Oldest first. Never held with another lock.
+    api_keys: RwLock<Vec<ApiKey>>,
+    /// Keyed by `(scope, key)`. Never held with another lock.
+    idempotency_keys: RwLock<HashMap<(String, String), IdempotencyRecord>>,
+}
+
+impl InMemoryReviewStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, HashMap<Uuid, Review>> {
+        self.reviews.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, HashMap<Uuid, Review>> {
+        self.reviews.write().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write_revisions(&self) -> std::sync::RwLockWriteGuard<'_, Vec<ReviewRevision>> {
+        self.revisions.write().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write_replies(&self) -> std::sync::RwLockWriteGuard<'_, HashMap<Uuid, ReviewReply>> {
+        self.replies.write().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write_reports(&self) -> std::sync::RwLockWriteGuard<'_, Vec<ReviewReport>> {
+        self.reports.write().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[async_trait]
+impl ReviewStore for InMemoryReviewStore {
+    async fn find_all(&self) -> Result<Vec<Review>, ReviewError> {
+        let mut rows: Vec<Review> = self.read().values().cloned().collect();
+        rows.sort_by(|a, b| compare(ReviewSort::Newest, a, b));
+        Ok(rows)
+    }
+
+    async fn find_page(
+        &self,
+        filter: &ReviewFilter,
+        status: ReviewStatus,
+        sort: ReviewSort,
+        after: Option<Cursor>,
+        limit: i64,
+    ) -> Result<Vec<Review>, ReviewError> {
+        let mut rows: Vec<Review> = self
+            .read()
+            .values()
+            .filter(|r| r.status == status && matches(filter, r))
+            .filter(|r| after.is_none_or(|c| compare_key(sort, key(r), cursor_key(&c)).is_gt()))
+            .cloned()
+            .collect();
+        rows.sort_by(|a, b| compare(sort, a, b));
+        rows.truncate(usize::try_from(limit).unwrap_or(0));
+        Ok(rows)
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> Result<Option<Review>, ReviewError> {
+        Ok(self.read().get(&id).cloned())
+    }
+
+    async fn find_revisions(&self, review_id: Uuid) -> Result<Vec<ReviewRevision>, ReviewError> {
+        let revisions = self.revisions.read().unwrap_or_else(|e| e.into_inner());
+        Ok(revisions
+            .iter()
+            .rev()
+            .filter(|r| r.review_id == review_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn search(
+        &self,
+        terms: &SearchTerms,
+        product_id: Option<Uuid>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<SearchRow>, ReviewError> {
+        let candidates: Vec<Review> = self
+            .read()
+            .values()
+            .filter(|r| {
+                r.status == ReviewStatus::Approved && product_id.is_none_or(|p| r.product_id == p)
+            })
+            .cloned()
+            .collect();
+        Ok(text_match::search(
+            candidates.into_iter(),
+            terms,
+            limit,
+            offset,
+        ))
+    }
+
+    async fn create(
+        &self,
+        id: Uuid,
+        body: &CreateReview,
+        status: ReviewStatus,
+    ) -> Result<Review, ReviewError> {
+        let mut reviews = self.write();
+        if find_pair(&reviews, body.product_id, body.user_id).is_some() {
+            return Err(ReviewError::Conflict(DUPLICATE_REVIEW.to_string()));
+        }
+        let review = new_review(id, body, status);
+        reviews.insert(id, review.clone());
+        Ok(review)
+    }
+
+    async fn upsert(
+        &self,
+        id: Uuid,
+        body: &CreateReview,
+        screened: Screened,
+    ) -> Result<(Review, bool), ReviewError> {
+        let mut reviews = self.write();
+        if let Some(existing) = find_pair(&reviews, body.product_id, body.user_id) {
+            let review = reviews.get_mut(&existing).expect("id was just found");
+            if let Some(revision) = rewrite(review, body.rating, body.body.clone(), screened) {
+                self.write_revisions().push(revision);
+            }
+            return Ok((review.clone(), false));
+        }
+        let review = new_review(id, body, screened.status);
+        reviews.insert(id, review.clone());
+        Ok((review, true))
+    }
+
+    async fn update(
+        &self,
+        id: Uuid,
+        patch: &UpdateReview,
+        screened: Screened,
+    ) -> Result<Option<Review>, ReviewError> {
+        let mut reviews = self.write();
+        let Some(review) = reviews.get_mut(&id) else {
+            return Ok(None);
+        };
+        let rating = patch.rating.unwrap_or(review.rating);
+        let body = patch.body.clone().unwrap_or_else(|| review.body.clone());
+        if let Some(revision) = rewrite(review, rating, body, screened) {
+            self.write_revisions().push(revision);
+        }
+        Ok(Some(review.clone()))
+    }
+
+    async fn moderate(
+        &self,
+        id: Uuid,
+        status: ReviewStatus,
+        reason: Option<&str>,
+        moderator: Uuid,
+    ) -> Result<Option<Review>, ReviewError> {
+        let mut reviews = self.write();
+        let Some(review) = reviews.get_mut(&id) else {
+            return Ok(None);
+        };
+        review.status = status;
+        review.moderation_reason = reason.map(str::to_string);
+        review.moderated_by = Some(moderator);
+        review.moderated_at = Some(now());
+        Ok(Some(review.clone()))
+    }
+
+    async fn vote(
+        &self,
+        review_id: Uuid,
+        voter: Uuid,
+        helpful: Option<bool>,
+    ) -> Result<Option<Review>, ReviewError> {
+        let mut reviews = self.write();
+        let Some(review) = reviews.get_mut(&review_id) else {
+            return Ok(None);
+        };
+        let mut votes = self.votes.write().unwrap_or_else(|e| e.into_inner());
+        match helpful {
+            Some(helpful) => votes.insert((review_id, voter), helpful),
+            None => votes.remove(&(review_id, voter)),
+        };
+        let (up, down) = votes.iter().filter(|((id, _), _)| *id == review_id).fold(
+            (0, 0),
+            |(up, down), (_, &helpful)| {
+                if helpful {
+                    (up + 1, down)
+                } else {
+                    (up, down + 1)
+                }
+            },
+        );
+        review.helpful_count = up;
+        review.unhelpful_count = down;
+        review.helpful_score = wilson_lower_bound(up as u64, down as u64);
+        Ok(Some(review.clone()))
+    }
+
+    async fn report(
+        &self,
+        id: Uuid,
+        review_id: Uuid,
+        reporter: Uuid,
+        report: &CreateReport,
+        hide_at: i64,
+    ) -> Result<Option<(ReviewReport, bool)>, ReviewError> {
+        let mut reviews = self.write();
+        let Some(review) = reviews.get_mut(&review_id) else {
+            return Ok(None);
+        };
+        let mut reports = self.write_reports();
+        let against: Vec<&ReviewReport> = reports
+            .iter()
+            .filter(|r| r.review_id == review_id)
+            .collect();
+        if against.iter().any(|r| r.reporter_id == reporter) {
+            return Err(ReviewError::Conflict(DUPLICATE_REPORT.to_string()));
+        }
+        let hide = against.len() as i64 + 1 == hide_at && review.status == ReviewStatus::Approved;
+        if hide {
+            review.status = ReviewStatus::Hidden;
+        }
+        let saved = ReviewReport {
+            id,
+            review_id,
+            reporter_id: reporter,
+            reason: report.reason,
+            comment: report.comment.clone(),
+            created_at: now(),
+        };
+        reports.push(saved.clone());
+        Ok(Some((saved, hide)))
+    }
+
+    async fn find_reports(
+        &self,
+        review_id: Option<Uuid>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<ReviewReport>, ReviewError> {
+        let reports = self.reports.read().unwrap_or_else(|e| e.into_inner());
+        Ok(reports
+            .iter()
+            .rev()
+            .filter(|r| review_id.is_none_or(|id| r.review_id == id))
+            .skip(offset.max(0) as usize)
+            .take(limit.max(0) as usize)
+            .cloned()
+            .collect())
+    }
+
+    async fn find_replies(&self, review_ids: &[Uuid]) -> Result<Vec<ReviewReply>, ReviewError> {
+        let replies = self.replies.read().unwrap_or_else(|e| e.into_inner());
+        Ok(review_ids
+            .iter()
+            .filter_map(|id| replies.get(id))
+            .cloned()
+            .collect())
+    }
+
+    async fn find_reply_queue(
+        &self,
+        status: ReviewStatus,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<ReviewReply>, ReviewError> {
+        let mut rows: Vec<ReviewReply> = self
+            .replies
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .filter(|r| r.status == status)
+            .cloned()
+            .collect();
+        rows.sort_by_key(|r| (r.created_at, r.review_id));
+        Ok(rows
+            .into_iter()
+            .skip(offset.max(0) as usize)
+            .take(limit.max(0) as usize)
+            .collect())
+    }
+
+    async fn create_reply(
+        &self,
+        review_id: Uuid,
+        merchant: Uuid,
+        body: &str,
+        status: ReviewStatus,
+    ) -> Result<ReviewReply, ReviewError> {
+        let reviews = self.read();
+        if !reviews.contains_key(&review_id) {
+            return Err(ReviewError::Conflict(
+                "Referenced row is missing or still in use".to_string(),
+            ));
+        }
+        let mut replies = self.write_replies();
+        if replies.contains_key(&review_id) {
+            return Err(ReviewError::Conflict(DUPLICATE_REPLY.to_string()));
+        }
+        let now = now();
+        let reply = ReviewReply {
+            review_id,
+            merchant_id: merchant,
+            body: body.to_string(),
+            status,
+            moderation_reason: None,
+            moderated_by: None,
+            moderated_at: None,
+            created_at: now,
+            updated_at: now,
+        };
+        replies.insert(review_id, reply.clone());
+        Ok(reply)
+    }
+
+    async fn update_reply(
+        &self,
+        review_id: Uuid,
+        body: &str,
+        status: ReviewStatus,
+    ) -> Result<Option<ReviewReply>, ReviewError> {
+        let mut replies = self.write_replies();
+        let Some(reply) = replies.get_mut(&review_id) else {
+            return Ok(None);
+        };
+        reply.body = body.to_string();
+        reply.status = status;
+        reply.updated_at = now();
+        Ok(Some(reply.clone()))
+    }
+
+    async fn moderate_reply(
+        &self,
+        review_id: Uuid,
+        status: ReviewStatus,
+        reason: Option<&str>,
+        moderator: Uuid,
+    ) -> Result<Option<ReviewReply>, ReviewError> {
+        let mut replies = self.write_replies();
+        let Some(reply) = replies.get_mut(&review_id) else {
+            return Ok(None);
+        };
+        reply.status = status;
+        reply.moderation_reason = reason.map(str::to_string);
+        reply.moderated_by = Some(moderator);
+        reply.moderated_at = Some(now());
+        Ok(Some(reply.clone()))
+    }
+
+    async fn delete_reply(&self, review_id: Uuid) -> Result<bool, ReviewError> {
+        Ok(self.write_replies().remove(&review_id).is_some())
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<bool, ReviewError> {
+        let mut reviews = self.write();
+        let Some(review) = reviews.remove(&id) else {
+            return Ok(false);
+        };
+        self.deleted
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(id, (review, now()));
+        Ok(true)
+    }
+
+    async fn restore(&self, id: Uuid) -> Result<Option<Review>, ReviewError> {
+        let mut reviews = self.write();
+        let mut deleted = self.deleted.write().unwrap_or_else(|e| e.into_inner());
+        let Some((review, _)) = deleted.get(&id) else {
+            return Ok(None);
+        };
+        if find_pair(&reviews, review.product_id, review.user_id).is_some() {
+            return Err(ReviewError::Conflict(DUPLICATE_REVIEW.to_string()));
+        }
+        let (review, _) = deleted.remove(&id).expect("id was just found");
+        reviews.insert(id, review.clone());
+        Ok(Some(review))
+    }
+
+    async fn purge_deleted(&self, deleted_before: DateTime<Utc>) -> Result<u64, ReviewError> {
+        let mut deleted = self.deleted.write().unwrap_or_else(|e| e.into_inner());
+        let purged: Vec<Uuid> = deleted
+            .iter()
+            .filter(|(_, (_, at))| *at < deleted_before)
+            .map(|(id, _)| *id)
+            .collect();
+        for id in &purged {
+            deleted.remove(id);
+        }
+        self.write_reports()
+            .retain(|r| !purged.contains(&r.review_id));
+        self.votes
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|(review_id, _), _| !purged.contains(review_id));
+        self.write_replies()
+            .retain(|review_id, _| !purged.contains(review_id));
+        self.write_revisions()
+            .retain(|r| !purged.contains(&r.review_id));
+        Ok(purged.len() as u64)
+    }
+
+    async fn find_aggregate(
+        &self,
+        product_id: Uuid,
+    ) -> Result<Option<ProductAggregate>, ReviewError> {
+        Ok(aggregates(self.read().values(), None).remove(&product_id))
+    }
+
+    async fn get_stats(&self) -> Result<(i64, f64), ReviewError> {
+        let reviews = self.read();
+        let approved = || {
+            reviews
+                .values()
+                .filter(|r| r.status == ReviewStatus::Approved)
+        };
+        let total = approved().count() as i64;
+        let sum: i64 = approved().map(|r| i64::from(r.rating)).sum();
+        let avg_rating = if total == 0 {
+            0.0
+        } else {
+            sum as f64 / total as f64
+        };
+        Ok((total, avg_rating))
+    }
+
+    async fn timeseries(
+        &self,
+        bucket: TimeBucket,
+        tz: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        product_id: Option<Uuid>,
+    ) -> Result<Vec<TimeseriesRow>, ReviewError> {
+        if !tz.eq_ignore_ascii_case("UTC") {
+            return Err(ReviewError::validation(
+                "The in-memory store only supports tz=UTC",
+            ));
+        }
+        let mut totals: HashMap<DateTime<Utc>, (i64, i64)> = HashMap::new();
+        for r in self.read().values() {
+            if r.status == ReviewStatus::Approved
+                && r.created_at >= from
+                && r.created_at < to
+                && product_id.is_none_or(|p| r.product_id == p)
+            {
+                let entry = totals
+                    .entry(utc_buckets::truncate(bucket, r.created_at))
+                    .or_default();
+                entry.0 += 1;
+                entry.1 += i64::from(r.rating);
+            }
+        }
+
+        Ok(utc_buckets::zero_filled(bucket, from, to, &totals))
+    }
+
+    async fn top_products(
+        &self,
+        order: LeaderboardOrder,
+        since: Option<DateTime<Utc>>,
+        min_reviews: i64,
+        prior_mean: f64,
+        prior_weight: f64,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<ProductAggregate>, ReviewError> {
+        let score = |a: &ProductAggregate| {
+            (prior_weight * prior_mean + a.rating_sum as f64)
+                / (prior_weight + a.review_count as f64)
+        };
+        let mut ranked: Vec<ProductAggregate> = aggregates(self.read().values(), since)
+            .into_values()
+            .filter(|a| a.review_count >= min_reviews.max(1))
+            .collect();
+        ranked.sort_by(|a, b| {
+            let by_score = score(a).total_cmp(&score(b));
+            let by_count = b.review_count.cmp(&a.review_count);
+            let primary = match order {
+                LeaderboardOrder::Best => by_score.reverse().then(by_count),
+                LeaderboardOrder::Worst => by_score.then(by_count),
+                LeaderboardOrder::MostReviewed => by_count.then(by_score.reverse()),
+            };
+            primary.then(a.product_id.cmp(&b.product_id))
+        });
+        Ok(ranked
+            .into_iter()
+            .skip(usize::try_from(offset).unwrap_or(0))
+            .take(usize::try_from(limit).unwrap_or(0))
+            .collect())
+    }
+
+    async fn reconcile_aggregates(
+        &self,
+        _repair: bool,
+    ) -> Result<(i64, Vec<(ProductAggregate, ProductAggregate)>), ReviewError> {
+        Ok((
+            aggregates(self.read().values(), None).len() as i64,
+            Vec::new(),
+        ))
+    }
+
+    async fn create_api_key(&self, key: &ApiKey) -> Result<(), ReviewError> {
+        self.api_keys
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(key.clone());
+        Ok(())
+    }
+
+    async fn find_api_keys(&self) -> Result<Vec<ApiKey>, ReviewError> {
+        Ok(self
+            .api_keys
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .rev()
+            .cloned()
+            .collect())
+    }
+
+    async fn find_api_key_by_hash(&self, key_hash: &str) -> Result<Option<ApiKey>, ReviewError> {
+        let keys = self.api_keys.read().unwrap_or_else(|e| e.into_inner());
+        Ok(keys.iter().find(|k| k.key_hash == key_hash).cloned())
+    }
+
+    async fn revoke_api_key(&self, id: Uuid) -> Result<Option<ApiKey>, ReviewError> {
+        let mut keys = self.api_keys.write().unwrap_or_else(|e| e.into_inner());
+        Ok(keys.iter_mut().find(|k| k.id == id).map(|k| {
+            k.revoked_at.get_or_insert_with(now);
+            k.clone()
+        }))
+    }
+
+    async fn claim_idempotency_key(
+        &self,
+        record: &IdempotencyRecord,
+    ) -> Result<Option<IdempotencyRecord>, ReviewError> {
+        let mut records = self
+            .idempotency_keys
+            .write()
+            .unwrap_or_else(|e| e.into_inner());
+        let id = (record.scope.clone(), record.key.clone());
+        match records.get(&id) {
+            Some(existing) if existing.expires_at > record.created_at => Ok(Some(existing.clone())),
+            _ => {
+                records.insert(id, record.clone());
+                Ok(None)
+            }
+        }
+    }
+
+    async fn complete_idempotency_key(
+        &self,
+        scope: &str,
+        key: &str,
+        status: i32,
+        body: &str,
+    ) -> Result<(), ReviewError> {
+        let mut records = self
+            .idempotency_keys
+            .write()
+            .unwrap_or_else(|e| e.into_inner());
+        if let Some(record) = records.get_mut(&(scope.to_string(), key.to_string())) {
+            record.response_status = Some(status);
+            record.response_body = Some(body.to_string());
+        }
+        Ok(())
+    }
+
+    async fn release_idempotency_key(&self, scope: &str, key: &str) -> Result<(), ReviewError> {
+        let mut records = self
+            .idempotency_keys
+            .write()
+            .unwrap_or_else(|e| e.into_inner());
+        records.remove(&(scope.to_string(), key.to_string()));
+        Ok(())
+    }
+
+    async fn purge_idempotency_keys(&self, now: DateTime<Utc>) -> Result<u64, ReviewError> {
+        let mut records = self
+            .idempotency_keys
+            .write()
+            .unwrap_or_else(|e| e.into_inner());
+        let before = records.len();
+        records.retain(|_, r| r.expires_at > now);
+        Ok((before - records.len()) as u64)
+    }
+}
+
+/// The current time at microsecond precision, as Postgres would store it, so cursors round-trip.
+fn now() -> DateTime<Utc> {
+    let now = Utc::now();
+    DateTime::from_timestamp_micros(now.timestamp_micros()).unwrap_or(now)
+}
+
+fn new_review(id: Uuid, body: &CreateReview, status: ReviewStatus) -> Review {
+    Review {
+        id,
+        product_id: body.product_id,
+        user_id: body.user_id,
+        rating: body.rating,
+        body: body.body.clone(),
+        created_at: now(),
+        status,
+        moderation_reason: None,
+        moderated_by: None,
+        moderated_at: None,
+        helpful_count: 0,
+        unhelpful_count: 0,
+        helpful_score: 0.0,
+        revision: 1,
+        edited_at: None,
+    }
+}
+
+/// Give `review` a new rating and body, and the status [`Screened::after_edit`] picks. Returns the
+/// version it replaced when the rating or body changed, for the caller to archive.
+fn rewrite(
+    review: &mut Review,
+    rating: i32,
+    body: Option<String>,
+    screened: Screened,
+) -> Option<ReviewRevision> {
+    let status = screened.after_edit(review.status, body != review.body);
+    if status != review.status {
+        review.status = status;
+        review.moderation_reason = None;
+        review.moderated_by = None;
+        review.moderated_at = None;
+    }
+    if rating == review.rating && body == review.body {
+        return None;
+    }
+    let now = now();
+    let replaced = ReviewRevision {
+        review_id: review.id,
+        revision: review.revision,
+        rating: review.rating,
+        body: std::mem::replace(&mut review.body, body),
+        created_at: review.edited_at.unwrap_or(review.created_at),
+        replaced_at: now,
+    };
+    review.rating = rating;
+    review.revision += 1;
+    review.edited_at = Some(now);
+    Some(replaced)
+}
+
+fn find_pair(reviews: &HashMap<Uuid, Review>, product_id: Uuid, user_id: Uuid) -> Option<Uuid> {
+    reviews
+        .values()
+        .find(|r| r.product_id == product_id && r.user_id == user_id)
+        .map(|r| r.id)
+}
+
+fn matches(filter: &ReviewFilter, r: &Review) -> bool {
+    let has_body = r.body.as_deref().is_some_and(|b| !b.is_empty());
+    filter.product_id.is_none_or(|v| r.product_id == v)
+        && filter.user_id.is_none_or(|v| r.user_id == v)
+        && filter.min_rating.is_none_or(|v| r.rating >= v)
+        && filter.max_rating.is_none_or(|v| r.rating <= v)
+        && filter.created_after.is_none_or(|v| r.created_at >= v)
+        && filter.created_before.is_none_or(|v| r.created_at < v)
+        && filter.has_body.is_none_or(|v| has_body == v)
+}
+
+/// Every column a listing may order by: rating, helpful score, creation time, id.
+type SortKey = (i32, f64, DateTime<Utc>, Uuid);
+
+fn key(r: &Review) -> SortKey {
+    (r.rating, r.helpful_score, r.created_at, r.id)
+}
+
+fn cursor_key(c: &Cursor) -> SortKey {
+    (c.rating, c.helpful_score, c.created_at, c.id)
+}
+
+fn compare(sort: ReviewSort, a: &Review, b: &Review) -> Ordering {
+    compare_key(sort, key(a), key(b))
+}
+
+/// Ordering of two rows in a listing; mirrors the SQL `ORDER BY` for each sort.
+fn compare_key(sort: ReviewSort, a: SortKey, b: SortKey) -> Ordering {
+    let newest_first = (b.2, b.3).cmp(&(a.2, a.3));
+    match sort {
+        ReviewSort::Newest => newest_first,
+        ReviewSort::Oldest => newest_first.reverse(),
+        ReviewSort::Highest => b.0.cmp(&a.0).then(newest_first),
+        ReviewSort::Lowest => a.0.cmp(&b.0).then(newest_first),
+        ReviewSort::MostHelpful => b.1.total_cmp(&a.1).then(newest_first),
+    }
+}
+
+/// Per-product aggregates over approved reviews created at or after `since`.
+fn aggregates<'a>(
+    reviews: impl Iterator<Item = &'a Review>,
+    since: Option<DateTime<Utc>>,
+) -> HashMap<Uuid, ProductAggregate> {
+    let mut out: HashMap<Uuid, ProductAggregate> = HashMap::new();
+    for r in reviews
+        .filter(|r| r.status == ReviewStatus::Approved && since.is_none_or(|s| r.created_at >= s))
+    {
+        let a = out.entry(r.product_id).or_insert_with(|| ProductAggregate {
+            product_id: r.product_id,
+            review_count: 0,
+            rating_sum: 0,
+            star_1: 0,
+            star_2: 0,
+            star_3: 0,
+            star_4: 0,
+            star_5: 0,
+            latest_review_at: None,
+        });
+        a.review_count += 1;
+        a.rating_sum += i64::from(r.rating);
+        match r.rating {
+            1 => a.star_1 += 1,
+            2 => a.star_2 += 1,
+            3 => a.star_3 += 1,
+            4 => a.star_4 += 1,
+            5 => a.star_5 += 1,
+            _ => {}
+        }
+        a.latest_review_at = a.latest_review_at.max(Some(r.created_at));
+    }
+    out
+}
@@ -1,5 +1,15 @@
 //! Data access layer. Repositories encapsulate all database queries.
 
+mod memory;
 mod review_repository;
+#[cfg(feature = "sqlite")]
+mod sqlite_repository;
+mod store;
+mod text_match;
+mod utc_buckets;
 
+pub use memory::InMemoryReviewStore;
 pub use review_repository::ReviewRepository;
+#[cfg(feature = "sqlite")]
+pub use sqlite_repository::SqliteReviewRepository;
+pub use store::ReviewStore;
@@ -1,9 +1,21 @@
 //! Review data access. All review-related SQL lives here.
 
-use sqlx::PgPool;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::postgres::PgRow;
+use sqlx::{PgConnection, PgPool, Postgres, QueryBuilder, Row};
 use uuid::Uuid;
 
-use crate::models::{CreateReview, Review};
+use super::ReviewStore;
+use crate::error::ReviewError;
+use crate::models::{
+    ApiKey, ApiScope, CreateReport, CreateReview, IdempotencyRecord, LeaderboardOrder,
+    ProductAggregate, Review, ReviewFilter, ReviewReply, ReviewReport, ReviewRevision, ReviewSort,
+    ReviewStatus, Screened, SearchRow, SearchTerm, SearchTerms, TimeBucket, TimeseriesRow,
+    UpdateReview,
+};
+use crate::pagination::Cursor;
+use crate::service::wilson_lower_bound;
 
 /// Repository for review persistence. No business logic, only queries.
 #[derive(Clone)]
@@ -17,52 +29,1382 @@ impl ReviewRepository {
     }
 
     pub async fn find_all(&self) -> Result<Vec<Review>, sqlx::Error> {
-        sqlx::query_as::<_, Review>(
-            "SELECT id, product_id, user_id, rating, body, created_at FROM reviews ORDER BY created_at DESC",
-        )
+        sqlx::query_as::<_, Review>(&format!(
+            "SELECT {REVIEW_COLUMNS} FROM reviews WHERE deleted_at IS NULL ORDER BY created_at DESC"
+        ))
         .fetch_all(&self.pool)
         .await
     }
 
+    /// One page of reviews in `status` matching `filter` in `sort` order, starting after `after` when given.
+    pub async fn find_page(
+        &self,
+        filter: &ReviewFilter,
+        status: ReviewStatus,
+        sort: ReviewSort,
+        after: Option<Cursor>,
+        limit: i64,
+    ) -> Result<Vec<Review>, sqlx::Error> {
+        let mut qb = QueryBuilder::<Postgres>::new(format!(
+            "SELECT {REVIEW_COLUMNS} FROM reviews WHERE deleted_at IS NULL AND status = "
+        ));
+        qb.push_bind(status.as_str());
+        push_filter(&mut qb, filter);
+        if let Some(cursor) = after {
+            push_keyset(&mut qb, sort, cursor);
+        }
+        qb.push(" ORDER BY ").push(order_by(sort));
+        qb.push(" LIMIT ").push_bind(limit);
+        qb.build_query_as::<Review>().fetch_all(&self.pool).await
+    }
+
+    /// A review in any status, unless deleted.
     pub async fn find_by_id(&self, id: Uuid) -> Result<Option<Review>, sqlx::Error> {
-        sqlx::query_as::<_, Review>(
-            "SELECT id, product_id, user_id, rating, body, created_at FROM reviews WHERE id = $1",
-        )
+        sqlx::query_as::<_, Review>(&format!(
+            "SELECT {REVIEW_COLUMNS} FROM reviews WHERE id = $1 AND deleted_at IS NULL"
+        ))
         .bind(id)
         .fetch_optional(&self.pool)
         .await
     }
 
-    pub async fn create(&self, id: Uuid, body: &CreateReview) -> Result<Review, sqlx::Error> {
-        sqlx::query(
-            "INSERT INTO reviews (id, product_id, user_id, rating, body) VALUES ($1, $2, $3, $4, $5)",
-        )
+    /// Approved reviews whose body matches every term, ranked by `ts_rank` and newest first on ties.
+    /// Snippets come from `ts_headline`, computed only for the returned page.
+    pub async fn search(
+        &self,
+        terms: &SearchTerms,
+        product_id: Option<Uuid>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<SearchRow>, sqlx::Error> {
+        sqlx::query_as::<_, SearchRow>(&format!(
+            "WITH q AS (SELECT to_tsquery('english', $1) AS query), \
+             hits AS ( \
+                 SELECT {REVIEW_COLUMNS}, ts_rank(body_tsv, q.query) AS rank \
+                 FROM reviews, q \
+                 WHERE body_tsv @@ q.query AND status = 'approved' AND deleted_at IS NULL AND ($2::uuid IS NULL OR product_id = $2) \
+                 ORDER BY rank DESC, created_at DESC, id DESC \
+                 LIMIT $3 OFFSET $4 \
+             ) \
+             SELECT hits.*, ts_headline('english', COALESCE(hits.body, ''), q.query, \
+                        'StartSel=<mark>, StopSel=</mark>, MaxWords=35, MinWords=15, MaxFragments=2') AS snippet \
+             FROM hits, q \
+             ORDER BY hits.rank DESC, hits.created_at DESC, hits.id DESC"
+        ))
+        .bind(to_tsquery(terms))
+        .bind(product_id)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Insert a review in `status` and, if approved, count it in its product's aggregate, atomically.
+    pub async fn create(
+        &self,
+        id: Uuid,
+        body: &CreateReview,
+        status: ReviewStatus,
+    ) -> Result<Review, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let review = sqlx::query_as::<_, Review>(&format!(
+            "INSERT INTO reviews (id, product_id, user_id, rating, body, status) VALUES ($1, $2, $3, $4, $5, $6) \
+             RETURNING {REVIEW_COLUMNS}"
+        ))
         .bind(id)
         .bind(body.product_id)
         .bind(body.user_id)
         .bind(body.rating)
         .bind(body.body.clone())
-        .execute(&self.pool)
+        .bind(status.as_str())
+        .fetch_one(&mut *tx)
         .await?;
+        apply_transition(&mut tx, &review, None).await?;
+        tx.commit().await?;
+        Ok(review)
+    }
 
-        self.find_by_id(id)
-            .await?
-            .ok_or_else(|| sqlx::Error::RowNotFound)
+    /// Insert a review, or replace rating and body of the existing review for the same
+    /// `(product_id, user_id)`. Either way the review ends up in `status`.
+    /// The existing row keeps its id and `created_at`, and its previous version is archived if it changed.
+    /// Returns the row and whether it was newly inserted.
+    pub async fn upsert(
+        &self,
+        id: Uuid,
+        body: &CreateReview,
+        screened: Screened,
+    ) -> Result<(Review, bool), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        // DO NOTHING waits out a concurrent insert of the same pair, so the lookup below sees it.
+        let inserted = sqlx::query_as::<_, Review>(&format!(
+            "INSERT INTO reviews (id, product_id, user_id, rating, body, status) VALUES ($1, $2, $3, $4, $5, $6) \
+             ON CONFLICT (product_id, user_id) WHERE deleted_at IS NULL DO NOTHING \
+             RETURNING {REVIEW_COLUMNS}"
+        ))
+        .bind(id)
+        .bind(body.product_id)
+        .bind(body.user_id)
+        .bind(body.rating)
+        .bind(body.body.clone())
+        .bind(screened.status.as_str())
+        .fetch_optional(&mut *tx)
+        .await?;
+        if let Some(review) = inserted {
+            apply_transition(&mut tx, &review, None).await?;
+            tx.commit().await?;
+            return Ok((review, true));
+        }
+
+        let old = sqlx::query_as::<_, Review>(&format!(
+            "SELECT {REVIEW_COLUMNS} FROM reviews WHERE product_id = $1 AND user_id = $2 AND deleted_at IS NULL FOR UPDATE"
+        ))
+        .bind(body.product_id)
+        .bind(body.user_id)
+        .fetch_one(&mut *tx)
+        .await?;
+        let review = rewrite(&mut tx, &old, body.rating, body.body.as_deref(), screened).await?;
+        tx.commit().await?;
+        Ok((review, false))
     }
 
-    pub async fn get_stats(&self) -> Result<(i64, f64), sqlx::Error> {
-        use sqlx::Row;
+    /// Apply a partial update, moving the review to the status `screened` allows after an edit and
+    /// archiving the previous version if the rating or body changed. `None` if the review does not exist.
+    pub async fn update(
+        &self,
+        id: Uuid,
+        patch: &UpdateReview,
+        screened: Screened,
+    ) -> Result<Option<Review>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let old = sqlx::query_as::<_, Review>(&format!(
+            "SELECT {REVIEW_COLUMNS} FROM reviews WHERE id = $1 AND deleted_at IS NULL FOR UPDATE"
+        ))
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some(old) = old else {
+            return Ok(None);
+        };
+        let rating = patch.rating.unwrap_or(old.rating);
+        let body = match &patch.body {
+            Some(body) => body.as_deref(),
+            None => old.body.as_deref(),
+        };
+        let review = rewrite(&mut tx, &old, rating, body, screened).await?;
+        tx.commit().await?;
+        Ok(Some(review))
+    }
+
+    /// Record a moderator's decision. Returns `None` if the review does not exist.
+    pub async fn moderate(
+        &self,
+        id: Uuid,
+        status: ReviewStatus,
+        reason: Option<&str>,
+        moderator: Uuid,
+    ) -> Result<Option<Review>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let old: Option<(i32, String)> = sqlx::query_as(
+            "SELECT rating, status FROM reviews WHERE id = $1 AND deleted_at IS NULL FOR UPDATE",
+        )
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some(old) = old else {
+            return Ok(None);
+        };
+        let review = sqlx::query_as::<_, Review>(&format!(
+            "UPDATE reviews SET status = $2, moderation_reason = $3, moderated_by = $4, moderated_at = NOW() \
+             WHERE id = $1 RETURNING {REVIEW_COLUMNS}"
+        ))
+        .bind(id)
+        .bind(status.as_str())
+        .bind(reason)
+        .bind(moderator)
+        .fetch_one(&mut *tx)
+        .await?;
+        apply_transition(&mut tx, &review, counted(old)).await?;
+        tx.commit().await?;
+        Ok(Some(review))
+    }
+
+    /// Set (`Some`) or withdraw (`None`) `voter`'s vote and refresh the review's vote totals and score.
+    /// `None` if the review does not exist.
+    pub async fn vote(
+        &self,
+        review_id: Uuid,
+        voter: Uuid,
+        helpful: Option<bool>,
+    ) -> Result<Option<Review>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        // Lock the review so concurrent votes recount one at a time.
+        let found: Option<(Uuid,)> = sqlx::query_as(
+            "SELECT id FROM reviews WHERE id = $1 AND deleted_at IS NULL FOR UPDATE",
+        )
+        .bind(review_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        if found.is_none() {
+            return Ok(None);
+        }
+        match helpful {
+            Some(helpful) => {
+                sqlx::query(
+                    "INSERT INTO review_votes (review_id, voter_id, helpful) VALUES ($1, $2, $3) \
+                     ON CONFLICT (review_id, voter_id) DO UPDATE SET helpful = EXCLUDED.helpful, updated_at = NOW()",
+                )
+                .bind(review_id)
+                .bind(voter)
+                .bind(helpful)
+                .execute(&mut *tx)
+                .await?;
+            }
+            None => {
+                sqlx::query("DELETE FROM review_votes WHERE review_id = $1 AND voter_id = $2")
+                    .bind(review_id)
+                    .bind(voter)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+        }
+        let (up, down): (i64, i64) = sqlx::query_as(
+            "SELECT COUNT(*) FILTER (WHERE helpful), COUNT(*) FILTER (WHERE NOT helpful) \
+             FROM review_votes WHERE review_id = $1",
+        )
+        .bind(review_id)
+        .fetch_one(&mut *tx)
+        .await?;
+        let review = sqlx::query_as::<_, Review>(&format!(
+            "UPDATE reviews SET helpful_count = $2, unhelpful_count = $3, helpful_score = $4 \
+             WHERE id = $1 RETURNING {REVIEW_COLUMNS}"
+        ))
+        .bind(review_id)
+        .bind(up as i32)
+        .bind(down as i32)
+        .bind(wilson_lower_bound(up as u64, down as u64))
+        .fetch_one(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(Some(review))
+    }
+
+    /// Record a report; hide the approved review when this is its `hide_at`-th. The review row is
+    /// locked first, so concurrent reports count one at a time and only one of them hides it.
+    pub async fn report(
+        &self,
+        id: Uuid,
+        review_id: Uuid,
+        reporter: Uuid,
+        report: &CreateReport,
+        hide_at: i64,
+    ) -> Result<Option<(ReviewReport, bool)>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let old: Option<(i32, String)> = sqlx::query_as(
+            "SELECT rating, status FROM reviews WHERE id = $1 AND deleted_at IS NULL FOR UPDATE",
+        )
+        .bind(review_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some(old) = old else {
+            return Ok(None);
+        };
+        let saved = sqlx::query_as::<_, ReviewReport>(&format!(
+            "INSERT INTO review_reports (id, review_id, reporter_id, reason, comment) VALUES ($1, $2, $3, $4, $5) \
+             RETURNING {REPORT_COLUMNS}"
+        ))
+        .bind(id)
+        .bind(review_id)
+        .bind(reporter)
+        .bind(report.reason.as_str())
+        .bind(&report.comment)
+        .fetch_one(&mut *tx)
+        .await?;
+        let (reports,): (i64,) =
+            sqlx::query_as("SELECT COUNT(*) FROM review_reports WHERE review_id = $1")
+                .bind(review_id)
+                .fetch_one(&mut *tx)
+                .await?;
+        let hide = reports == hide_at && old.1 == ReviewStatus::Approved.as_str();
+        if hide {
+            let review = sqlx::query_as::<_, Review>(&format!(
+                "UPDATE reviews SET status = 'hidden' WHERE id = $1 RETURNING {REVIEW_COLUMNS}"
+            ))
+            .bind(review_id)
+            .fetch_one(&mut *tx)
+            .await?;
+            apply_transition(&mut tx, &review, counted(old)).await?;
+        }
+        tx.commit().await?;
+        Ok(Some((saved, hide)))
+    }
+
+    /// Reports newest first, optionally only those against `review_id`.
+    pub async fn find_reports(
+        &self,
+        review_id: Option<Uuid>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<ReviewReport>, sqlx::Error> {
+        sqlx::query_as::<_, ReviewReport>(&format!(
+            "SELECT {REPORT_COLUMNS} FROM review_reports WHERE ($1::uuid IS NULL OR review_id = $1) \
+             ORDER BY created_at DESC, id DESC LIMIT $2 OFFSET $3"
+        ))
+        .bind(review_id)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Earlier versions of a review, newest first.
+    pub async fn find_revisions(
+        &self,
+        review_id: Uuid,
+    ) -> Result<Vec<ReviewRevision>, sqlx::Error> {
+        sqlx::query_as::<_, ReviewRevision>(&format!(
+            "SELECT {REVISION_COLUMNS} FROM review_revisions WHERE review_id = $1 ORDER BY revision DESC"
+        ))
+        .bind(review_id)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Replies to any of `review_ids`.
+    pub async fn find_replies(&self, review_ids: &[Uuid]) -> Result<Vec<ReviewReply>, sqlx::Error> {
+        sqlx::query_as::<_, ReviewReply>(&format!(
+            "SELECT {REPLY_COLUMNS} FROM review_replies WHERE review_id = ANY($1)"
+        ))
+        .bind(review_ids)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Replies in `status`, oldest first.
+    pub async fn find_reply_queue(
+        &self,
+        status: ReviewStatus,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<ReviewReply>, sqlx::Error> {
+        sqlx::query_as::<_, ReviewReply>(&format!(
+            "SELECT {REPLY_COLUMNS} FROM review_replies WHERE status = $1 \
+             ORDER BY created_at, review_id LIMIT $2 OFFSET $3"
+        ))
+        .bind(status.as_str())
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    pub async fn create_reply(
+        &self,
+        review_id: Uuid,
+        merchant: Uuid,
+        body: &str,
+        status: ReviewStatus,
+    ) -> Result<ReviewReply, sqlx::Error> {
+        sqlx::query_as::<_, ReviewReply>(&format!(
+            "INSERT INTO review_replies (review_id, merchant_id, body, status) VALUES ($1, $2, $3, $4) \
+             RETURNING {REPLY_COLUMNS}"
+        ))
+        .bind(review_id)
+        .bind(merchant)
+        .bind(body)
+        .bind(status.as_str())
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    /// Replace a reply's body and status. Returns `None` if the review has no reply.
+    pub async fn update_reply(
+        &self,
+        review_id: Uuid,
+        body: &str,
+        status: ReviewStatus,
+    ) -> Result<Option<ReviewReply>, sqlx::Error> {
+        sqlx::query_as::<_, ReviewReply>(&format!(
+            "UPDATE review_replies SET body = $2, status = $3, updated_at = NOW() \
+             WHERE review_id = $1 RETURNING {REPLY_COLUMNS}"
+        ))
+        .bind(review_id)
+        .bind(body)
+        .bind(status.as_str())
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    /// Record a moderator's decision on a reply. Returns `None` if the review has no reply.
+    pub async fn moderate_reply(
+        &self,
+        review_id: Uuid,
+        status: ReviewStatus,
+        reason: Option<&str>,
+        moderator: Uuid,
+    ) -> Result<Option<ReviewReply>, sqlx::Error> {
+        sqlx::query_as::<_, ReviewReply>(&format!(
+            "UPDATE review_replies SET status = $2, moderation_reason = $3, moderated_by = $4, moderated_at = NOW() \
+             WHERE review_id = $1 RETURNING {REPLY_COLUMNS}"
+        ))
+        .bind(review_id)
+        .bind(status.as_str())
+        .bind(reason)
+        .bind(moderator)
+        .fetch_optional(&self.pool)
+        .await
+    }
 
-        let row = sqlx::query("SELECT COUNT(*) as count FROM reviews")
-            .fetch_one(&self.pool)
+    pub async fn delete_reply(&self, review_id: Uuid) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM review_replies WHERE review_id = $1")
+            .bind(review_id)
+            .execute(&self.pool)
             .await?;
-        let total: i64 = row.get::<i64, _>("count");
+        Ok(result.rows_affected() > 0)
+    }
 
-        let row = sqlx::query("SELECT COALESCE(AVG(rating), 0)::float8 as avg FROM reviews")
-            .fetch_one(&self.pool)
+    /// Soft-delete a review and uncount it from its product's aggregate. Returns whether a live review was deleted.
+    pub async fn delete(&self, id: Uuid) -> Result<bool, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let deleted: Option<(Uuid, i32, String)> = sqlx::query_as(
+            "UPDATE reviews SET deleted_at = NOW() WHERE id = $1 AND deleted_at IS NULL \
+             RETURNING product_id, rating, status",
+        )
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some((product_id, rating, status)) = deleted else {
+            return Ok(false);
+        };
+        if let Some(rating) = counted((rating, status)) {
+            apply_delta(
+                &mut tx,
+                product_id,
+                &AggregateDelta::transition(Some(rating), None),
+                None,
+            )
             .await?;
-        let avg_rating: f64 = row.get::<f64, _>("avg");
+            refresh_latest(&mut tx, product_id).await?;
+        }
+        tx.commit().await?;
+        Ok(true)
+    }
 
+    /// Undo a soft delete and count the review again. Returns `None` if no deleted review has this id.
+    pub async fn restore(&self, id: Uuid) -> Result<Option<Review>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let review = sqlx::query_as::<_, Review>(&format!(
+            "UPDATE reviews SET deleted_at = NULL WHERE id = $1 AND deleted_at IS NOT NULL RETURNING {REVIEW_COLUMNS}"
+        ))
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some(review) = review else {
+            return Ok(None);
+        };
+        apply_transition(&mut tx, &review, None).await?;
+        tx.commit().await?;
+        Ok(Some(review))
+    }
+
+    /// Hard-delete reviews soft-deleted before `deleted_before`, with everything that references them.
+    /// Returns how many were removed.
+    pub async fn purge_deleted(&self, deleted_before: DateTime<Utc>) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM reviews WHERE deleted_at < $1")
+            .bind(deleted_before)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Stored aggregate for one product, or `None` if it has never been reviewed.
+    pub async fn find_aggregate(
+        &self,
+        product_id: Uuid,
+    ) -> Result<Option<ProductAggregate>, sqlx::Error> {
+        sqlx::query_as::<_, ProductAggregate>(
+            "SELECT product_id, review_count, rating_sum, star_1, star_2, star_3, star_4, star_5, latest_review_at \
+             FROM product_rating_aggregates WHERE product_id = $1",
+        )
+        .bind(product_id)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    /// Global review count and mean rating, summed from the per-product aggregates.
+    pub async fn get_stats(&self) -> Result<(i64, f64), sqlx::Error> {
+        let (total, sum): (i64, i64) = sqlx::query_as(
+            "SELECT COALESCE(SUM(review_count), 0)::int8, COALESCE(SUM(rating_sum), 0)::int8 \
+             FROM product_rating_aggregates",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        let avg_rating = if total == 0 {
+            0.0
+        } else {
+            sum as f64 / total as f64
+        };
         Ok((total, avg_rating))
     }
+
+    /// Review counts and mean ratings per bucket over `[from, to)`, one row per bucket even when empty.
+    /// Buckets are truncated in local time of `tz`, so days and months follow that zone's DST rules.
+    pub async fn timeseries(
+        &self,
+        bucket: TimeBucket,
+        tz: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        product_id: Option<Uuid>,
+    ) -> Result<Vec<TimeseriesRow>, sqlx::Error> {
+        sqlx::query_as::<_, TimeseriesRow>(
+            "WITH counts AS ( \
+                 SELECT date_trunc($1, created_at AT TIME ZONE $2) AS local_start, \
+                        COUNT(*) AS review_count, AVG(rating)::float8 AS avg_rating \
+                 FROM reviews \
+                 WHERE status = 'approved' AND deleted_at IS NULL AND created_at >= $3 AND created_at < $4 \
+                   AND ($5::uuid IS NULL OR product_id = $5) \
+                 GROUP BY 1 \
+             ), series AS ( \
+                 SELECT generate_series( \
+                     date_trunc($1, $3 AT TIME ZONE $2), \
+                     date_trunc($1, ($4 - interval '1 microsecond') AT TIME ZONE $2), \
+                     ('1 ' || $1)::interval \
+                 ) AS local_start \
+             ) \
+             SELECT s.local_start AT TIME ZONE $2 AS bucket_start, \
+                    COALESCE(c.review_count, 0) AS review_count, c.avg_rating \
+             FROM series s LEFT JOIN counts c ON c.local_start = s.local_start \
+             ORDER BY s.local_start",
+        )
+        .bind(bucket.as_str())
+        .bind(tz)
+        .bind(from)
+        .bind(to)
+        .bind(product_id)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Products ranked by `order`, as aggregates over reviews created at or after `since`
+    /// (all time, read from `product_rating_aggregates`, when `since` is `None`).
+    /// Scores are Bayesian averages with the given prior.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn top_products(
+        &self,
+        order: LeaderboardOrder,
+        since: Option<DateTime<Utc>>,
+        min_reviews: i64,
+        prior_mean: f64,
+        prior_weight: f64,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<ProductAggregate>, sqlx::Error> {
+        let mut qb = QueryBuilder::<Postgres>::new("WITH source AS (");
+        match since {
+            Some(since) => {
+                qb.push(
+                    "SELECT product_id, COUNT(*)::int8 AS review_count, SUM(rating)::int8 AS rating_sum, \
+                     COUNT(*) FILTER (WHERE rating = 1) AS star_1, COUNT(*) FILTER (WHERE rating = 2) AS star_2, \
+                     COUNT(*) FILTER (WHERE rating = 3) AS star_3, COUNT(*) FILTER (WHERE rating = 4) AS star_4, \
+                     COUNT(*) FILTER (WHERE rating = 5) AS star_5, MAX(created_at) AS latest_review_at \
+                     FROM reviews WHERE status = 'approved' AND deleted_at IS NULL AND created_at >= ",
+                )
+                .push_bind(since)
+                .push(" GROUP BY product_id");
+            }
+            None => {
+                qb.push(
+                    "SELECT product_id, review_count, rating_sum, star_1, star_2, star_3, star_4, star_5, \
+                     latest_review_at FROM product_rating_aggregates",
+                );
+            }
+        }
+        qb.push("), scored AS (SELECT *, (")
+            .push_bind(prior_weight)
+            .push(" * ")
+            .push_bind(prior_mean)
+            .push(" + rating_sum) / (")
+            .push_bind(prior_weight)
+            .push(" + review_count) AS score FROM source WHERE review_count >= ")
+            .push_bind(min_reviews.max(1))
+            .push(") SELECT product_id, review_count, rating_sum, star_1, star_2, star_3, star_4, star_5, \
+                   latest_review_at FROM scored ORDER BY ")
+            .push(match order {
+                LeaderboardOrder::Best => "score DESC, review_count DESC, product_id",
+                LeaderboardOrder::Worst => "score ASC, review_count DESC, product_id",
+                LeaderboardOrder::MostReviewed => "review_count DESC, score DESC, product_id",
+            })
+            .push(" LIMIT ")
+            .push_bind(limit)
+            .push(" OFFSET ")
+            .push_bind(offset);
+        qb.build_query_as::<ProductAggregate>()
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    /// Compare stored aggregates with values recomputed from `reviews`.
+    /// Returns the number of products examined and `(expected, stored)` for each product that differs.
+    /// With `repair`, the table is then rebuilt from `reviews`.
+    ///
+    /// Holds an EXCLUSIVE lock on the aggregate table throughout, so concurrent review writes wait
+    /// and then apply their deltas on top of the result.
+    pub async fn reconcile_aggregates(
+        &self,
+        repair: bool,
+    ) -> Result<(i64, Vec<(ProductAggregate, ProductAggregate)>), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("LOCK TABLE product_rating_aggregates IN EXCLUSIVE MODE")
+            .execute(&mut *tx)
+            .await?;
+
+        let rows = sqlx::query(&format!(
+            "WITH expected AS ({EXPECTED_AGGREGATES}) \
+             SELECT COALESCE(e.product_id, a.product_id) AS product_id, \
+                    COALESCE(e.review_count, 0) AS e_count, COALESCE(e.rating_sum, 0) AS e_sum, \
+                    COALESCE(e.star_1, 0) AS e_1, COALESCE(e.star_2, 0) AS e_2, COALESCE(e.star_3, 0) AS e_3, \
+                    COALESCE(e.star_4, 0) AS e_4, COALESCE(e.star_5, 0) AS e_5, e.latest_review_at AS e_latest, \
+                    COALESCE(a.review_count, 0) AS a_count, COALESCE(a.rating_sum, 0) AS a_sum, \
+                    COALESCE(a.star_1, 0) AS a_1, COALESCE(a.star_2, 0) AS a_2, COALESCE(a.star_3, 0) AS a_3, \
+                    COALESCE(a.star_4, 0) AS a_4, COALESCE(a.star_5, 0) AS a_5, a.latest_review_at AS a_latest, \
+                    COUNT(*) OVER () AS checked \
+             FROM expected e FULL OUTER JOIN product_rating_aggregates a ON a.product_id = e.product_id"
+        ))
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let mut checked = 0;
+        let mut drift = Vec::new();
+        for row in &rows {
+            checked = row.try_get("checked")?;
+            let expected = aggregate_from_row(row, "e")?;
+            let stored = aggregate_from_row(row, "a")?;
+            if expected.counts() != stored.counts() {
+                drift.push((expected, stored));
+            }
+        }
+
+        if repair {
+            sqlx::query("DELETE FROM product_rating_aggregates")
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query(&format!(
+                "INSERT INTO product_rating_aggregates \
+                 (product_id, review_count, rating_sum, star_1, star_2, star_3, star_4, star_5, latest_review_at) \
+                 {EXPECTED_AGGREGATES}"
+            ))
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok((checked, drift))
+    }
+
+    pub async fn create_api_key(&self, key: &ApiKey) -> Result<(), sqlx::Error> {
+        let scopes: Vec<&str> = key.scopes.iter().map(|s| s.as_str()).collect();
+        sqlx::query(
+            "INSERT INTO api_keys (id, name, prefix, key_hash, scopes, created_at, expires_at, revoked_at) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+        )
+        .bind(key.id)
+        .bind(&key.name)
+        .bind(&key.prefix)
+        .bind(&key.key_hash)
+        .bind(scopes)
+        .bind(key.created_at)
+        .bind(key.expires_at)
+        .bind(key.revoked_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Every API key, newest first.
+    pub async fn find_api_keys(&self) -> Result<Vec<ApiKey>, sqlx::Error> {
+        let rows = sqlx::query(&format!(
+            "SELECT {API_KEY_COLUMNS} FROM api_keys ORDER BY created_at DESC, id DESC"
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+        rows.iter().map(api_key_from_row).collect()
+    }
+
+    pub async fn find_api_key_by_hash(
+        &self,
+        key_hash: &str,
+    ) -> Result<Option<ApiKey>, sqlx::Error> {
+        let row = sqlx::query(&format!(
+            "SELECT {API_KEY_COLUMNS} FROM api_keys WHERE key_hash = $1"
+        ))
+        .bind(key_hash)
+        .fetch_optional(&self.pool)
+        .await?;
+        row.as_ref().map(api_key_from_row).transpose()
+    }
+
+    /// Revoke an API key; an earlier revocation stands. Returns `None` if no key has this id.
+    pub async fn revoke_api_key(&self, id: Uuid) -> Result<Option<ApiKey>, sqlx::Error> {
+        let row = sqlx::query(&format!(
+            "UPDATE api_keys SET revoked_at = COALESCE(revoked_at, NOW()) WHERE id = $1 RETURNING {API_KEY_COLUMNS}"
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+        row.as_ref().map(api_key_from_row).transpose()
+    }
+
+    /// Claim an idempotency key unless an unexpired record holds it; returns that record if so.
+    pub async fn claim_idempotency_key(
+        &self,
+        record: &IdempotencyRecord,
+    ) -> Result<Option<IdempotencyRecord>, sqlx::Error> {
+        loop {
+            let claimed = sqlx::query(
+                "INSERT INTO idempotency_keys (scope, key, request_hash, response_status, response_body, created_at, expires_at) \
+                 VALUES ($1, $2, $3, $4, $5, $6, $7) \
+                 ON CONFLICT (scope, key) DO UPDATE SET request_hash = EXCLUDED.request_hash, response_status = EXCLUDED.response_status, \
+                 response_body = EXCLUDED.response_body, created_at = EXCLUDED.created_at, expires_at = EXCLUDED.expires_at \
+                 WHERE idempotency_keys.expires_at <= EXCLUDED.created_at \
+                 RETURNING scope",
+            )
+            .bind(&record.scope)
+            .bind(&record.key)
+            .bind(&record.request_hash)
+            .bind(record.response_status)
+            .bind(&record.response_body)
+            .bind(record.created_at)
+            .bind(record.expires_at)
+            .fetch_optional(&self.pool)
+            .await?;
+            if claimed.is_some() {
+                return Ok(None);
+            }
+            let row = sqlx::query(&format!(
+                "SELECT {IDEMPOTENCY_COLUMNS} FROM idempotency_keys WHERE scope = $1 AND key = $2"
+            ))
+            .bind(&record.scope)
+            .bind(&record.key)
+            .fetch_optional(&self.pool)
+            .await?;
+            // The holder may release the key between the two statements; then claim it again
+            // rather than report a claim this request does not hold.
+            if let Some(row) = row {
+                return idempotency_from_row(&row).map(Some);
+            }
+        }
+    }
+
+    pub async fn complete_idempotency_key(
+        &self,
+        scope: &str,
+        key: &str,
+        status: i32,
+        body: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE idempotency_keys SET response_status = $3, response_body = $4 WHERE scope = $1 AND key = $2")
+            .bind(scope)
+            .bind(key)
+            .bind(status)
+            .bind(body)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn release_idempotency_key(&self, scope: &str, key: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM idempotency_keys WHERE scope = $1 AND key = $2")
+            .bind(scope)
+            .bind(key)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn purge_idempotency_keys(&self, now: DateTime<Utc>) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM idempotency_keys WHERE expires_at <= $1")
+            .bind(now)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+}
+
+#[async_trait]
+impl ReviewStore for ReviewRepository {
+    async fn find_all(&self) -> Result<Vec<Review>, ReviewError> {
+        Ok(ReviewRepository::find_all(self).await?)
+    }
+
+    async fn find_page(
+        &self,
+        filter: &ReviewFilter,
+        status: ReviewStatus,
+        sort: ReviewSort,
+        after: Option<Cursor>,
+        limit: i64,
+    ) -> Result<Vec<Review>, ReviewError> {
+        Ok(ReviewRepository::find_page(self, filter, status, sort, after, limit).await?)
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> Result<Option<Review>, ReviewError> {
+        Ok(ReviewRepository::find_by_id(self, id).await?)
+    }
+
+    async fn search(
+        &self,
+        terms: &SearchTerms,
+        product_id: Option<Uuid>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<SearchRow>, ReviewError> {
+        Ok(ReviewRepository::search(self, terms, product_id, limit, offset).await?)
+    }
+
+    async fn create(
+        &self,
+        id: Uuid,
+        body: &CreateReview,
+        status: ReviewStatus,
+    ) -> Result<Review, ReviewError> {
+        Ok(ReviewRepository::create(self, id, body, status).await?)
+    }
+
+    async fn upsert(
+        &self,
+        id: Uuid,
+        body: &CreateReview,
+        screened: Screened,
+    ) -> Result<(Review, bool), ReviewError> {
+        Ok(ReviewRepository::upsert(self, id, body, screened).await?)
+    }
+
+    async fn update(
+        &self,
+        id: Uuid,
+        patch: &UpdateReview,
+        screened: Screened,
+    ) -> Result<Option<Review>, ReviewError> {
+        Ok(ReviewRepository::update(self, id, patch, screened).await?)
+    }
+
+    async fn moderate(
+        &self,
+        id: Uuid,
+        status: ReviewStatus,
+        reason: Option<&str>,
+        moderator: Uuid,
+    ) -> Result<Option<Review>, ReviewError> {
+        Ok(ReviewRepository::moderate(self, id, status, reason, moderator).await?)
+    }
+
+    async fn vote(
+        &self,
+        review_id: Uuid,
+        voter: Uuid,
+        helpful: Option<bool>,
+    ) -> Result<Option<Review>, ReviewError> {
+        Ok(ReviewRepository::vote(self, review_id, voter, helpful).await?)
+    }
+
+    async fn report(
+        &self,
+        id: Uuid,
+        review_id: Uuid,
+        reporter: Uuid,
+        report: &CreateReport,
+        hide_at: i64,
+    ) -> Result<Option<(ReviewReport, bool)>, ReviewError> {
+        Ok(ReviewRepository::report(self, id, review_id, reporter, report, hide_at).await?)
+    }
+
+    async fn find_reports(
+        &self,
+        review_id: Option<Uuid>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<ReviewReport>, ReviewError> {
+        Ok(ReviewRepository::find_reports(self, review_id, limit, offset).await?)
+    }
+
+    async fn find_revisions(&self, review_id: Uuid) -> Result<Vec<ReviewRevision>, ReviewError> {
+        Ok(ReviewRepository::find_revisions(self, review_id).await?)
+    }
+
+    async fn find_replies(&self, review_ids: &[Uuid]) -> Result<Vec<ReviewReply>, ReviewError> {
+        Ok(ReviewRepository::find_replies(self, review_ids).await?)
+    }
+
+    async fn find_reply_queue(
+        &self,
+        status: ReviewStatus,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<ReviewReply>, ReviewError> {
+        Ok(ReviewRepository::find_reply_queue(self, status, limit, offset).await?)
+    }
+
+    async fn create_reply(
+        &self,
+        review_id: Uuid,
+        merchant: Uuid,
+        body: &str,
+        status: ReviewStatus,
+    ) -> Result<ReviewReply, ReviewError> {
+        Ok(ReviewRepository::create_reply(self, review_id, merchant, body, status).await?)
+    }
+
+    async fn update_reply(
+        &self,
+        review_id: Uuid,
+        body: &str,
+        status: ReviewStatus,
+    ) -> Result<Option<ReviewReply>, ReviewError> {
+        Ok(ReviewRepository::update_reply(self, review_id, body, status).await?)
+    }
+
+    async fn moderate_reply(
+        &self,
+        review_id: Uuid,
+        status: ReviewStatus,
+        reason: Option<&str>,
+        moderator: Uuid,
+    ) -> Result<Option<ReviewReply>, ReviewError> {
+        Ok(ReviewRepository::moderate_reply(self, review_id, status, reason, moderator).await?)
+    }
+
+    async fn delete_reply(&self, review_id: Uuid) -> Result<bool, ReviewError> {
+        Ok(ReviewRepository::delete_reply(self, review_id).await?)
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<bool, ReviewError> {
+        Ok(ReviewRepository::delete(self, id).await?)
+    }
+
+    async fn restore(&self, id: Uuid) -> Result<Option<Review>, ReviewError> {
+        Ok(ReviewRepository::restore(self, id).await?)
+    }
+
+    async fn purge_deleted(&self, deleted_before: DateTime<Utc>) -> Result<u64, ReviewError> {
+        Ok(ReviewRepository::purge_deleted(self, deleted_before).await?)
+    }
+
+    async fn find_aggregate(
+        &self,
+        product_id: Uuid,
+    ) -> Result<Option<ProductAggregate>, ReviewError> {
+        Ok(ReviewRepository::find_aggregate(self, product_id).await?)
+    }
+
+    async fn get_stats(&self) -> Result<(i64, f64), ReviewError> {
+        Ok(ReviewRepository::get_stats(self).await?)
+    }
+
+    async fn timeseries(
+        &self,
+        bucket: TimeBucket,
+        tz: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        product_id: Option<Uuid>,
+    ) -> Result<Vec<TimeseriesRow>, ReviewError> {
+        Ok(ReviewRepository::timeseries(self, bucket, tz, from, to, product_id).await?)
+    }
+
+    async fn top_products(
+        &self,
+        order: LeaderboardOrder,
+        since: Option<DateTime<Utc>>,
+        min_reviews: i64,
+        prior_mean: f64,
+        prior_weight: f64,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<ProductAggregate>, ReviewError> {
+        Ok(ReviewRepository::top_products(
+            self,
+            order,
+            since,
+            min_reviews,
+            prior_mean,
+            prior_weight,
+            limit,
+            offset,
+        )
+        .await?)
+    }
+
+    async fn reconcile_aggregates(
+        &self,
+        repair: bool,
+    ) -> Result<(i64, Vec<(ProductAggregate, ProductAggregate)>), ReviewError> {
+        Ok(ReviewRepository::reconcile_aggregates(self, repair).await?)
+    }
+
+    async fn create_api_key(&self, key: &ApiKey) -> Result<(), ReviewError> {
+        Ok(ReviewRepository::create_api_key(self, key).await?)
+    }
+
+    async fn find_api_keys(&self) -> Result<Vec<ApiKey>, ReviewError> {
+        Ok(ReviewRepository::find_api_keys(self).await?)
+    }
+
+    async fn find_api_key_by_hash(&self, key_hash: &str) -> Result<Option<ApiKey>, ReviewError> {
+        Ok(ReviewRepository::find_api_key_by_hash(self, key_hash).await?)
+    }
+
+    async fn revoke_api_key(&self, id: Uuid) -> Result<Option<ApiKey>, ReviewError> {
+        Ok(ReviewRepository::revoke_api_key(self, id).await?)
+    }
+
+    async fn claim_idempotency_key(
+        &self,
+        record: &IdempotencyRecord,
+    ) -> Result<Option<IdempotencyRecord>, ReviewError> {
+        Ok(ReviewRepository::claim_idempotency_key(self, record).await?)
+    }
+
+    async fn complete_idempotency_key(
+        &self,
+        scope: &str,
+        key: &str,
+        status: i32,
+        body: &str,
+    ) -> Result<(), ReviewError> {
+        Ok(ReviewRepository::complete_idempotency_key(self, scope, key, status, body).await?)
+    }
+
+    async fn release_idempotency_key(&self, scope: &str, key: &str) -> Result<(), ReviewError> {
+        Ok(ReviewRepository::release_idempotency_key(self, scope, key).await?)
+    }
+
+    async fn purge_idempotency_keys(&self, now: DateTime<Utc>) -> Result<u64, ReviewError> {
+        Ok(ReviewRepository::purge_idempotency_keys(self, now).await?)
+    }
+}
+
+const REVIEW_COLUMNS: &str =
+    "id, product_id, user_id, rating, body, created_at, status, moderation_reason, moderated_by, moderated_at, \
+     helpful_count, unhelpful_count, helpful_score, revision, edited_at";
+const REVISION_COLUMNS: &str = "review_id, revision, rating, body, created_at, replaced_at";
+const REPORT_COLUMNS: &str = "id, review_id, reporter_id, reason, comment, created_at";
+const REPLY_COLUMNS: &str =
+    "review_id, merchant_id, body, status, moderation_reason, moderated_by, moderated_at, created_at, updated_at";
+const API_KEY_COLUMNS: &str =
+    "id, name, prefix, key_hash, scopes, created_at, expires_at, revoked_at";
+const IDEMPOTENCY_COLUMNS: &str =
+    "scope, key, request_hash, response_status, response_body, created_at, expires_at";
+
+/// Aggregates recomputed from scratch, in `product_rating_aggregates` column order.
+const EXPECTED_AGGREGATES: &str =
+    "SELECT product_id, COUNT(*)::int8 AS review_count, SUM(rating)::int8 AS rating_sum, \
+     COUNT(*) FILTER (WHERE rating = 1) AS star_1, COUNT(*) FILTER (WHERE rating = 2) AS star_2, \
+     COUNT(*) FILTER (WHERE rating = 3) AS star_3, COUNT(*) FILTER (WHERE rating = 4) AS star_4, \
+     COUNT(*) FILTER (WHERE rating = 5) AS star_5, MAX(created_at) AS latest_review_at \
+     FROM reviews WHERE status = 'approved' AND deleted_at IS NULL GROUP BY product_id";
+
+fn aggregate_from_row(row: &PgRow, prefix: &str) -> Result<ProductAggregate, sqlx::Error> {
+    let col = |name: &str| format!("{}_{}", prefix, name);
+    Ok(ProductAggregate {
+        product_id: row.try_get("product_id")?,
+        review_count: row.try_get(col("count").as_str())?,
+        rating_sum: row.try_get(col("sum").as_str())?,
+        star_1: row.try_get(col("1").as_str())?,
+        star_2: row.try_get(col("2").as_str())?,
+        star_3: row.try_get(col("3").as_str())?,
+        star_4: row.try_get(col("4").as_str())?,
+        star_5: row.try_get(col("5").as_str())?,
+        latest_review_at: row.try_get(col("latest").as_str())?,
+    })
+}
+
+fn idempotency_from_row(row: &PgRow) -> Result<IdempotencyRecord, sqlx::Error> {
+    Ok(IdempotencyRecord {
+        scope: row.try_get("scope")?,
+        key: row.try_get("key")?,
+        request_hash: row.try_get("request_hash")?,
+        response_status: row.try_get("response_status")?,
+        response_body: row.try_get("response_body")?,
+        created_at: row.try_get("created_at")?,
+        expires_at: row.try_get("expires_at")?,
+    })
+}
+
+fn api_key_from_row(row: &PgRow) -> Result<ApiKey, sqlx::Error> {
+    let scopes: Vec<String> = row.try_get("scopes")?;
+    Ok(ApiKey {
+        id: row.try_get("id")?,
+        name: row.try_get("name")?,
+        prefix: row.try_get("prefix")?,
+        key_hash: row.try_get("key_hash")?,
+        scopes: scopes
+            .iter()
+            .map(|s| s.parse::<ApiScope>())
+            .collect::<Result<_, _>>()
+            .map_err(|e| sqlx::Error::Decode(e.into()))?,
+        created_at: row.try_get("created_at")?,
+        expires_at: row.try_get("expires_at")?,
+        revoked_at: row.try_get("revoked_at")?,
+    })
+}
+
+/// The rating a review in `(rating, status)` contributes to its product's aggregate; only approved reviews count.
+pub(super) fn counted((rating, status): (i32, String)) -> Option<i32> {
+    (status == ReviewStatus::Approved.as_str()).then_some(rating)
+}
+
+/// Give `old` a new rating and body, and the status [`Screened::after_edit`] picks. When the rating
+/// or body changes, `old` is archived in `review_revisions` and the review moves to the next
+/// revision. A status change clears the moderation stamp. The aggregate follows.
+async fn rewrite(
+    conn: &mut PgConnection,
+    old: &Review,
+    rating: i32,
+    body: Option<&str>,
+    screened: Screened,
+) -> Result<Review, sqlx::Error> {
+    let status = screened.after_edit(old.status, body != old.body.as_deref());
+    let edited = rating != old.rating || body != old.body.as_deref();
+    if edited {
+        sqlx::query(
+            "INSERT INTO review_revisions (review_id, revision, rating, body, created_at) VALUES ($1, $2, $3, $4, $5)",
+        )
+        .bind(old.id)
+        .bind(old.revision)
+        .bind(old.rating)
+        .bind(&old.body)
+        .bind(old.edited_at.unwrap_or(old.created_at))
+        .execute(&mut *conn)
+        .await?;
+    }
+    let review = sqlx::query_as::<_, Review>(&format!(
+        "UPDATE reviews SET rating = $2, body = $3, status = $4, \
+         moderation_reason = CASE WHEN status = $4 THEN moderation_reason END, \
+         moderated_by = CASE WHEN status = $4 THEN moderated_by END, \
+         moderated_at = CASE WHEN status = $4 THEN moderated_at END, \
+         revision = revision + CASE WHEN $5 THEN 1 ELSE 0 END, edited_at = CASE WHEN $5 THEN NOW() ELSE edited_at END \
+         WHERE id = $1 RETURNING {REVIEW_COLUMNS}"
+    ))
+    .bind(old.id)
+    .bind(rating)
+    .bind(body)
+    .bind(status.as_str())
+    .bind(edited)
+    .fetch_one(&mut *conn)
+    .await?;
+    let before = (old.status == ReviewStatus::Approved).then_some(old.rating);
+    apply_transition(conn, &review, before).await?;
+    Ok(review)
+}
+
+/// Bring the aggregate in line after `review` was written, given what it counted for before.
+async fn apply_transition(
+    conn: &mut PgConnection,
+    review: &Review,
+    before: Option<i32>,
+) -> Result<(), sqlx::Error> {
+    let after = (review.status == ReviewStatus::Approved).then_some(review.rating);
+    if before == after {
+        return Ok(());
+    }
+    let latest = after.map(|_| review.created_at);
+    apply_delta(
+        &mut *conn,
+        review.product_id,
+        &AggregateDelta::transition(before, after),
+        latest,
+    )
+    .await?;
+    if before.is_some() && after.is_none() {
+        refresh_latest(conn, review.product_id).await?;
+    }
+    Ok(())
+}
+
+/// Net change to one product's aggregate caused by a single review write.
+#[derive(Debug, Default)]
+pub(super) struct AggregateDelta {
+    pub(super) count: i64,
+    pub(super) sum: i64,
+    pub(super) stars: [i64; 5],
+}
+
+impl AggregateDelta {
+    /// Change from a review counting as `before` to counting as `after`; `None` means not counted.
+    pub(super) fn transition(before: Option<i32>, after: Option<i32>) -> Self {
+        let mut d = Self::default();
+        if let Some(rating) = before {
+            d.shift(rating, -1);
+        }
+        if let Some(rating) = after {
+            d.shift(rating, 1);
+        }
+        d
+    }
+
+    fn shift(&mut self, rating: i32, by: i64) {
+        self.count += by;
+        self.sum += by * i64::from(rating);
+        if let Some(star) = usize::try_from(rating - 1)
+            .ok()
+            .and_then(|i| self.stars.get_mut(i))
+        {
+            *star += by;
+        }
+    }
+}
+
+/// Add `delta` to the product's aggregate row, creating it on first use.
+/// Increments are relative, so concurrent writers to the same product commute.
+async fn apply_delta(
+    conn: &mut PgConnection,
+    product_id: Uuid,
+    delta: &AggregateDelta,
+    latest: Option<DateTime<Utc>>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO product_rating_aggregates AS a \
+         (product_id, review_count, rating_sum, star_1, star_2, star_3, star_4, star_5, latest_review_at) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) \
+         ON CONFLICT (product_id) DO UPDATE SET \
+             review_count = a.review_count + EXCLUDED.review_count, \
+             rating_sum = a.rating_sum + EXCLUDED.rating_sum, \
+             star_1 = a.star_1 + EXCLUDED.star_1, \
+             star_2 = a.star_2 + EXCLUDED.star_2, \
+             star_3 = a.star_3 + EXCLUDED.star_3, \
+             star_4 = a.star_4 + EXCLUDED.star_4, \
+             star_5 = a.star_5 + EXCLUDED.star_5, \
+             latest_review_at = GREATEST(a.latest_review_at, EXCLUDED.latest_review_at)",
+    )
+    .bind(product_id)
+    .bind(delta.count)
+    .bind(delta.sum)
+    .bind(delta.stars[0])
+    .bind(delta.stars[1])
+    .bind(delta.stars[2])
+    .bind(delta.stars[3])
+    .bind(delta.stars[4])
+    .bind(latest)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// Recompute `latest_review_at` after a removal, which a relative delta cannot express.
+async fn refresh_latest(conn: &mut PgConnection, product_id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE product_rating_aggregates \
+         SET latest_review_at = (SELECT MAX(created_at) FROM reviews WHERE product_id = $1 AND status = 'approved' AND deleted_at IS NULL) \
+         WHERE product_id = $1",
+    )
+    .bind(product_id)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// Render terms as `to_tsquery` input: terms are ANDed, phrases use `<->`, prefixes `:*`.
+/// Terms hold only alphanumeric words, so no tsquery operator can come from user input.
+fn to_tsquery(terms: &SearchTerms) -> String {
+    terms
+        .0
+        .iter()
+        .map(|term| match term {
+            SearchTerm::Word(w) => w.clone(),
+            SearchTerm::Prefix(w) => format!("{}:*", w),
+            SearchTerm::Phrase(ws) => format!("({})", ws.join(" <-> ")),
+        })
+        .collect::<Vec<_>>()
+        .join(" & ")
+}
+
+/// Append `AND ...` for each set field. Values are always bound, never interpolated.
+/// Equality on `product_id` / `user_id` lets the planner use their single-column indexes.
+fn push_filter(qb: &mut QueryBuilder<'_, Postgres>, filter: &ReviewFilter) {
+    if let Some(product_id) = filter.product_id {
+        qb.push(" AND product_id = ").push_bind(product_id);
+    }
+    if let Some(user_id) = filter.user_id {
+        qb.push(" AND user_id = ").push_bind(user_id);
+    }
+    if let Some(min) = filter.min_rating {
+        qb.push(" AND rating >= ").push_bind(min);
+    }
+    if let Some(max) = filter.max_rating {
+        qb.push(" AND rating <= ").push_bind(max);
+    }
+    if let Some(after) = filter.created_after {
+        qb.push(" AND created_at >= ").push_bind(after);
+    }
+    if let Some(before) = filter.created_before {
+        qb.push(" AND created_at < ").push_bind(before);
+    }
+    match filter.has_body {
+        Some(true) => {
+            qb.push(" AND body IS NOT NULL AND body <> ''");
+        }
+        Some(false) => {
+            qb.push(" AND (body IS NULL OR body = '')");
+        }
+        None => {}
+    }
+}
+
+/// Append the predicate selecting rows strictly after `cursor` in `sort` order.
+fn push_keyset(qb: &mut QueryBuilder<'_, Postgres>, sort: ReviewSort, cursor: Cursor) {
+    match sort {
+        ReviewSort::Newest => {
+            qb.push(" AND (created_at, id) < (")
+                .push_bind(cursor.created_at)
+                .push(", ")
+                .push_bind(cursor.id)
+                .push(")");
+        }
+        ReviewSort::Oldest => {
+            qb.push(" AND (created_at, id) > (")
+                .push_bind(cursor.created_at)
+                .push(", ")
+                .push_bind(cursor.id)
+                .push(")");
+        }
+        ReviewSort::Highest => {
+            qb.push(" AND (rating, created_at, id) < (")
+                .push_bind(cursor.rating)
+                .push(", ")
+                .push_bind(cursor.created_at)
+                .push(", ")
+                .push_bind(cursor.id)
+                .push(")");
+        }
+        // Rating ascends but ties still break newest first, so a row comparison won't do.
+        ReviewSort::Lowest => {
+            qb.push(" AND (rating > ")
+                .push_bind(cursor.rating)
+                .push(" OR (rating = ")
+                .push_bind(cursor.rating)
+                .push(" AND (created_at, id) < (")
+                .push_bind(cursor.created_at)
+                .push(", ")
+                .push_bind(cursor.id)
+                .push(")))");
+        }
+        ReviewSort::MostHelpful => {
+            qb.push(" AND (helpful_score, created_at, id) < (")
+                .push_bind(cursor.helpful_score)
+                .push(", ")
+                .push_bind(cursor.created_at)
+                .push(", ")
+                .push_bind(cursor.id)
+                .push(")");
+        }
+    }
+}
+
+fn order_by(sort: ReviewSort) -> &'static str {
+    match sort {
+        ReviewSort::Newest => "created_at DESC, id DESC",
+        ReviewSort::Oldest => "created_at ASC, id ASC",
+        ReviewSort::Highest => "rating DESC, created_at DESC, id DESC",
+        ReviewSort::Lowest => "rating ASC, created_at DESC, id DESC",
+        ReviewSort::MostHelpful => "helpful_score DESC, created_at DESC, id DESC",
+    }
 }
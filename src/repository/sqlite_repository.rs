@@ -0,0 +1,1254 @@
+//! SQLite implementation of [`ReviewStore`] for small deployments without Postgres.
+//!
+//! UUIDs are stored as blobs and timestamps as integer microseconds since the Unix epoch,
+//! so keyset comparisons behave exactly as on Postgres. Time series support `tz=UTC` only.
+//! Search runs on an FTS5 index that matches whole words without stemming.
+
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{QueryBuilder, Row, Sqlite, SqliteConnection, SqlitePool, Transaction};
+use uuid::Uuid;
+
+use super::review_repository::{counted, AggregateDelta};
+use super::{text_match, utc_buckets, ReviewStore};
+use crate::error::{ReviewError, DUPLICATE_REPLY, DUPLICATE_REPORT, DUPLICATE_REVIEW};
+use crate::models::{
+    ApiKey, CreateReport, CreateReview, IdempotencyRecord, LeaderboardOrder, ProductAggregate,
+    Review, ReviewFilter, ReviewReply, ReviewReport, ReviewRevision, ReviewSort, ReviewStatus,
+    Screened, SearchRow, SearchTerm, SearchTerms, TimeBucket, TimeseriesRow, UpdateReview,
+};
+use crate::pagination::Cursor;
+use crate::service::wilson_lower_bound;
+
+const REVIEW_COLUMNS: &str =
+    "id, product_id, user_id, rating, body, created_at, status, moderation_reason, moderated_by, moderated_at, \
+     helpful_count, unhelpful_count, helpful_score, revision, edited_at";
+const REVISION_COLUMNS: &str = "review_id, revision, rating, body, created_at, replaced_at";
+const REPORT_COLUMNS: &str = "id, review_id, reporter_id, reason, comment, created_at";
+const REPLY_COLUMNS: &str =
+    "review_id, merchant_id, body, status, moderation_reason, moderated_by, moderated_at, created_at, updated_at";
+const API_KEY_COLUMNS: &str =
+    "id, name, prefix, key_hash, scopes, created_at, expires_at, revoked_at";
+const IDEMPOTENCY_COLUMNS: &str =
+    "scope, key, request_hash, response_status, response_body, created_at, expires_at";
+const AGGREGATE_COLUMNS: &str =
+    "product_id, review_count, rating_sum, star_1, star_2, star_3, star_4, star_5, latest_review_at";
+
+/// Aggregates recomputed from scratch, in `product_rating_aggregates` column order.
+const EXPECTED_AGGREGATES: &str =
+    "SELECT product_id, COUNT(*) AS review_count, SUM(rating) AS rating_sum, \
+     COUNT(*) FILTER (WHERE rating = 1) AS star_1, COUNT(*) FILTER (WHERE rating = 2) AS star_2, \
+     COUNT(*) FILTER (WHERE rating = 3) AS star_3, COUNT(*) FILTER (WHERE rating = 4) AS star_4, \
+     COUNT(*) FILTER (WHERE rating = 5) AS star_5, MAX(created_at) AS latest_review_at \
+     FROM reviews WHERE status = 'approved' AND deleted_at IS NULL GROUP BY product_id";
+
+/// Repository for review persistence in SQLite. No business logic, only queries.
+#[derive(Clone)]
+pub struct SqliteReviewRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteReviewRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Apply the SQLite schema in `migrations_sqlite/`.
+    pub async fn migrate(pool: &SqlitePool) -> Result<(), sqlx::migrate::MigrateError> {
+        sqlx::migrate!("./migrations_sqlite").run(pool).await
+    }
+
+    /// Start a write transaction. `IMMEDIATE` takes the write lock up front, so a transaction
+    /// that reads before writing cannot fail to upgrade its lock under contention.
+    async fn begin(&self) -> Result<Transaction<'static, Sqlite>, sqlx::Error> {
+        self.pool.begin_with("BEGIN IMMEDIATE").await
+    }
+}
+
+#[async_trait]
+impl ReviewStore for SqliteReviewRepository {
+    async fn find_all(&self) -> Result<Vec<Review>, ReviewError> {
+        let rows = sqlx::query(&format!(
+            "SELECT {REVIEW_COLUMNS} FROM reviews WHERE deleted_at IS NULL ORDER BY created_at DESC"
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.iter().map(review_from_row).collect::<Result<_, _>>()?)
+    }
+
+    async fn find_page(
+        &self,
+        filter: &ReviewFilter,
+        status: ReviewStatus,
+        sort: ReviewSort,
+        after: Option<Cursor>,
+        limit: i64,
+    ) -> Result<Vec<Review>, ReviewError> {
+        let mut qb = QueryBuilder::<Sqlite>::new(format!(
+            "SELECT {REVIEW_COLUMNS} FROM reviews WHERE deleted_at IS NULL AND status = "
+        ));
+        qb.push_bind(status.as_str());
+        push_filter(&mut qb, filter);
+        if let Some(cursor) = after {
+            push_keyset(&mut qb, sort, cursor);
+        }
+        qb.push(" ORDER BY ").push(order_by(sort));
+        qb.push(" LIMIT ").push_bind(limit);
+        let rows = qb.build().fetch_all(&self.pool).await?;
+        Ok(rows.iter().map(review_from_row).collect::<Result<_, _>>()?)
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> Result<Option<Review>, ReviewError> {
+        let row = sqlx::query(&format!(
+            "SELECT {REVIEW_COLUMNS} FROM reviews WHERE id = ? AND deleted_at IS NULL"
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row.as_ref().map(review_from_row).transpose()?)
+    }
+
+    async fn find_revisions(&self, review_id: Uuid) -> Result<Vec<ReviewRevision>, ReviewError> {
+        let rows = sqlx::query(&format!(
+            "SELECT {REVISION_COLUMNS} FROM review_revisions WHERE review_id = ? ORDER BY revision DESC"
+        ))
+        .bind(review_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows
+            .iter()
+            .map(revision_from_row)
+            .collect::<Result<_, _>>()?)
+    }
+
+    async fn search(
+        &self,
+        terms: &SearchTerms,
+        product_id: Option<Uuid>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<SearchRow>, ReviewError> {
+        // bm25() is lower for better matches; it is negated so rank, as on Postgres, is higher.
+        let mut qb = QueryBuilder::<Sqlite>::new(format!(
+            "SELECT {REVIEW_COLUMNS}, -hits.score AS rank FROM reviews JOIN ( \
+                 SELECT s.review_id, bm25(reviews_search) AS score FROM reviews_search \
+                 JOIN review_search_ids s ON s.doc_id = reviews_search.rowid WHERE reviews_search MATCH "
+        ));
+        qb.push_bind(fts_query(terms));
+        qb.push(") hits ON hits.review_id = reviews.id WHERE status = 'approved' AND deleted_at IS NULL");
+        if let Some(product_id) = product_id {
+            qb.push(" AND product_id = ").push_bind(product_id);
+        }
+        qb.push(" ORDER BY hits.score, created_at DESC, id DESC LIMIT ")
+            .push_bind(limit)
+            .push(" OFFSET ")
+            .push_bind(offset);
+        let rows = qb.build().fetch_all(&self.pool).await?;
+        rows.iter()
+            .map(|row| {
+                let review = review_from_row(row)?;
+                let snippet =
+                    text_match::highlight(terms, review.body.as_deref().unwrap_or_default());
+                let rank = row.try_get::<f64, _>("rank")? as f32;
+                Ok(SearchRow {
+                    review,
+                    rank,
+                    snippet,
+                })
+            })
+            .collect()
+    }
+
+    async fn create(
+        &self,
+        id: Uuid,
+        body: &CreateReview,
+        status: ReviewStatus,
+    ) -> Result<Review, ReviewError> {
+        let mut tx = self.begin().await?;
+        let review =
+            insert(&mut tx, id, body, status)
+                .await
+                .map_err(|e| match e.as_database_error() {
+                    // SQLite does not name the violated index; `reviews_product_user_key` is the only candidate.
+                    Some(db) if db.is_unique_violation() => {
+                        ReviewError::Conflict(DUPLICATE_REVIEW.to_string())
+                    }
+                    _ => ReviewError::from(e),
+                })?;
+        apply_transition(&mut tx, &review, None).await?;
+        tx.commit().await?;
+        Ok(review)
+    }
+
+    async fn upsert(
+        &self,
+        id: Uuid,
+        body: &CreateReview,
+        screened: Screened,
+    ) -> Result<(Review, bool), ReviewError> {
+        let mut tx = self.begin().await?;
+        let old = sqlx::query(&format!(
+            "SELECT {REVIEW_COLUMNS} FROM reviews WHERE product_id = ? AND user_id = ? AND deleted_at IS NULL"
+        ))
+            .bind(body.product_id)
+            .bind(body.user_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+        let Some(old) = old.as_ref().map(review_from_row).transpose()? else {
+            let review = insert(&mut tx, id, body, screened.status).await?;
+            apply_transition(&mut tx, &review, None).await?;
+            tx.commit().await?;
+            return Ok((review, true));
+        };
+
+        let review = rewrite(&mut tx, &old, body.rating, body.body.as_deref(), screened).await?;
+        tx.commit().await?;
+        Ok((review, false))
+    }
+
+    async fn update(
+        &self,
+        id: Uuid,
+        patch: &UpdateReview,
+        screened: Screened,
+    ) -> Result<Option<Review>, ReviewError> {
+        let mut tx = self.begin().await?;
+        let old = sqlx::query(&format!(
+            "SELECT {REVIEW_COLUMNS} FROM reviews WHERE id = ? AND deleted_at IS NULL"
+        ))
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some(old) = old.as_ref().map(review_from_row).transpose()? else {
+            return Ok(None);
+        };
+        let rating = patch.rating.unwrap_or(old.rating);
+        let body = match &patch.body {
+            Some(body) => body.as_deref(),
+            None => old.body.as_deref(),
+        };
+        let review = rewrite(&mut tx, &old, rating, body, screened).await?;
+        tx.commit().await?;
+        Ok(Some(review))
+    }
+
+    async fn moderate(
+        &self,
+        id: Uuid,
+        status: ReviewStatus,
+        reason: Option<&str>,
+        moderator: Uuid,
+    ) -> Result<Option<Review>, ReviewError> {
+        let mut tx = self.begin().await?;
+        let old: Option<(i32, String)> = sqlx::query_as(
+            "SELECT rating, status FROM reviews WHERE id = ? AND deleted_at IS NULL",
+        )
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some(old) = old else {
+            return Ok(None);
+        };
+        let row = sqlx::query(&format!(
+            "UPDATE reviews SET status = ?, moderation_reason = ?, moderated_by = ?, moderated_at = ? \
+             WHERE id = ? RETURNING {REVIEW_COLUMNS}"
+        ))
+        .bind(status.as_str())
+        .bind(reason)
+        .bind(moderator)
+        .bind(Utc::now().timestamp_micros())
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await?;
+        let review = review_from_row(&row)?;
+        apply_transition(&mut tx, &review, counted(old)).await?;
+        tx.commit().await?;
+        Ok(Some(review))
+    }
+
+    async fn vote(
+        &self,
+        review_id: Uuid,
+        voter: Uuid,
+        helpful: Option<bool>,
+    ) -> Result<Option<Review>, ReviewError> {
+        let mut tx = self.begin().await?;
+        let found: Option<(Uuid,)> =
+            sqlx::query_as("SELECT id FROM reviews WHERE id = ? AND deleted_at IS NULL")
+                .bind(review_id)
+                .fetch_optional(&mut *tx)
+                .await?;
+        if found.is_none() {
+            return Ok(None);
+        }
+        match helpful {
+            Some(helpful) => {
+                sqlx::query(
+                    "INSERT INTO review_votes (review_id, voter_id, helpful, created_at, updated_at) \
+                     VALUES (?1, ?2, ?3, ?4, ?4) \
+                     ON CONFLICT (review_id, voter_id) DO UPDATE SET helpful = excluded.helpful, updated_at = excluded.updated_at",
+                )
+                .bind(review_id)
+                .bind(voter)
+                .bind(helpful)
+                .bind(Utc::now().timestamp_micros())
+                .execute(&mut *tx)
+                .await?;
+            }
+            None => {
+                sqlx::query("DELETE FROM review_votes WHERE review_id = ? AND voter_id = ?")
+                    .bind(review_id)
+                    .bind(voter)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+        }
+        let (up, down): (i64, i64) = sqlx::query_as(
+            "SELECT COALESCE(SUM(helpful), 0), COALESCE(SUM(1 - helpful), 0) FROM review_votes WHERE review_id = ?",
+        )
+        .bind(review_id)
+        .fetch_one(&mut *tx)
+        .await?;
+        let row = sqlx::query(&format!(
+            "UPDATE reviews SET helpful_count = ?, unhelpful_count = ?, helpful_score = ? WHERE id = ? \
+             RETURNING {REVIEW_COLUMNS}"
+        ))
+        .bind(up)
+        .bind(down)
+        .bind(wilson_lower_bound(up as u64, down as u64))
+        .bind(review_id)
+        .fetch_one(&mut *tx)
+        .await?;
+        let review = review_from_row(&row)?;
+        tx.commit().await?;
+        Ok(Some(review))
+    }
+
+    async fn report(
+        &self,
+        id: Uuid,
+        review_id: Uuid,
+        reporter: Uuid,
+        report: &CreateReport,
+        hide_at: i64,
+    ) -> Result<Option<(ReviewReport, bool)>, ReviewError> {
+        let mut tx = self.begin().await?;
+        let old: Option<(i32, String)> = sqlx::query_as(
+            "SELECT rating, status FROM reviews WHERE id = ? AND deleted_at IS NULL",
+        )
+        .bind(review_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some(old) = old else {
+            return Ok(None);
+        };
+        let row = sqlx::query(&format!(
+            "INSERT INTO review_reports (id, review_id, reporter_id, reason, comment, created_at) \
+             VALUES (?, ?, ?, ?, ?, ?) RETURNING {REPORT_COLUMNS}"
+        ))
+        .bind(id)
+        .bind(review_id)
+        .bind(reporter)
+        .bind(report.reason.as_str())
+        .bind(&report.comment)
+        .bind(Utc::now().timestamp_micros())
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| match e.as_database_error() {
+            Some(db) if db.is_unique_violation() => {
+                ReviewError::Conflict(DUPLICATE_REPORT.to_string())
+            }
+            _ => ReviewError::from(e),
+        })?;
+        let saved = report_from_row(&row)?;
+        let reports: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM review_reports WHERE review_id = ?")
+                .bind(review_id)
+                .fetch_one(&mut *tx)
+                .await?;
+        let hide = reports == hide_at && old.1 == ReviewStatus::Approved.as_str();
+        if hide {
+            let row = sqlx::query(&format!(
+                "UPDATE reviews SET status = 'hidden' WHERE id = ? RETURNING {REVIEW_COLUMNS}"
+            ))
+            .bind(review_id)
+            .fetch_one(&mut *tx)
+            .await?;
+            apply_transition(&mut tx, &review_from_row(&row)?, counted(old)).await?;
+        }
+        tx.commit().await?;
+        Ok(Some((saved, hide)))
+    }
+
+    async fn find_reports(
+        &self,
+        review_id: Option<Uuid>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<ReviewReport>, ReviewError> {
+        let rows = sqlx::query(&format!(
+            "SELECT {REPORT_COLUMNS} FROM review_reports WHERE (?1 IS NULL OR review_id = ?1) \
+             ORDER BY created_at DESC, id DESC LIMIT ?2 OFFSET ?3"
+        ))
+        .bind(review_id)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.iter().map(report_from_row).collect::<Result<_, _>>()?)
+    }
+
+    async fn find_replies(&self, review_ids: &[Uuid]) -> Result<Vec<ReviewReply>, ReviewError> {
+        if review_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut qb = QueryBuilder::<Sqlite>::new(format!(
+            "SELECT {REPLY_COLUMNS} FROM review_replies WHERE review_id IN ("
+        ));
+        let mut ids = qb.separated(", ");
+        for id in review_ids {
+            ids.push_bind(*id);
+        }
+        qb.push(")");
+        let rows = qb.build().fetch_all(&self.pool).await?;
+        Ok(rows.iter().map(reply_from_row).collect::<Result<_, _>>()?)
+    }
+
+    async fn find_reply_queue(
+        &self,
+        status: ReviewStatus,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<ReviewReply>, ReviewError> {
+        let rows = sqlx::query(&format!(
+            "SELECT {REPLY_COLUMNS} FROM review_replies WHERE status = ? ORDER BY created_at, review_id LIMIT ? OFFSET ?"
+        ))
+        .bind(status.as_str())
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.iter().map(reply_from_row).collect::<Result<_, _>>()?)
+    }
+
+    async fn create_reply(
+        &self,
+        review_id: Uuid,
+        merchant: Uuid,
+        body: &str,
+        status: ReviewStatus,
+    ) -> Result<ReviewReply, ReviewError> {
+        let row = sqlx::query(&format!(
+            "INSERT INTO review_replies (review_id, merchant_id, body, status, created_at, updated_at) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?5) RETURNING {REPLY_COLUMNS}"
+        ))
+        .bind(review_id)
+        .bind(merchant)
+        .bind(body)
+        .bind(status.as_str())
+        .bind(Utc::now().timestamp_micros())
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| match e.as_database_error() {
+            Some(db) if db.is_unique_violation() => ReviewError::Conflict(DUPLICATE_REPLY.to_string()),
+            _ => ReviewError::from(e),
+        })?;
+        Ok(reply_from_row(&row)?)
+    }
+
+    async fn update_reply(
+        &self,
+        review_id: Uuid,
+        body: &str,
+        status: ReviewStatus,
+    ) -> Result<Option<ReviewReply>, ReviewError> {
+        let row = sqlx::query(&format!(
+            "UPDATE review_replies SET body = ?, status = ?, updated_at = ? WHERE review_id = ? RETURNING {REPLY_COLUMNS}"
+        ))
+        .bind(body)
+        .bind(status.as_str())
+        .bind(Utc::now().timestamp_micros())
+        .bind(review_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row.as_ref().map(reply_from_row).transpose()?)
+    }
+
+    async fn moderate_reply(
+        &self,
+        review_id: Uuid,
+        status: ReviewStatus,
+        reason: Option<&str>,
+        moderator: Uuid,
+    ) -> Result<Option<ReviewReply>, ReviewError> {
+        let row = sqlx::query(&format!(
+            "UPDATE review_replies SET status = ?, moderation_reason = ?, moderated_by = ?, moderated_at = ? \
+             WHERE review_id = ? RETURNING {REPLY_COLUMNS}"
+        ))
+        .bind(status.as_str())
+        .bind(reason)
+        .bind(moderator)
+        .bind(Utc::now().timestamp_micros())
+        .bind(review_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row.as_ref().map(reply_from_row).transpose()?)
+    }
+
+    async fn delete_reply(&self, review_id: Uuid) -> Result<bool, ReviewError> {
+        let result = sqlx::query("DELETE FROM review_replies WHERE review_id = ?")
+            .bind(review_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<bool, ReviewError> {
+        let mut tx = self.begin().await?;
+        let deleted: Option<(Uuid, i32, String)> = sqlx::query_as(
+            "UPDATE reviews SET deleted_at = ? WHERE id = ? AND deleted_at IS NULL RETURNING product_id, rating, status",
+        )
+        .bind(Utc::now().timestamp_micros())
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some((product_id, rating, status)) = deleted else {
+            return Ok(false);
+        };
+        if let Some(rating) = counted((rating, status)) {
+            apply_delta(
+                &mut tx,
+                product_id,
+                &AggregateDelta::transition(Some(rating), None),
+                None,
+            )
+            .await?;
+            refresh_latest(&mut tx, product_id).await?;
+        }
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    async fn restore(&self, id: Uuid) -> Result<Option<Review>, ReviewError> {
+        let mut tx = self.begin().await?;
+        let row = sqlx::query(&format!(
+            "UPDATE reviews SET deleted_at = NULL WHERE id = ? AND deleted_at IS NOT NULL RETURNING {REVIEW_COLUMNS}"
+        ))
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| match e.as_database_error() {
+            Some(db) if db.is_unique_violation() => ReviewError::Conflict(DUPLICATE_REVIEW.to_string()),
+            _ => ReviewError::from(e),
+        })?;
+        let Some(review) = row.as_ref().map(review_from_row).transpose()? else {
+            return Ok(None);
+        };
+        apply_transition(&mut tx, &review, None).await?;
+        tx.commit().await?;
+        Ok(Some(review))
+    }
+
+    async fn purge_deleted(&self, deleted_before: DateTime<Utc>) -> Result<u64, ReviewError> {
+        let result = sqlx::query("DELETE FROM reviews WHERE deleted_at < ?")
+            .bind(deleted_before.timestamp_micros())
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    async fn find_aggregate(
+        &self,
+        product_id: Uuid,
+    ) -> Result<Option<ProductAggregate>, ReviewError> {
+        let row = sqlx::query(&format!(
+            "SELECT {AGGREGATE_COLUMNS} FROM product_rating_aggregates WHERE product_id = ?"
+        ))
+        .bind(product_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row.as_ref().map(aggregate_from_row).transpose()?)
+    }
+
+    async fn get_stats(&self) -> Result<(i64, f64), ReviewError> {
+        let (total, sum): (i64, i64) = sqlx::query_as(
+            "SELECT COALESCE(SUM(review_count), 0), COALESCE(SUM(rating_sum), 0) FROM product_rating_aggregates",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        let avg_rating = if total == 0 {
+            0.0
+        } else {
+            sum as f64 / total as f64
+        };
+        Ok((total, avg_rating))
+    }
+
+    async fn timeseries(
+        &self,
+        bucket: TimeBucket,
+        tz: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        product_id: Option<Uuid>,
+    ) -> Result<Vec<TimeseriesRow>, ReviewError> {
+        if !tz.eq_ignore_ascii_case("UTC") {
+            return Err(ReviewError::validation(
+                "The SQLite store only supports tz=UTC",
+            ));
+        }
+        // 'weekday 0' moves forward to Sunday; six days back is that week's Monday.
+        let start = match bucket {
+            TimeBucket::Day => "date(created_at / 1000000, 'unixepoch')",
+            TimeBucket::Week => "date(created_at / 1000000, 'unixepoch', 'weekday 0', '-6 days')",
+            TimeBucket::Month => "strftime('%Y-%m-01', created_at / 1000000, 'unixepoch')",
+        };
+        let rows: Vec<(String, i64, i64)> = sqlx::query_as(&format!(
+            "SELECT {start} AS local_start, COUNT(*), SUM(rating) FROM reviews \
+             WHERE status = 'approved' AND deleted_at IS NULL AND created_at >= ?1 AND created_at < ?2 AND (?3 IS NULL OR product_id = ?3) \
+             GROUP BY local_start"
+        ))
+        .bind(from.timestamp_micros())
+        .bind(to.timestamp_micros())
+        .bind(product_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut totals = HashMap::new();
+        for (day, count, sum) in rows {
+            let day = NaiveDate::parse_from_str(&day, "%Y-%m-%d")
+                .map_err(|e| ReviewError::Database(sqlx::Error::Decode(e.into())))?;
+            totals.insert(day.and_time(NaiveTime::MIN).and_utc(), (count, sum));
+        }
+        Ok(utc_buckets::zero_filled(bucket, from, to, &totals))
+    }
+
+    async fn top_products(
+        &self,
+        order: LeaderboardOrder,
+        since: Option<DateTime<Utc>>,
+        min_reviews: i64,
+        prior_mean: f64,
+        prior_weight: f64,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<ProductAggregate>, ReviewError> {
+        let mut qb = QueryBuilder::<Sqlite>::new("WITH source AS (");
+        match since {
+            Some(since) => {
+                qb.push(
+                    "SELECT product_id, COUNT(*) AS review_count, SUM(rating) AS rating_sum, \
+                     COUNT(*) FILTER (WHERE rating = 1) AS star_1, COUNT(*) FILTER (WHERE rating = 2) AS star_2, \
+                     COUNT(*) FILTER (WHERE rating = 3) AS star_3, COUNT(*) FILTER (WHERE rating = 4) AS star_4, \
+                     COUNT(*) FILTER (WHERE rating = 5) AS star_5, MAX(created_at) AS latest_review_at \
+                     FROM reviews WHERE status = 'approved' AND deleted_at IS NULL AND created_at >= ",
+                )
+                .push_bind(since.timestamp_micros())
+                .push(" GROUP BY product_id");
+            }
+            None => {
+                qb.push(format!(
+                    "SELECT {AGGREGATE_COLUMNS} FROM product_rating_aggregates"
+                ));
+            }
+        }
+        qb.push("), scored AS (SELECT *, (")
+            .push_bind(prior_weight)
+            .push(" * ")
+            .push_bind(prior_mean)
+            .push(" + rating_sum) / (")
+            .push_bind(prior_weight)
+            .push(" + review_count) AS score FROM source WHERE review_count >= ")
+            .push_bind(min_reviews.max(1))
+            .push(format!(
+                ") SELECT {AGGREGATE_COLUMNS} FROM scored ORDER BY "
+            ))
+            .push(match order {
+                LeaderboardOrder::Best => "score DESC, review_count DESC, product_id",
+                LeaderboardOrder::Worst => "score ASC, review_count DESC, product_id",
+                LeaderboardOrder::MostReviewed => "review_count DESC, score DESC, product_id",
+            })
+            .push(" LIMIT ")
+            .push_bind(limit)
+            .push(" OFFSET ")
+            .push_bind(offset);
+        let rows = qb.build().fetch_all(&self.pool).await?;
+        Ok(rows
+            .iter()
+            .map(aggregate_from_row)
+            .collect::<Result<_, _>>()?)
+    }
+
+    /// SQLite has a single writer, so the `IMMEDIATE` transaction alone keeps concurrent
+    /// review writes out until the comparison (and rebuild) is done.
+    async fn reconcile_aggregates(
+        &self,
+        repair: bool,
+    ) -> Result<(i64, Vec<(ProductAggregate, ProductAggregate)>), ReviewError> {
+        let mut tx = self.begin().await?;
+        let rows = sqlx::query(&format!(
+            "WITH expected AS ({EXPECTED_AGGREGATES}) \
+             SELECT COALESCE(e.product_id, a.product_id) AS product_id, \
+                    COALESCE(e.review_count, 0) AS e_count, COALESCE(e.rating_sum, 0) AS e_sum, \
+                    COALESCE(e.star_1, 0) AS e_1, COALESCE(e.star_2, 0) AS e_2, COALESCE(e.star_3, 0) AS e_3, \
+                    COALESCE(e.star_4, 0) AS e_4, COALESCE(e.star_5, 0) AS e_5, e.latest_review_at AS e_latest, \
+                    COALESCE(a.review_count, 0) AS a_count, COALESCE(a.rating_sum, 0) AS a_sum, \
+                    COALESCE(a.star_1, 0) AS a_1, COALESCE(a.star_2, 0) AS a_2, COALESCE(a.star_3, 0) AS a_3, \
+                    COALESCE(a.star_4, 0) AS a_4, COALESCE(a.star_5, 0) AS a_5, a.latest_review_at AS a_latest, \
+                    COUNT(*) OVER () AS checked \
+             FROM expected e FULL OUTER JOIN product_rating_aggregates a ON a.product_id = e.product_id"
+        ))
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let mut checked = 0;
+        let mut drift = Vec::new();
+        for row in &rows {
+            checked = row.try_get("checked")?;
+            let expected = prefixed_aggregate_from_row(row, "e")?;
+            let stored = prefixed_aggregate_from_row(row, "a")?;
+            if expected.counts() != stored.counts() {
+                drift.push((expected, stored));
+            }
+        }
+
+        if repair {
+            sqlx::query("DELETE FROM product_rating_aggregates")
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query(&format!(
+                "INSERT INTO product_rating_aggregates ({AGGREGATE_COLUMNS}) {EXPECTED_AGGREGATES}"
+            ))
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok((checked, drift))
+    }
+
+    async fn create_api_key(&self, key: &ApiKey) -> Result<(), ReviewError> {
+        let scopes: Vec<&str> = key.scopes.iter().map(|s| s.as_str()).collect();
+        sqlx::query(
+            "INSERT INTO api_keys (id, name, prefix, key_hash, scopes, created_at, expires_at, revoked_at) \
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(key.id)
+        .bind(&key.name)
+        .bind(&key.prefix)
+        .bind(&key.key_hash)
+        .bind(scopes.join(" "))
+        .bind(key.created_at.timestamp_micros())
+        .bind(key.expires_at.map(|at| at.timestamp_micros()))
+        .bind(key.revoked_at.map(|at| at.timestamp_micros()))
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn find_api_keys(&self) -> Result<Vec<ApiKey>, ReviewError> {
+        let rows = sqlx::query(&format!(
+            "SELECT {API_KEY_COLUMNS} FROM api_keys ORDER BY created_at DESC, id DESC"
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows
+            .iter()
+            .map(api_key_from_row)
+            .collect::<Result<_, _>>()?)
+    }
+
+    async fn find_api_key_by_hash(&self, key_hash: &str) -> Result<Option<ApiKey>, ReviewError> {
+        let row = sqlx::query(&format!(
+            "SELECT {API_KEY_COLUMNS} FROM api_keys WHERE key_hash = ?"
+        ))
+        .bind(key_hash)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row.as_ref().map(api_key_from_row).transpose()?)
+    }
+
+    async fn revoke_api_key(&self, id: Uuid) -> Result<Option<ApiKey>, ReviewError> {
+        let row = sqlx::query(&format!(
+            "UPDATE api_keys SET revoked_at = COALESCE(revoked_at, ?2) WHERE id = ?1 RETURNING {API_KEY_COLUMNS}"
+        ))
+        .bind(id)
+        .bind(Utc::now().timestamp_micros())
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row.as_ref().map(api_key_from_row).transpose()?)
+    }
+
+    async fn claim_idempotency_key(
+        &self,
+        record: &IdempotencyRecord,
+    ) -> Result<Option<IdempotencyRecord>, ReviewError> {
+        loop {
+            let claimed = sqlx::query(
+                "INSERT INTO idempotency_keys (scope, key, request_hash, response_status, response_body, created_at, expires_at) \
+                 VALUES (?, ?, ?, ?, ?, ?, ?) \
+                 ON CONFLICT (scope, key) DO UPDATE SET request_hash = excluded.request_hash, response_status = excluded.response_status, \
+                 response_body = excluded.response_body, created_at = excluded.created_at, expires_at = excluded.expires_at \
+                 WHERE idempotency_keys.expires_at <= excluded.created_at \
+                 RETURNING scope",
+            )
+            .bind(&record.scope)
+            .bind(&record.key)
+            .bind(&record.request_hash)
+            .bind(record.response_status)
+            .bind(&record.response_body)
+            .bind(record.created_at.timestamp_micros())
+            .bind(record.expires_at.timestamp_micros())
+            .fetch_optional(&self.pool)
+            .await?;
+            if claimed.is_some() {
+                return Ok(None);
+            }
+            let row = sqlx::query(&format!(
+                "SELECT {IDEMPOTENCY_COLUMNS} FROM idempotency_keys WHERE scope = ? AND key = ?"
+            ))
+            .bind(&record.scope)
+            .bind(&record.key)
+            .fetch_optional(&self.pool)
+            .await?;
+            // The holder may release the key between the two statements; then claim it again
+            // rather than report a claim this request does not hold.
+            if let Some(row) = row {
+                return Ok(Some(idempotency_from_row(&row)?));
+            }
+        }
+    }
+
+    async fn complete_idempotency_key(
+        &self,
+        scope: &str,
+        key: &str,
+        status: i32,
+        body: &str,
+    ) -> Result<(), ReviewError> {
+        sqlx::query("UPDATE idempotency_keys SET response_status = ?3, response_body = ?4 WHERE scope = ?1 AND key = ?2")
+            .bind(scope)
+            .bind(key)
+            .bind(status)
+            .bind(body)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn release_idempotency_key(&self, scope: &str, key: &str) -> Result<(), ReviewError> {
+        sqlx::query("DELETE FROM idempotency_keys WHERE scope = ? AND key = ?")
+            .bind(scope)
+            .bind(key)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn purge_idempotency_keys(&self, now: DateTime<Utc>) -> Result<u64, ReviewError> {
+        let result = sqlx::query("DELETE FROM idempotency_keys WHERE expires_at <= ?")
+            .bind(now.timestamp_micros())
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+}
+
+fn from_micros(micros: i64) -> Result<DateTime<Utc>, sqlx::Error> {
+    DateTime::from_timestamp_micros(micros)
+        .ok_or_else(|| sqlx::Error::Decode("timestamp out of range".into()))
+}
+
+fn review_from_row(row: &SqliteRow) -> Result<Review, sqlx::Error> {
+    Ok(Review {
+        id: row.try_get("id")?,
+        product_id: row.try_get("product_id")?,
+        user_id: row.try_get("user_id")?,
+        rating: row.try_get("rating")?,
+        body: row.try_get("body")?,
+        created_at: from_micros(row.try_get("created_at")?)?,
+        status: ReviewStatus::try_from(row.try_get::<String, _>("status")?)
+            .map_err(|e| sqlx::Error::Decode(e.into()))?,
+        moderation_reason: row.try_get("moderation_reason")?,
+        moderated_by: row.try_get("moderated_by")?,
+        moderated_at: row
+            .try_get::<Option<i64>, _>("moderated_at")?
+            .map(from_micros)
+            .transpose()?,
+        helpful_count: row.try_get("helpful_count")?,
+        unhelpful_count: row.try_get("unhelpful_count")?,
+        helpful_score: row.try_get("helpful_score")?,
+        revision: row.try_get("revision")?,
+        edited_at: row
+            .try_get::<Option<i64>, _>("edited_at")?
+            .map(from_micros)
+            .transpose()?,
+    })
+}
+
+fn revision_from_row(row: &SqliteRow) -> Result<ReviewRevision, sqlx::Error> {
+    Ok(ReviewRevision {
+        review_id: row.try_get("review_id")?,
+        revision: row.try_get("revision")?,
+        rating: row.try_get("rating")?,
+        body: row.try_get("body")?,
+        created_at: from_micros(row.try_get("created_at")?)?,
+        replaced_at: from_micros(row.try_get("replaced_at")?)?,
+    })
+}
+
+fn report_from_row(row: &SqliteRow) -> Result<ReviewReport, sqlx::Error> {
+    Ok(ReviewReport {
+        id: row.try_get("id")?,
+        review_id: row.try_get("review_id")?,
+        reporter_id: row.try_get("reporter_id")?,
+        reason: row
+            .try_get::<String, _>("reason")?
+            .try_into()
+            .map_err(|e: String| sqlx::Error::Decode(e.into()))?,
+        comment: row.try_get("comment")?,
+        created_at: from_micros(row.try_get("created_at")?)?,
+    })
+}
+
+fn reply_from_row(row: &SqliteRow) -> Result<ReviewReply, sqlx::Error> {
+    Ok(ReviewReply {
+        review_id: row.try_get("review_id")?,
+        merchant_id: row.try_get("merchant_id")?,
+        body: row.try_get("body")?,
+        status: ReviewStatus::try_from(row.try_get::<String, _>("status")?)
+            .map_err(|e| sqlx::Error::Decode(e.into()))?,
+        moderation_reason: row.try_get("moderation_reason")?,
+        moderated_by: row.try_get("moderated_by")?,
+        moderated_at: row
+            .try_get::<Option<i64>, _>("moderated_at")?
+            .map(from_micros)
+            .transpose()?,
+        created_at: from_micros(row.try_get("created_at")?)?,
+        updated_at: from_micros(row.try_get("updated_at")?)?,
+    })
+}
+
+fn idempotency_from_row(row: &SqliteRow) -> Result<IdempotencyRecord, sqlx::Error> {
+    Ok(IdempotencyRecord {
+        scope: row.try_get("scope")?,
+        key: row.try_get("key")?,
+        request_hash: row.try_get("request_hash")?,
+        response_status: row.try_get("response_status")?,
+        response_body: row.try_get("response_body")?,
+        created_at: from_micros(row.try_get("created_at")?)?,
+        expires_at: from_micros(row.try_get("expires_at")?)?,
+    })
+}
+
+fn api_key_from_row(row: &SqliteRow) -> Result<ApiKey, sqlx::Error> {
+    let scopes: String = row.try_get("scopes")?;
+    Ok(ApiKey {
+        id: row.try_get("id")?,
+        name: row.try_get("name")?,
+        prefix: row.try_get("prefix")?,
+        key_hash: row.try_get("key_hash")?,
+        scopes: scopes
+            .split_whitespace()
+            .map(str::parse)
+            .collect::<Result<_, String>>()
+            .map_err(|e| sqlx::Error::Decode(e.into()))?,
+        created_at: from_micros(row.try_get("created_at")?)?,
+        expires_at: row
+            .try_get::<Option<i64>, _>("expires_at")?
+            .map(from_micros)
+            .transpose()?,
+        revoked_at: row
+            .try_get::<Option<i64>, _>("revoked_at")?
+            .map(from_micros)
+            .transpose()?,
+    })
+}
+
+fn aggregate_from_row(row: &SqliteRow) -> Result<ProductAggregate, sqlx::Error> {
+    let latest: Option<i64> = row.try_get("latest_review_at")?;
+    Ok(ProductAggregate {
+        product_id: row.try_get("product_id")?,
+        review_count: row.try_get("review_count")?,
+        rating_sum: row.try_get("rating_sum")?,
+        star_1: row.try_get("star_1")?,
+        star_2: row.try_get("star_2")?,
+        star_3: row.try_get("star_3")?,
+        star_4: row.try_get("star_4")?,
+        star_5: row.try_get("star_5")?,
+        latest_review_at: latest.map(from_micros).transpose()?,
+    })
+}
+
+fn prefixed_aggregate_from_row(
+    row: &SqliteRow,
+    prefix: &str,
+) -> Result<ProductAggregate, sqlx::Error> {
+    let col = |name: &str| format!("{}_{}", prefix, name);
+    let latest: Option<i64> = row.try_get(col("latest").as_str())?;
+    Ok(ProductAggregate {
+        product_id: row.try_get("product_id")?,
+        review_count: row.try_get(col("count").as_str())?,
+        rating_sum: row.try_get(col("sum").as_str())?,
+        star_1: row.try_get(col("1").as_str())?,
+        star_2: row.try_get(col("2").as_str())?,
+        star_3: row.try_get(col("3").as_str())?,
+        star_4: row.try_get(col("4").as_str())?,
+        star_5: row.try_get(col("5").as_str())?,
+        latest_review_at: latest.map(from_micros).transpose()?,
+    })
+}
+
+async fn insert(
+    conn: &mut SqliteConnection,
+    id: Uuid,
+    body: &CreateReview,
+    status: ReviewStatus,
+) -> Result<Review, sqlx::Error> {
+    let row = sqlx::query(&format!(
+        "INSERT INTO reviews (id, product_id, user_id, rating, body, created_at, status) VALUES (?, ?, ?, ?, ?, ?, ?) \
+         RETURNING {REVIEW_COLUMNS}"
+    ))
+    .bind(id)
+    .bind(body.product_id)
+    .bind(body.user_id)
+    .bind(body.rating)
+    .bind(body.body.clone())
+    .bind(Utc::now().timestamp_micros())
+    .bind(status.as_str())
+    .fetch_one(conn)
+    .await?;
+    review_from_row(&row)
+}
+
+/// Give `old` a new rating and body, and the status [`Screened::after_edit`] picks, archiving it
+/// first if the rating or body changes. See the Postgres repository's `rewrite`.
+async fn rewrite(
+    conn: &mut SqliteConnection,
+    old: &Review,
+    rating: i32,
+    body: Option<&str>,
+    screened: Screened,
+) -> Result<Review, sqlx::Error> {
+    let status = screened.after_edit(old.status, body != old.body.as_deref());
+    let edited = rating != old.rating || body != old.body.as_deref();
+    let now = Utc::now().timestamp_micros();
+    if edited {
+        sqlx::query(
+            "INSERT INTO review_revisions (review_id, revision, rating, body, created_at, replaced_at) \
+             VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(old.id)
+        .bind(old.revision)
+        .bind(old.rating)
+        .bind(&old.body)
+        .bind(old.edited_at.unwrap_or(old.created_at).timestamp_micros())
+        .bind(now)
+        .execute(&mut *conn)
+        .await?;
+    }
+    let row = sqlx::query(&format!(
+        "UPDATE reviews SET rating = ?1, body = ?2, status = ?3, \
+         moderation_reason = CASE WHEN status = ?3 THEN moderation_reason END, \
+         moderated_by = CASE WHEN status = ?3 THEN moderated_by END, \
+         moderated_at = CASE WHEN status = ?3 THEN moderated_at END, \
+         revision = revision + ?4, edited_at = CASE WHEN ?4 THEN ?5 ELSE edited_at END \
+         WHERE id = ?6 RETURNING {REVIEW_COLUMNS}"
+    ))
+    .bind(rating)
+    .bind(body)
+    .bind(status.as_str())
+    .bind(edited)
+    .bind(now)
+    .bind(old.id)
+    .fetch_one(&mut *conn)
+    .await?;
+    let review = review_from_row(&row)?;
+    let before = (old.status == ReviewStatus::Approved).then_some(old.rating);
+    apply_transition(conn, &review, before).await?;
+    Ok(review)
+}
+
+/// Bring the aggregate in line after `review` was written, given what it counted for before.
+async fn apply_transition(
+    conn: &mut SqliteConnection,
+    review: &Review,
+    before: Option<i32>,
+) -> Result<(), sqlx::Error> {
+    let after = (review.status == ReviewStatus::Approved).then_some(review.rating);
+    if before == after {
+        return Ok(());
+    }
+    let latest = after.map(|_| review.created_at);
+    apply_delta(
+        &mut *conn,
+        review.product_id,
+        &AggregateDelta::transition(before, after),
+        latest,
+    )
+    .await?;
+    if before.is_some() && after.is_none() {
+        refresh_latest(conn, review.product_id).await?;
+    }
+    Ok(())
+}
+
+/// Recompute the product's newest approved review time after one stopped counting.
+async fn refresh_latest(conn: &mut SqliteConnection, product_id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE product_rating_aggregates \
+         SET latest_review_at = (SELECT MAX(created_at) FROM reviews WHERE product_id = ?1 AND status = 'approved' AND deleted_at IS NULL) \
+         WHERE product_id = ?1",
+    )
+    .bind(product_id)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// Add `delta` to the product's aggregate row, creating it on first use.
+async fn apply_delta(
+    conn: &mut SqliteConnection,
+    product_id: Uuid,
+    delta: &AggregateDelta,
+    latest: Option<DateTime<Utc>>,
+) -> Result<(), sqlx::Error> {
+    // Scalar MAX() is NULL if any argument is, hence the COALESCEs.
+    sqlx::query(&format!(
+        "INSERT INTO product_rating_aggregates ({AGGREGATE_COLUMNS}) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?) \
+         ON CONFLICT (product_id) DO UPDATE SET \
+             review_count = review_count + excluded.review_count, \
+             rating_sum = rating_sum + excluded.rating_sum, \
+             star_1 = star_1 + excluded.star_1, \
+             star_2 = star_2 + excluded.star_2, \
+             star_3 = star_3 + excluded.star_3, \
+             star_4 = star_4 + excluded.star_4, \
+             star_5 = star_5 + excluded.star_5, \
+             latest_review_at = MAX(COALESCE(latest_review_at, excluded.latest_review_at), \
+                                    COALESCE(excluded.latest_review_at, latest_review_at))"
+    ))
+    .bind(product_id)
+    .bind(delta.count)
+    .bind(delta.sum)
+    .bind(delta.stars[0])
+    .bind(delta.stars[1])
+    .bind(delta.stars[2])
+    .bind(delta.stars[3])
+    .bind(delta.stars[4])
+    .bind(latest.map(|t| t.timestamp_micros()))
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// Render terms as an FTS5 query: terms are ANDed, each quoted so none reads as an operator,
+/// and prefixes take a trailing `*`. Terms hold only alphanumeric words.
+fn fts_query(terms: &SearchTerms) -> String {
+    terms
+        .0
+        .iter()
+        .map(|term| match term {
+            SearchTerm::Word(w) => format!("\"{}\"", w),
+            SearchTerm::Prefix(w) => format!("\"{}\"*", w),
+            SearchTerm::Phrase(ws) => format!("\"{}\"", ws.join(" ")),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Append `AND ...` for each set field. Values are always bound, never interpolated.
+fn push_filter(qb: &mut QueryBuilder<'_, Sqlite>, filter: &ReviewFilter) {
+    if let Some(product_id) = filter.product_id {
+        qb.push(" AND product_id = ").push_bind(product_id);
+    }
+    if let Some(user_id) = filter.user_id {
+        qb.push(" AND user_id = ").push_bind(user_id);
+    }
+    if let Some(min) = filter.min_rating {
+        qb.push(" AND rating >= ").push_bind(min);
+    }
+    if let Some(max) = filter.max_rating {
+        qb.push(" AND rating <= ").push_bind(max);
+    }
+    if let Some(after) = filter.created_after {
+        qb.push(" AND created_at >= ")
+            .push_bind(after.timestamp_micros());
+    }
+    if let Some(before) = filter.created_before {
+        qb.push(" AND created_at < ")
+            .push_bind(before.timestamp_micros());
+    }
+    match filter.has_body {
+        Some(true) => {
+            qb.push(" AND body IS NOT NULL AND body <> ''");
+        }
+        Some(false) => {
+            qb.push(" AND (body IS NULL OR body = '')");
+        }
+        None => {}
+    }
+}
+
+/// Append the predicate selecting rows strictly after `cursor` in `sort` order.
+fn push_keyset(qb: &mut QueryBuilder<'_, Sqlite>, sort: ReviewSort, cursor: Cursor) {
+    let created_at = cursor.created_at.timestamp_micros();
+    match sort {
+        ReviewSort::Newest => {
+            qb.push(" AND (created_at, id) < (")
+                .push_bind(created_at)
+                .push(", ")
+                .push_bind(cursor.id)
+                .push(")");
+        }
+        ReviewSort::Oldest => {
+            qb.push(" AND (created_at, id) > (")
+                .push_bind(created_at)
+                .push(", ")
+                .push_bind(cursor.id)
+                .push(")");
+        }
+        ReviewSort::Highest => {
+            qb.push(" AND (rating, created_at, id) < (")
+                .push_bind(cursor.rating)
+                .push(", ")
+                .push_bind(created_at)
+                .push(", ")
+                .push_bind(cursor.id)
+                .push(")");
+        }
+        // Rating ascends but ties still break newest first, so a row comparison won't do.
+        ReviewSort::Lowest => {
+            qb.push(" AND (rating > ")
+                .push_bind(cursor.rating)
+                .push(" OR (rating = ")
+                .push_bind(cursor.rating)
+                .push(" AND (created_at, id) < (")
+                .push_bind(created_at)
+                .push(", ")
+                .push_bind(cursor.id)
+                .push(")))");
+        }
+        ReviewSort::MostHelpful => {
+            qb.push(" AND (helpful_score, created_at, id) < (")
+                .push_bind(cursor.helpful_score)
+                .push(", ")
+                .push_bind(created_at)
+                .push(", ")
+                .push_bind(cursor.id)
+                .push(")");
+        }
+    }
+}
+
+fn order_by(sort: ReviewSort) -> &'static str {
+    match sort {
+        ReviewSort::Newest => "created_at DESC, id DESC",
+        ReviewSort::Oldest => "created_at ASC, id ASC",
+        ReviewSort::Highest => "rating DESC, created_at DESC, id DESC",
+        ReviewSort::Lowest => "rating ASC, created_at DESC, id DESC",
+        ReviewSort::MostHelpful => "helpful_score DESC, created_at DESC, id DESC",
+    }
+}
@@ -0,0 +1,244 @@
+//! Storage abstraction the service layer is written against.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::error::ReviewError;
+use crate::models::{
+    ApiKey, CreateReport, CreateReview, IdempotencyRecord, LeaderboardOrder, ProductAggregate,
+    Review, ReviewFilter, ReviewReply, ReviewReport, ReviewRevision, ReviewSort, ReviewStatus,
+    Screened, SearchRow, SearchTerms, TimeBucket, TimeseriesRow, UpdateReview,
+};
+use crate::pagination::Cursor;
+
+/// Review persistence. Only approved reviews count: implementations must keep per-product
+/// aggregates consistent with them, and search, time series and rankings must ignore the rest.
+/// Deleted reviews are soft-deleted: every read and write below except [`restore`](Self::restore)
+/// and [`purge_deleted`](Self::purge_deleted) treats them as missing.
+/// See [`ReviewRepository`](super::ReviewRepository) for the reference semantics.
+#[async_trait]
+pub trait ReviewStore: Send + Sync {
+    /// Every review, newest first.
+    async fn find_all(&self) -> Result<Vec<Review>, ReviewError>;
+
+    /// One page of reviews in `status` matching `filter` in `sort` order, starting after `after`.
+    async fn find_page(
+        &self,
+        filter: &ReviewFilter,
+        status: ReviewStatus,
+        sort: ReviewSort,
+        after: Option<Cursor>,
+        limit: i64,
+    ) -> Result<Vec<Review>, ReviewError>;
+
+    /// A review in any status, unless deleted.
+    async fn find_by_id(&self, id: Uuid) -> Result<Option<Review>, ReviewError>;
+
+    /// Earlier versions of a review, newest first.
+    async fn find_revisions(&self, review_id: Uuid) -> Result<Vec<ReviewRevision>, ReviewError>;
+
+    /// Approved reviews whose body matches every term, most relevant first, with highlighted snippets.
+    async fn search(
+        &self,
+        terms: &SearchTerms,
+        product_id: Option<Uuid>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<SearchRow>, ReviewError>;
+
+    /// Insert a review in `status`. Fails with [`ReviewError::Conflict`] if the user already reviewed the product.
+    async fn create(
+        &self,
+        id: Uuid,
+        body: &CreateReview,
+        status: ReviewStatus,
+    ) -> Result<Review, ReviewError>;
+
+    /// Insert the review for `(product_id, user_id)` in `screened.status`, or replace it as
+    /// [`update`](Self::update) does; `true` when newly inserted.
+    async fn upsert(
+        &self,
+        id: Uuid,
+        body: &CreateReview,
+        screened: Screened,
+    ) -> Result<(Review, bool), ReviewError>;
+
+    /// Apply a partial update, moving the review to [`Screened::after_edit`] of its current status.
+    /// A status change clears the moderation stamp, which belonged to the old status. If the rating
+    /// or body changes, the previous version is archived, `revision` goes up by one and `edited_at`
+    /// is stamped. `None` if the review does not exist.
+    async fn update(
+        &self,
+        id: Uuid,
+        patch: &UpdateReview,
+        screened: Screened,
+    ) -> Result<Option<Review>, ReviewError>;
+
+    /// Set `status` as `moderator`'s decision, stamped with the current time.
+    /// `None` if the review does not exist.
+    async fn moderate(
+        &self,
+        id: Uuid,
+        status: ReviewStatus,
+        reason: Option<&str>,
+        moderator: Uuid,
+    ) -> Result<Option<Review>, ReviewError>;
+
+    /// Set (`Some`) or withdraw (`None`) `voter`'s helpful vote and refresh the review's vote
+    /// totals and [`helpful_score`](Review::helpful_score). `None` if the review does not exist.
+    async fn vote(
+        &self,
+        review_id: Uuid,
+        voter: Uuid,
+        helpful: Option<bool>,
+    ) -> Result<Option<Review>, ReviewError>;
+
+    /// Record `reporter`'s report against a review. When this is the review's `hide_at`-th report
+    /// and the review is approved, hide it. Returns the report and whether it hid the review, or
+    /// `None` if the review does not exist. Fails with [`ReviewError::Conflict`] on a repeat report.
+    async fn report(
+        &self,
+        id: Uuid,
+        review_id: Uuid,
+        reporter: Uuid,
+        report: &CreateReport,
+        hide_at: i64,
+    ) -> Result<Option<(ReviewReport, bool)>, ReviewError>;
+
+    /// Reports newest first, optionally only those against `review_id`.
+    async fn find_reports(
+        &self,
+        review_id: Option<Uuid>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<ReviewReport>, ReviewError>;
+
+    /// Replies to any of `review_ids`, in any status and no particular order.
+    async fn find_replies(&self, review_ids: &[Uuid]) -> Result<Vec<ReviewReply>, ReviewError>;
+
+    /// Replies in `status`, oldest first.
+    async fn find_reply_queue(
+        &self,
+        status: ReviewStatus,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<ReviewReply>, ReviewError>;
+
+    /// Insert `merchant`'s reply to a review in `status`. Fails with [`ReviewError::Conflict`] if the
+    /// review already has a reply or does not exist.
+    async fn create_reply(
+        &self,
+        review_id: Uuid,
+        merchant: Uuid,
+        body: &str,
+        status: ReviewStatus,
+    ) -> Result<ReviewReply, ReviewError>;
+
+    /// Replace a reply's body and move it to `status`. `None` if the review has no reply.
+    async fn update_reply(
+        &self,
+        review_id: Uuid,
+        body: &str,
+        status: ReviewStatus,
+    ) -> Result<Option<ReviewReply>, ReviewError>;
+
+    /// Set a reply's `status` as `moderator`'s decision, stamped with the current time.
+    /// `None` if the review has no reply.
+    async fn moderate_reply(
+        &self,
+        review_id: Uuid,
+        status: ReviewStatus,
+        reason: Option<&str>,
+        moderator: Uuid,
+    ) -> Result<Option<ReviewReply>, ReviewError>;
+
+    /// Delete a review's reply; `false` if it had none.
+    async fn delete_reply(&self, review_id: Uuid) -> Result<bool, ReviewError>;
+
+    /// Soft-delete a review, keeping its revisions, reports, votes and reply until it is purged;
+    /// `false` if there was no live review with this id.
+    async fn delete(&self, id: Uuid) -> Result<bool, ReviewError>;
+
+    /// Undo a soft delete. `None` if no deleted review has this id; fails with
+    /// [`ReviewError::Conflict`] if the author has reviewed the product again since.
+    async fn restore(&self, id: Uuid) -> Result<Option<Review>, ReviewError>;
+
+    /// Permanently remove reviews deleted before `deleted_before`, with their revisions, reports,
+    /// votes and replies. Returns how many reviews were removed.
+    async fn purge_deleted(&self, deleted_before: DateTime<Utc>) -> Result<u64, ReviewError>;
+
+    /// Aggregate for one product, or `None` if it has never been reviewed.
+    async fn find_aggregate(
+        &self,
+        product_id: Uuid,
+    ) -> Result<Option<ProductAggregate>, ReviewError>;
+
+    /// Global review count and mean rating.
+    async fn get_stats(&self) -> Result<(i64, f64), ReviewError>;
+
+    /// Zero-filled per-bucket counts and mean ratings over `[from, to)` in time zone `tz`.
+    async fn timeseries(
+        &self,
+        bucket: TimeBucket,
+        tz: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        product_id: Option<Uuid>,
+    ) -> Result<Vec<TimeseriesRow>, ReviewError>;
+
+    /// Products ranked by `order` over reviews since `since` (all time when `None`).
+    #[allow(clippy::too_many_arguments)]
+    async fn top_products(
+        &self,
+        order: LeaderboardOrder,
+        since: Option<DateTime<Utc>>,
+        min_reviews: i64,
+        prior_mean: f64,
+        prior_weight: f64,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<ProductAggregate>, ReviewError>;
+
+    /// Store a newly issued API key.
+    async fn create_api_key(&self, key: &ApiKey) -> Result<(), ReviewError>;
+
+    /// Every API key, newest first, including revoked and expired ones.
+    async fn find_api_keys(&self) -> Result<Vec<ApiKey>, ReviewError>;
+
+    /// The API key whose secret hashes to `key_hash`, whatever its state.
+    async fn find_api_key_by_hash(&self, key_hash: &str) -> Result<Option<ApiKey>, ReviewError>;
+
+    /// Stamp `revoked_at` on an API key, keeping the first stamp if it was already revoked.
+    /// `None` if no key has this id.
+    async fn revoke_api_key(&self, id: Uuid) -> Result<Option<ApiKey>, ReviewError>;
+
+    /// Store `record` as the first use of its scope and key, replacing an expired record.
+    /// Returns `None` once stored, or the unexpired record that already holds the key.
+    async fn claim_idempotency_key(
+        &self,
+        record: &IdempotencyRecord,
+    ) -> Result<Option<IdempotencyRecord>, ReviewError>;
+
+    /// Store the response to replay for a claimed key.
+    async fn complete_idempotency_key(
+        &self,
+        scope: &str,
+        key: &str,
+        status: i32,
+        body: &str,
+    ) -> Result<(), ReviewError>;
+
+    /// Drop a claimed key whose request failed, so that it can be retried.
+    async fn release_idempotency_key(&self, scope: &str, key: &str) -> Result<(), ReviewError>;
+
+    /// Remove idempotency keys that expired at or before `now`. Returns how many were removed.
+    async fn purge_idempotency_keys(&self, now: DateTime<Utc>) -> Result<u64, ReviewError>;
+
+    /// Compare stored aggregates with the reviews, optionally rebuilding them.
+    /// Returns products examined and `(expected, stored)` pairs that differed.
+    async fn reconcile_aggregates(
+        &self,
+        repair: bool,
+    ) -> Result<(i64, Vec<(ProductAggregate, ProductAggregate)>), ReviewError>;
+}
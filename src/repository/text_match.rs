@@ -0,0 +1,137 @@
+//! Plain-Rust search for backends without full-text indexing, and snippets for SQLite's. Words
+//! match exactly (case-insensitive, no stemming); rank is the share of the body's words that matched.
+
+use crate::models::{Review, SearchRow, SearchTerm, SearchTerms};
+
+/// Words of context kept in a snippet, roughly what `ts_headline` is configured for.
+const SNIPPET_WORDS: usize = 35;
+
+struct Token {
+    start: usize,
+    end: usize,
+    word: String,
+}
+
+fn tokens(body: &str) -> Vec<Token> {
+    let mut out = Vec::new();
+    let mut start = None;
+    for (i, c) in body
+        .char_indices()
+        .chain(std::iter::once((body.len(), ' ')))
+    {
+        match (start, c.is_alphanumeric()) {
+            (None, true) => start = Some(i),
+            (Some(s), false) => {
+                out.push(Token {
+                    start: s,
+                    end: i,
+                    word: body[s..i].to_lowercase(),
+                });
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    out
+}
+
+/// Which of `tokens` each term matched, or `None` unless every term matches.
+fn mark(terms: &SearchTerms, tokens: &[Token]) -> Option<Vec<bool>> {
+    let mut marked = vec![false; tokens.len()];
+    for term in &terms.0 {
+        let mut found = false;
+        match term {
+            SearchTerm::Word(w) | SearchTerm::Prefix(w) => {
+                let prefix = matches!(term, SearchTerm::Prefix(_));
+                for (i, t) in tokens.iter().enumerate() {
+                    if t.word == *w || (prefix && t.word.starts_with(w.as_str())) {
+                        marked[i] = true;
+                        found = true;
+                    }
+                }
+            }
+            SearchTerm::Phrase(ws) => {
+                for i in 0..tokens.len().saturating_sub(ws.len() - 1) {
+                    if ws.iter().zip(&tokens[i..]).all(|(w, t)| t.word == *w) {
+                        marked[i..i + ws.len()].iter_mut().for_each(|m| *m = true);
+                        found = true;
+                    }
+                }
+            }
+        }
+        if !found {
+            return None;
+        }
+    }
+    Some(marked)
+}
+
+/// Excerpt of `body` around the first marked token, with marked tokens wrapped in `<mark>`.
+fn snippet(body: &str, tokens: &[Token], marked: &[bool]) -> String {
+    if tokens.is_empty() {
+        return String::new();
+    }
+    let first = marked.iter().position(|m| *m).unwrap_or(0);
+    let from = first.saturating_sub(SNIPPET_WORDS / 3);
+    let to = (from + SNIPPET_WORDS).min(tokens.len());
+    let mut snippet = String::new();
+    let mut pos = tokens[from].start;
+    for (t, m) in tokens[from..to].iter().zip(&marked[from..to]) {
+        snippet.push_str(&body[pos..t.start]);
+        if *m {
+            snippet.push_str("<mark>");
+            snippet.push_str(&body[t.start..t.end]);
+            snippet.push_str("</mark>");
+        } else {
+            snippet.push_str(&body[t.start..t.end]);
+        }
+        pos = t.end;
+    }
+    snippet
+}
+
+/// Rank and snippet for `body`, or `None` unless every term matches.
+fn match_body(terms: &SearchTerms, body: &str) -> Option<(f32, String)> {
+    let tokens = tokens(body);
+    let marked = mark(terms, &tokens)?;
+    let hits = marked.iter().filter(|m| **m).count();
+    let rank = hits as f32 / tokens.len() as f32;
+    Some((rank, snippet(body, &tokens, &marked)))
+}
+
+/// Snippet for a body a full-text index already matched. Should the index and these rules
+/// disagree on a word, the excerpt comes back without highlights rather than being dropped.
+#[cfg(feature = "sqlite")]
+pub(super) fn highlight(terms: &SearchTerms, body: &str) -> String {
+    let tokens = tokens(body);
+    let marked = mark(terms, &tokens).unwrap_or_else(|| vec![false; tokens.len()]);
+    snippet(body, &tokens, &marked)
+}
+
+/// Match `candidates` against `terms` and return one page, best match then newest first.
+pub(super) fn search(
+    candidates: impl Iterator<Item = Review>,
+    terms: &SearchTerms,
+    limit: i64,
+    offset: i64,
+) -> Vec<SearchRow> {
+    let mut hits: Vec<SearchRow> = candidates
+        .filter_map(|review| {
+            let (rank, snippet) = match_body(terms, review.body.as_deref()?)?;
+            Some(SearchRow {
+                review,
+                rank,
+                snippet,
+            })
+        })
+        .collect();
+    hits.sort_by(|a, b| {
+        b.rank
+            .total_cmp(&a.rank)
+            .then((b.review.created_at, b.review.id).cmp(&(a.review.created_at, a.review.id)))
+    });
+    hits.into_iter()
+        .skip(usize::try_from(offset).unwrap_or(0))
+        .take(usize::try_from(limit).unwrap_or(0))
+        .collect()
+}
@@ -0,0 +1,49 @@
+//! Time-series bucketing in UTC for backends without time zone support.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Datelike, Duration, Months, NaiveTime, Utc};
+
+use crate::models::{TimeBucket, TimeseriesRow};
+
+/// Start of the UTC bucket containing `t`, as Postgres `date_trunc` would compute it.
+pub(super) fn truncate(bucket: TimeBucket, t: DateTime<Utc>) -> DateTime<Utc> {
+    let date = t.date_naive();
+    let date = match bucket {
+        TimeBucket::Day => date,
+        TimeBucket::Week => date - Duration::days(i64::from(date.weekday().num_days_from_monday())),
+        TimeBucket::Month => date.with_day(1).unwrap_or(date),
+    };
+    date.and_time(NaiveTime::MIN).and_utc()
+}
+
+fn next(bucket: TimeBucket, start: DateTime<Utc>) -> DateTime<Utc> {
+    match bucket {
+        TimeBucket::Day => start + Duration::days(1),
+        TimeBucket::Week => start + Duration::weeks(1),
+        TimeBucket::Month => start + Months::new(1),
+    }
+}
+
+/// One row per bucket overlapping `[from, to)`, taking `(count, rating sum)` from `totals`
+/// keyed by bucket start and zero elsewhere.
+pub(super) fn zero_filled(
+    bucket: TimeBucket,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    totals: &HashMap<DateTime<Utc>, (i64, i64)>,
+) -> Vec<TimeseriesRow> {
+    let mut rows = Vec::new();
+    let last = truncate(bucket, to - Duration::microseconds(1));
+    let mut start = truncate(bucket, from);
+    while start <= last {
+        let (count, sum) = totals.get(&start).copied().unwrap_or_default();
+        rows.push(TimeseriesRow {
+            bucket_start: start,
+            review_count: count,
+            avg_rating: (count > 0).then(|| sum as f64 / count as f64),
+        });
+        start = next(bucket, start);
+    }
+    rows
+}
@@ -1,8 +1,15 @@
 //! API route definitions. Group routes by domain for clarity as the service grows.
 
-use axum::{routing::get, Router};
+use std::sync::Arc;
+
+use axum::{
+    middleware,
+    routing::{delete, get, post, put},
+    Router,
+};
 
 use crate::handlers;
+use crate::rate_limit::{self, RateLimit, RateLimitStore, RateLimiter, RateLimits};
 use crate::service::ReviewService;
 
 /// Health check routes.
@@ -13,19 +20,121 @@ fn health_routes() -> Router<ReviewService> {
 /// Review CRUD routes.
 fn review_routes() -> Router<ReviewService> {
     Router::new()
-        .route("/reviews", get(handlers::list_reviews).post(handlers::create_review))
-        .route("/reviews/:id", get(handlers::get_review))
+        .route("/reviews", get(handlers::list_reviews))
+        .route("/reviews/search", get(handlers::search_reviews))
+        .route(
+            "/reviews/:id",
+            get(handlers::get_review)
+                .patch(handlers::update_review)
+                .delete(handlers::delete_review),
+        )
+        .route("/reviews/:id/revisions", get(handlers::list_revisions))
+        .route("/reviews/:id/reports", post(handlers::report_review))
+        .route(
+            "/reviews/:id/reply",
+            post(handlers::reply_to_review)
+                .patch(handlers::update_reply)
+                .delete(handlers::delete_reply),
+        )
+}
+
+/// Routes that write a new review or replace one; throttled together.
+fn review_submission_routes() -> Router<ReviewService> {
+    Router::new()
+        .route("/reviews", post(handlers::create_review))
+        .route(
+            "/products/:product_id/reviews/mine",
+            put(handlers::upsert_my_review),
+        )
+}
+
+/// Helpful-vote routes; throttled together.
+fn vote_routes() -> Router<ReviewService> {
+    Router::new()
+        .route("/reviews/:id/votes", post(handlers::vote_review))
+        .route("/reviews/:id/votes/mine", delete(handlers::retract_vote))
+}
+
+/// Per-product routes.
+fn product_routes() -> Router<ReviewService> {
+    Router::new().route("/products/:product_id/stats", get(handlers::product_stats))
 }
 
 /// Stats / dashboard routes.
 fn stats_routes() -> Router<ReviewService> {
-    Router::new().route("/stats/dashboard", get(handlers::dashboard_stats))
+    Router::new()
+        .route("/stats/dashboard", get(handlers::dashboard_stats))
+        .route("/stats/timeseries", get(handlers::timeseries))
+        .route("/stats/products/top", get(handlers::top_products))
+}
+
+/// Moderator routes.
+fn moderation_routes() -> Router<ReviewService> {
+    Router::new()
+        .route("/moderation/queue", get(handlers::moderation_queue))
+        .route(
+            "/moderation/reviews/:id/approve",
+            post(handlers::approve_review),
+        )
+        .route(
+            "/moderation/reviews/:id/reject",
+            post(handlers::reject_review),
+        )
+        .route("/moderation/reports", get(handlers::list_reports))
+        .route("/moderation/replies", get(handlers::reply_queue))
+        .route(
+            "/moderation/reviews/:id/reply/approve",
+            post(handlers::approve_reply),
+        )
+        .route(
+            "/moderation/reviews/:id/reply/reject",
+            post(handlers::reject_reply),
+        )
+}
+
+/// Operational / admin routes.
+fn admin_routes() -> Router<ReviewService> {
+    Router::new()
+        .route(
+            "/admin/aggregates/reconcile",
+            post(handlers::reconcile_aggregates),
+        )
+        .route("/admin/reviews/purge", post(handlers::purge_deleted))
+        .route("/reviews/:id/restore", post(handlers::restore_review))
+}
+
+/// Throttle every route in `routes` under `limit`, sharing one bucket per caller across the
+/// group. `None` leaves the group unthrottled.
+fn throttled(
+    routes: Router<ReviewService>,
+    group: &'static str,
+    limit: Option<RateLimit>,
+    store: &Arc<dyn RateLimitStore>,
+) -> Router<ReviewService> {
+    match limit {
+        Some(limit) => routes.route_layer(middleware::from_fn_with_state(
+            RateLimiter::new(group, limit, store.clone()),
+            rate_limit::throttle,
+        )),
+        None => routes,
+    }
 }
 
-/// All API routes combined. Add new route groups here as the service grows.
-pub fn api_routes() -> Router<ReviewService> {
+/// All API routes combined. Add new route groups here as the service grows, and give any that
+/// write on a caller's behalf a rate limit.
+pub fn api_routes(limits: &RateLimits, store: Arc<dyn RateLimitStore>) -> Router<ReviewService> {
     Router::new()
         .merge(health_routes())
         .merge(review_routes())
+        .merge(throttled(
+            review_submission_routes(),
+            "reviews",
+            limits.reviews,
+            &store,
+        ))
+        .merge(throttled(vote_routes(), "votes", limits.votes, &store))
+        .merge(product_routes())
         .merge(stats_routes())
+        .merge(moderation_routes())
+        .merge(admin_routes())
 }
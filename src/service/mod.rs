@@ -1,5 +1,16 @@
 //! Business logic layer. Services orchestrate repositories and enforce rules.
 
 mod review_service;
+mod scoring;
+mod screening;
+mod validation;
 
-pub use review_service::ReviewService;
+pub use review_service::{
+    ReviewService, DEFAULT_IDEMPOTENCY_TTL_HOURS, DEFAULT_REPORT_THRESHOLD, DEFAULT_RETENTION_DAYS,
+};
+pub use scoring::{wilson_lower_bound, RatingPrior};
+pub use screening::{
+    BannedWords, ContactDetails, ExcessiveCaps, RepeatedChars, Screener, ScreeningChain,
+    ScreeningConfig, Verdict,
+};
+pub use validation::{ValidationLimits, MAX_RATING, MIN_RATING};
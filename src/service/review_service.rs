@@ -1,50 +1,961 @@
 //! Review business logic. Handles validation, orchestration, and mapping to API types.
 
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+use sha2::{Digest, Sha256};
 use uuid::Uuid;
 
-use crate::models::{CreateReview, DashboardStats, Review, ReviewResponse};
-use crate::repository::ReviewRepository;
+use crate::auth;
+use crate::error::{FieldError, ReviewError};
+use crate::extractors::ApiClient;
+use crate::models::{
+    AggregateCounts, AggregateDrift, ApiKey, CastVote, CreateReport, CreateReview, DashboardStats,
+    IdempotencyRecord, Idempotent, LeaderboardPage, LeaderboardQuery, ListReviewsQuery,
+    ModerationDecision, ModerationInfo, ModerationQueueQuery, NewApiKey, ProductAggregate,
+    ProductRanking, ProductStats, PurgeReport, ReconcileReport, ReplyInput, ReplyPage,
+    ReplyQueueQuery, ReplyResponse, ReportListQuery, ReportPage, ReportResponse, Review,
+    ReviewFilter, ReviewPage, ReviewReply, ReviewReport, ReviewResponse, ReviewSort, ReviewStatus,
+    RevisionResponse, Screened, SearchHit, SearchPage, SearchQuery, TimeWindow, Timeseries,
+    TimeseriesPoint, TimeseriesQuery, UpdateReview, UpsertReview, VoteKind,
+};
+use crate::pagination::{self, Cursor};
+use crate::repository::{ReviewRepository, ReviewStore};
+use crate::service::scoring::RatingPrior;
+use crate::service::screening::{ScreeningChain, ScreeningConfig, Verdict};
+use crate::service::validation::{self, ValidationLimits};
+
+/// Range used by the time series when the caller gives no `from`.
+const DEFAULT_TIMESERIES_DAYS: i64 = 30;
+/// Upper bound on buckets per time-series request.
+const MAX_TIMESERIES_BUCKETS: i64 = 1000;
+/// Reports after which a published review is hidden, unless configured otherwise.
+pub const DEFAULT_REPORT_THRESHOLD: u32 = 3;
+/// Days a soft-deleted review is kept before it may be purged, unless configured otherwise.
+pub const DEFAULT_RETENTION_DAYS: u32 = 30;
+/// Hours a response is kept for replay under its `Idempotency-Key`, unless configured otherwise.
+pub const DEFAULT_IDEMPOTENCY_TTL_HOURS: u32 = 24;
+/// Status `POST /reviews` answers with, stored for replay.
+const CREATED_STATUS: i32 = 201;
 
 /// Application service for reviews and review-derived stats.
 #[derive(Clone)]
 pub struct ReviewService {
-    repo: ReviewRepository,
+    repo: Arc<dyn ReviewStore>,
+    limits: ValidationLimits,
+    prior: RatingPrior,
+    /// Status given to new and edited reviews that pass screening.
+    initial_status: ReviewStatus,
+    screening: ScreeningChain,
+    /// Reports that hide a published review pending moderation.
+    report_threshold: u32,
+    /// How long soft-deleted reviews are kept before [`ReviewService::purge_deleted`] removes them.
+    deleted_retention: Duration,
+    /// How long a response is kept for replay under its idempotency key.
+    idempotency_ttl: Duration,
 }
 
 impl ReviewService {
     pub fn new(pool: sqlx::PgPool) -> Self {
+        Self::with_store(Arc::new(ReviewRepository::new(pool)))
+    }
+
+    /// Build the service over any storage backend, e.g. [`InMemoryReviewStore`](crate::repository::InMemoryReviewStore).
+    pub fn with_store(store: Arc<dyn ReviewStore>) -> Self {
         Self {
-            repo: ReviewRepository::new(pool),
+            repo: store,
+            limits: ValidationLimits::default(),
+            prior: RatingPrior::default(),
+            initial_status: ReviewStatus::Pending,
+            screening: ScreeningChain::from_config(&ScreeningConfig::default()),
+            report_threshold: DEFAULT_REPORT_THRESHOLD,
+            deleted_retention: Duration::days(DEFAULT_RETENTION_DAYS.into()),
+            idempotency_ttl: Duration::hours(DEFAULT_IDEMPOTENCY_TTL_HOURS.into()),
         }
     }
 
-    pub async fn list_reviews(&self) -> Result<Vec<ReviewResponse>, String> {
-        let rows = self.repo.find_all().await.map_err(|e| e.to_string())?;
-        Ok(rows.into_iter().map(review_to_response).collect())
+    /// Replace the default input limits.
+    pub fn with_limits(mut self, limits: ValidationLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Replace the default prior used for Bayesian scores.
+    pub fn with_prior(mut self, prior: RatingPrior) -> Self {
+        self.prior = prior;
+        self
+    }
+
+    /// Replace the default screeners run over review bodies before they are stored.
+    pub fn with_screening(mut self, screening: ScreeningChain) -> Self {
+        self.screening = screening;
+        self
+    }
+
+    /// Hide a published review once it has this many reports. Values below 1 are treated as 1.
+    pub fn with_report_threshold(mut self, threshold: u32) -> Self {
+        self.report_threshold = threshold.max(1);
+        self
+    }
+
+    /// Keep soft-deleted reviews this long before purging them.
+    pub fn with_deleted_retention(mut self, retention: Duration) -> Self {
+        self.deleted_retention = retention;
+        self
+    }
+
+    /// Keep responses for replay under their idempotency key this long.
+    pub fn with_idempotency_ttl(mut self, ttl: Duration) -> Self {
+        self.idempotency_ttl = ttl;
+        self
+    }
+
+    /// Publish new and edited reviews immediately instead of queueing them for moderation.
+    pub fn with_auto_approve(mut self, auto_approve: bool) -> Self {
+        self.initial_status = if auto_approve {
+            ReviewStatus::Approved
+        } else {
+            ReviewStatus::Pending
+        };
+        self
+    }
+
+    pub async fn list_reviews(
+        &self,
+        filter: ReviewFilter,
+        query: ListReviewsQuery,
+    ) -> Result<ReviewPage, ReviewError> {
+        let limit = pagination::clamp_limit(query.limit) as usize;
+        self.page(
+            &filter,
+            ReviewStatus::Approved,
+            query.sort.unwrap_or_default(),
+            query.cursor,
+            limit,
+        )
+        .await
     }
 
-    pub async fn get_review(&self, id: Uuid) -> Result<ReviewResponse, String> {
-        let r = self.repo.find_by_id(id).await.map_err(|e| e.to_string())?;
-        r.map(review_to_response)
-            .ok_or_else(|| "Not found".to_string())
+    /// Reviews awaiting moderation, oldest first.
+    pub async fn moderation_queue(
+        &self,
+        query: ModerationQueueQuery,
+    ) -> Result<ReviewPage, ReviewError> {
+        let limit = pagination::clamp_limit(query.limit) as usize;
+        let status = query.status.unwrap_or(ReviewStatus::Pending);
+        self.page(
+            &ReviewFilter::default(),
+            status,
+            ReviewSort::Oldest,
+            query.cursor,
+            limit,
+        )
+        .await
     }
 
-    pub async fn create_review(&self, body: CreateReview) -> Result<ReviewResponse, String> {
+    async fn page(
+        &self,
+        filter: &ReviewFilter,
+        status: ReviewStatus,
+        sort: ReviewSort,
+        cursor: Option<Cursor>,
+        limit: usize,
+    ) -> Result<ReviewPage, ReviewError> {
+        // Fetch one extra row to learn whether another page follows.
+        let mut rows = self
+            .repo
+            .find_page(filter, status, sort, cursor, limit as i64 + 1)
+            .await?;
+        let next_cursor = if rows.len() > limit {
+            rows.truncate(limit);
+            rows.last().map(|r| {
+                Cursor::new(r.rating, r.created_at, r.id)
+                    .with_helpful_score(r.helpful_score)
+                    .encode()
+            })
+        } else {
+            None
+        };
+        let ids: Vec<Uuid> = rows.iter().map(|r| r.id).collect();
+        let mut replies = self.published_replies(&ids).await?;
+        Ok(ReviewPage {
+            items: rows
+                .into_iter()
+                .map(|r| {
+                    let reply = replies.remove(&r.id);
+                    review_to_response(r, reply)
+                })
+                .collect(),
+            next_cursor,
+        })
+    }
+
+    /// Full-text search over review bodies, best match first.
+    pub async fn search_reviews(&self, query: SearchQuery) -> Result<SearchPage, ReviewError> {
+        let limit = pagination::clamp_limit(query.limit) as usize;
+        let offset = query.offset.unwrap_or(0) as usize;
+        // Fetch one extra row to learn whether another page follows.
+        let mut rows = self
+            .repo
+            .search(&query.q, query.product_id, limit as i64 + 1, offset as i64)
+            .await?;
+        let next_offset = if rows.len() > limit {
+            rows.truncate(limit);
+            Some((offset + limit) as u64)
+        } else {
+            None
+        };
+        let ids: Vec<Uuid> = rows.iter().map(|r| r.review.id).collect();
+        let mut replies = self.published_replies(&ids).await?;
+        Ok(SearchPage {
+            items: rows
+                .into_iter()
+                .map(|r| {
+                    let reply = replies.remove(&r.review.id);
+                    SearchHit {
+                        review: review_to_response(r.review, reply),
+                        rank: r.rank,
+                        snippet: r.snippet,
+                    }
+                })
+                .collect(),
+            next_offset,
+        })
+    }
+
+    /// Get a review. Unapproved reviews are visible only to their author.
+    pub async fn get_review(
+        &self,
+        id: Uuid,
+        caller: Option<Uuid>,
+    ) -> Result<ReviewResponse, ReviewError> {
+        let r = self.visible_review(id, caller).await?;
+        self.respond(r).await
+    }
+
+    /// Earlier versions of a review, newest first. Visible to whoever may see the review.
+    pub async fn list_revisions(
+        &self,
+        id: Uuid,
+        caller: Option<Uuid>,
+    ) -> Result<Vec<RevisionResponse>, ReviewError> {
+        self.visible_review(id, caller).await?;
+        let rows = self.repo.find_revisions(id).await?;
+        Ok(rows
+            .into_iter()
+            .map(|r| RevisionResponse {
+                revision: r.revision,
+                rating: r.rating,
+                body: r.body,
+                created_at: r.created_at,
+                replaced_at: r.replaced_at,
+            })
+            .collect())
+    }
+
+    pub async fn create_review(&self, body: CreateReview) -> Result<ReviewResponse, ReviewError> {
+        let body = validation::validate_create(body, &self.limits)?;
+        let status = self.screen(body.body.as_deref())?.status;
         let id = Uuid::new_v4();
-        let r = self.repo.create(id, &body).await.map_err(|e| e.to_string())?;
-        Ok(review_to_response(r))
+        let r = self.repo.create(id, &body, status).await?;
+        Ok(review_to_response(r, None))
+    }
+
+    /// [`create_review`](Self::create_review), safe to retry under an idempotency `key` owned by
+    /// `scope`. The first request with the key runs and its response is kept for the idempotency
+    /// TTL; a retry with the same payload gets that response back. Reusing the key for another
+    /// payload fails with 422, and retrying while the first request is still running with 409.
+    /// A request that fails keeps nothing, so the key can be retried.
+    pub async fn create_review_idempotent(
+        &self,
+        scope: &str,
+        key: &str,
+        body: CreateReview,
+    ) -> Result<Idempotent<ReviewResponse>, ReviewError> {
+        let key = validation::validate_idempotency_key(key)?;
+        let now = Utc::now();
+        let record = IdempotencyRecord {
+            scope: scope.to_string(),
+            key: key.to_string(),
+            request_hash: request_hash(&body),
+            response_status: None,
+            response_body: None,
+            created_at: now,
+            expires_at: now + self.idempotency_ttl,
+        };
+        if let Some(existing) = self.repo.claim_idempotency_key(&record).await? {
+            if existing.request_hash != record.request_hash {
+                return Err(ReviewError::invalid_fields(vec![FieldError::new(
+                    "Idempotency-Key",
+                    "reused",
+                    "was already used for a different request",
+                )]));
+            }
+            return match (existing.response_status, existing.response_body) {
+                (Some(status), Some(body)) => Ok(Idempotent::Replayed {
+                    status: status as u16,
+                    body,
+                }),
+                _ => Err(ReviewError::Conflict(
+                    "A request with this Idempotency-Key is still in progress".to_string(),
+                )),
+            };
+        }
+        let r = match self.create_review(body).await {
+            Ok(r) => r,
+            Err(e) => {
+                self.repo.release_idempotency_key(scope, key).await?;
+                return Err(e);
+            }
+        };
+        let stored = serde_json::to_string(&r).unwrap_or_default();
+        // The review exists now; a failure here only costs retries a 409 until the key expires.
+        if let Err(e) = self
+            .repo
+            .complete_idempotency_key(scope, key, CREATED_STATUS, &stored)
+            .await
+        {
+            tracing::error!(error = %e, scope, key, "storing idempotent response failed");
+        }
+        Ok(Idempotent::Fresh(r))
+    }
+
+    /// Create or replace `caller`'s review of `product_id`. Returns the review and whether it is new.
+    pub async fn upsert_review(
+        &self,
+        product_id: Uuid,
+        caller: Uuid,
+        input: UpsertReview,
+    ) -> Result<(ReviewResponse, bool), ReviewError> {
+        let body = CreateReview {
+            product_id,
+            user_id: caller,
+            rating: input.rating,
+            body: input.body,
+        };
+        let body = validation::validate_create(body, &self.limits)?;
+        let screened = self.screen(body.body.as_deref())?;
+        let (r, created) = self.repo.upsert(Uuid::new_v4(), &body, screened).await?;
+        Ok((self.respond(r).await?, created))
+    }
+
+    /// Update a review on behalf of `caller`, who must be its author.
+    pub async fn update_review(
+        &self,
+        id: Uuid,
+        caller: Uuid,
+        patch: UpdateReview,
+    ) -> Result<ReviewResponse, ReviewError> {
+        let patch = validation::validate_update(patch, &self.limits)?;
+        let existing = self.owned_review(id, caller).await?;
+        if patch.is_empty() {
+            return self.respond(existing).await;
+        }
+        let body = patch.body.clone().unwrap_or(existing.body);
+        let screened = self.screen(body.as_deref())?;
+        let r = self.repo.update(id, &patch, screened).await?;
+        self.respond(r.ok_or(ReviewError::NotFound("Review"))?)
+            .await
+    }
+
+    /// Delete a review on behalf of `caller`, who must be its author.
+    pub async fn delete_review(&self, id: Uuid, caller: Uuid) -> Result<(), ReviewError> {
+        self.owned_review(id, caller).await?;
+        if self.repo.delete(id).await? {
+            Ok(())
+        } else {
+            Err(ReviewError::NotFound("Review"))
+        }
+    }
+
+    /// Bring back a soft-deleted review. Conflicts if its author has reviewed the product again since.
+    pub async fn restore_review(&self, id: Uuid) -> Result<ReviewResponse, ReviewError> {
+        let r = self
+            .repo
+            .restore(id)
+            .await?
+            .ok_or(ReviewError::NotFound("Review"))?;
+        self.respond(r).await
+    }
+
+    /// Record or change `voter`'s helpfulness vote on a published review. Authors may not vote on their own.
+    pub async fn vote_review(
+        &self,
+        id: Uuid,
+        voter: Uuid,
+        input: CastVote,
+    ) -> Result<ReviewResponse, ReviewError> {
+        let review = self.published_review(id).await?;
+        if review.user_id == voter {
+            return Err(ReviewError::Forbidden(
+                "Authors cannot vote on their own review".to_string(),
+            ));
+        }
+        let helpful = input.vote == VoteKind::Helpful;
+        let r = self.repo.vote(id, voter, Some(helpful)).await?;
+        self.respond(r.ok_or(ReviewError::NotFound("Review"))?)
+            .await
+    }
+
+    /// Withdraw `voter`'s vote, if any.
+    pub async fn retract_vote(&self, id: Uuid, voter: Uuid) -> Result<(), ReviewError> {
+        self.published_review(id).await?;
+        self.repo
+            .vote(id, voter, None)
+            .await?
+            .ok_or(ReviewError::NotFound("Review"))?;
+        Ok(())
+    }
+
+    /// Report a published review on behalf of `reporter`. Enough reports hide it until a moderator decides.
+    pub async fn report_review(
+        &self,
+        id: Uuid,
+        reporter: Uuid,
+        input: CreateReport,
+    ) -> Result<ReportResponse, ReviewError> {
+        let input = validation::validate_report(input)?;
+        self.published_review(id).await?;
+        let (report, hidden) = self
+            .repo
+            .report(
+                Uuid::new_v4(),
+                id,
+                reporter,
+                &input,
+                i64::from(self.report_threshold),
+            )
+            .await?
+            .ok_or(ReviewError::NotFound("Review"))?;
+        if hidden {
+            tracing::info!(review_id = %id, "review hidden after reports");
+        }
+        Ok(report_to_response(report))
+    }
+
+    /// Reports for moderators, newest first.
+    pub async fn list_reports(&self, query: ReportListQuery) -> Result<ReportPage, ReviewError> {
+        let limit = pagination::clamp_limit(query.limit) as usize;
+        let offset = query.offset.unwrap_or(0) as usize;
+        // Fetch one extra row to learn whether another page follows.
+        let mut rows = self
+            .repo
+            .find_reports(query.review_id, limit as i64 + 1, offset as i64)
+            .await?;
+        let next_offset = if rows.len() > limit {
+            rows.truncate(limit);
+            Some((offset + limit) as u64)
+        } else {
+            None
+        };
+        Ok(ReportPage {
+            items: rows.into_iter().map(report_to_response).collect(),
+            next_offset,
+        })
+    }
+
+    /// Publish a review on behalf of `moderator`.
+    pub async fn approve_review(
+        &self,
+        id: Uuid,
+        moderator: Uuid,
+        decision: ModerationDecision,
+    ) -> Result<ReviewResponse, ReviewError> {
+        let reason = decision.reason.filter(|r| !r.trim().is_empty());
+        self.moderate(id, ReviewStatus::Approved, reason, moderator)
+            .await
+    }
+
+    /// Reject a review on behalf of `moderator`. A reason is required.
+    pub async fn reject_review(
+        &self,
+        id: Uuid,
+        moderator: Uuid,
+        decision: ModerationDecision,
+    ) -> Result<ReviewResponse, ReviewError> {
+        let reason = rejection_reason(decision)?;
+        self.moderate(id, ReviewStatus::Rejected, Some(reason), moderator)
+            .await
+    }
+
+    async fn moderate(
+        &self,
+        id: Uuid,
+        status: ReviewStatus,
+        reason: Option<String>,
+        moderator: Uuid,
+    ) -> Result<ReviewResponse, ReviewError> {
+        let r = self
+            .repo
+            .moderate(id, status, reason.as_deref(), moderator)
+            .await?;
+        self.respond(r.ok_or(ReviewError::NotFound("Review"))?)
+            .await
+    }
+
+    /// Reply to a published review on behalf of `merchant`. A review takes one reply, and its author may not reply.
+    pub async fn reply_to_review(
+        &self,
+        id: Uuid,
+        merchant: Uuid,
+        input: ReplyInput,
+    ) -> Result<ReplyResponse, ReviewError> {
+        let input = validation::validate_reply(input, &self.limits)?;
+        let review = self.published_review(id).await?;
+        if review.user_id == merchant {
+            return Err(ReviewError::Forbidden(
+                "Authors cannot reply to their own review".to_string(),
+            ));
+        }
+        let status = self.screen(Some(&input.body))?.status;
+        let reply = self
+            .repo
+            .create_reply(id, merchant, &input.body, status)
+            .await?;
+        Ok(reply_to_response(reply))
+    }
+
+    /// Edit a reply on behalf of `merchant`, who must have written it. The new body is screened again.
+    pub async fn update_reply(
+        &self,
+        id: Uuid,
+        merchant: Uuid,
+        input: ReplyInput,
+    ) -> Result<ReplyResponse, ReviewError> {
+        let input = validation::validate_reply(input, &self.limits)?;
+        self.owned_reply(id, merchant).await?;
+        let status = self.screen(Some(&input.body))?.status;
+        let reply = self.repo.update_reply(id, &input.body, status).await?;
+        reply
+            .map(reply_to_response)
+            .ok_or(ReviewError::NotFound("Reply"))
+    }
+
+    /// Delete a reply on behalf of `merchant`, who must have written it.
+    pub async fn delete_reply(&self, id: Uuid, merchant: Uuid) -> Result<(), ReviewError> {
+        self.owned_reply(id, merchant).await?;
+        if self.repo.delete_reply(id).await? {
+            Ok(())
+        } else {
+            Err(ReviewError::NotFound("Reply"))
+        }
+    }
+
+    /// Replies awaiting moderation, oldest first.
+    pub async fn reply_queue(&self, query: ReplyQueueQuery) -> Result<ReplyPage, ReviewError> {
+        let limit = pagination::clamp_limit(query.limit) as usize;
+        let offset = query.offset.unwrap_or(0) as usize;
+        let status = query.status.unwrap_or(ReviewStatus::Pending);
+        // Fetch one extra row to learn whether another page follows.
+        let mut rows = self
+            .repo
+            .find_reply_queue(status, limit as i64 + 1, offset as i64)
+            .await?;
+        let next_offset = if rows.len() > limit {
+            rows.truncate(limit);
+            Some((offset + limit) as u64)
+        } else {
+            None
+        };
+        Ok(ReplyPage {
+            items: rows.into_iter().map(reply_to_response).collect(),
+            next_offset,
+        })
+    }
+
+    /// Publish the reply to review `id` on behalf of `moderator`.
+    pub async fn approve_reply(
+        &self,
+        id: Uuid,
+        moderator: Uuid,
+        decision: ModerationDecision,
+    ) -> Result<ReplyResponse, ReviewError> {
+        let reason = decision.reason.filter(|r| !r.trim().is_empty());
+        self.moderate_reply(id, ReviewStatus::Approved, reason, moderator)
+            .await
     }
 
-    pub async fn get_dashboard_stats(&self) -> Result<DashboardStats, String> {
-        let (total, avg_rating) = self.repo.get_stats().await.map_err(|e| e.to_string())?;
+    /// Reject the reply to review `id` on behalf of `moderator`. A reason is required.
+    pub async fn reject_reply(
+        &self,
+        id: Uuid,
+        moderator: Uuid,
+        decision: ModerationDecision,
+    ) -> Result<ReplyResponse, ReviewError> {
+        let reason = rejection_reason(decision)?;
+        self.moderate_reply(id, ReviewStatus::Rejected, Some(reason), moderator)
+            .await
+    }
+
+    async fn moderate_reply(
+        &self,
+        id: Uuid,
+        status: ReviewStatus,
+        reason: Option<String>,
+        moderator: Uuid,
+    ) -> Result<ReplyResponse, ReviewError> {
+        let reply = self
+            .repo
+            .moderate_reply(id, status, reason.as_deref(), moderator)
+            .await?;
+        reply
+            .map(reply_to_response)
+            .ok_or(ReviewError::NotFound("Reply"))
+    }
+
+    /// Screening of a review body: rejected bodies fail, flagged ones wait for a moderator.
+    fn screen(&self, body: Option<&str>) -> Result<Screened, ReviewError> {
+        match body.map_or(Verdict::Accept, |b| self.screening.screen(b)) {
+            Verdict::Accept => Ok(Screened::accepted(self.initial_status)),
+            Verdict::Flag(reason) => {
+                tracing::info!(%reason, "review flagged for moderation");
+                Ok(Screened::flagged())
+            }
+            Verdict::Reject(reason) => Err(ReviewError::invalid_fields(vec![FieldError::new(
+                "body", "rejected", reason,
+            )])),
+        }
+    }
+
+    /// A review `caller` may see: approved, or their own. Others are reported as missing.
+    async fn visible_review(&self, id: Uuid, caller: Option<Uuid>) -> Result<Review, ReviewError> {
+        self.repo
+            .find_by_id(id)
+            .await?
+            .filter(|r| r.status == ReviewStatus::Approved || caller == Some(r.user_id))
+            .ok_or(ReviewError::NotFound("Review"))
+    }
+
+    /// A review the public can see; others are reported as missing.
+    async fn published_review(&self, id: Uuid) -> Result<Review, ReviewError> {
+        self.repo
+            .find_by_id(id)
+            .await?
+            .filter(|r| r.status == ReviewStatus::Approved)
+            .ok_or(ReviewError::NotFound("Review"))
+    }
+
+    /// Approved replies to any of `review_ids`, keyed by review.
+    async fn published_replies(
+        &self,
+        review_ids: &[Uuid],
+    ) -> Result<HashMap<Uuid, ReviewReply>, ReviewError> {
+        if review_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let replies = self.repo.find_replies(review_ids).await?;
+        Ok(replies
+            .into_iter()
+            .filter(|r| r.status == ReviewStatus::Approved)
+            .map(|r| (r.review_id, r))
+            .collect())
+    }
+
+    /// Map a review for the API with its published reply, if any.
+    async fn respond(&self, r: Review) -> Result<ReviewResponse, ReviewError> {
+        let reply = self.published_replies(&[r.id]).await?.remove(&r.id);
+        Ok(review_to_response(r, reply))
+    }
+
+    async fn owned_reply(&self, id: Uuid, merchant: Uuid) -> Result<ReviewReply, ReviewError> {
+        let reply = self
+            .repo
+            .find_replies(&[id])
+            .await?
+            .pop()
+            .ok_or(ReviewError::NotFound("Reply"))?;
+        if reply.merchant_id != merchant {
+            return Err(ReviewError::Forbidden(
+                "Only the merchant who wrote this reply may modify it".to_string(),
+            ));
+        }
+        Ok(reply)
+    }
+
+    async fn owned_review(&self, id: Uuid, caller: Uuid) -> Result<Review, ReviewError> {
+        let r = self
+            .repo
+            .find_by_id(id)
+            .await?
+            .ok_or(ReviewError::NotFound("Review"))?;
+        if r.user_id != caller {
+            return Err(ReviewError::Forbidden(
+                "Only the author may modify this review".to_string(),
+            ));
+        }
+        Ok(r)
+    }
+
+    pub async fn get_dashboard_stats(&self) -> Result<DashboardStats, ReviewError> {
+        let (total, avg_rating) = self.repo.get_stats().await?;
         Ok(DashboardStats {
             total_reviews: total as u64,
             avg_rating,
         })
     }
+
+    pub async fn get_product_stats(&self, product_id: Uuid) -> Result<ProductStats, ReviewError> {
+        let agg = self.repo.find_aggregate(product_id).await?;
+        let (count, sum) = agg
+            .as_ref()
+            .map(|a| (a.review_count.max(0) as u64, a.rating_sum.max(0) as u64))
+            .unwrap_or_default();
+        let avg_rating = if count == 0 {
+            0.0
+        } else {
+            sum as f64 / count as f64
+        };
+        Ok(ProductStats {
+            product_id,
+            review_count: count,
+            avg_rating,
+            histogram: agg
+                .as_ref()
+                .map(ProductAggregate::histogram)
+                .unwrap_or_default(),
+            latest_review_at: agg.and_then(|a| a.latest_review_at),
+            bayesian_score: self.prior.weighted_average(count, sum),
+        })
+    }
+
+    /// Review volume and mean rating per time bucket, zero-filled across the whole range.
+    pub async fn get_timeseries(&self, query: TimeseriesQuery) -> Result<Timeseries, ReviewError> {
+        let bucket = query.bucket.unwrap_or_default();
+        let to = query.to.unwrap_or_else(Utc::now);
+        let from = query
+            .from
+            .unwrap_or(to - Duration::days(DEFAULT_TIMESERIES_DAYS));
+        let tz = query.tz.unwrap_or_else(|| "UTC".to_string());
+        if from >= to {
+            return Err(ReviewError::invalid_fields(vec![FieldError::new(
+                "from",
+                "out_of_range",
+                "must be before `to`",
+            )]));
+        }
+        if (to - from).num_days() / bucket.min_days() > MAX_TIMESERIES_BUCKETS {
+            return Err(ReviewError::invalid_fields(vec![FieldError::new(
+                "bucket",
+                "too_many_buckets",
+                format!(
+                    "range spans more than {} buckets; widen the bucket or narrow the range",
+                    MAX_TIMESERIES_BUCKETS
+                ),
+            )]));
+        }
+
+        let rows = self
+            .repo
+            .timeseries(bucket, &tz, from, to, query.product_id)
+            .await?;
+        Ok(Timeseries {
+            bucket,
+            tz,
+            from,
+            to,
+            points: rows
+                .into_iter()
+                .map(|r| TimeseriesPoint {
+                    bucket_start: r.bucket_start,
+                    review_count: r.review_count as u64,
+                    avg_rating: r.avg_rating,
+                })
+                .collect(),
+        })
+    }
+
+    /// One page of products ranked by score or volume, optionally within a trailing window.
+    pub async fn get_leaderboard(
+        &self,
+        query: LeaderboardQuery,
+    ) -> Result<LeaderboardPage, ReviewError> {
+        let order = query.order.unwrap_or_default();
+        let limit = pagination::clamp_limit(query.limit) as usize;
+        let offset = query.offset.unwrap_or(0) as usize;
+        let since = match query.window {
+            Some(w) => Some(
+                w.duration()
+                    .and_then(|d| Utc::now().checked_sub_signed(d))
+                    .ok_or_else(|| {
+                        ReviewError::invalid_fields(vec![FieldError::new(
+                            "window",
+                            "out_of_range",
+                            format!("must be at most {} days", TimeWindow::MAX_DAYS),
+                        )])
+                    })?,
+            ),
+            None => None,
+        };
+        // Fetch one extra row to learn whether another page follows.
+        let mut rows = self
+            .repo
+            .top_products(
+                order,
+                since,
+                i64::from(query.min_reviews.unwrap_or(1)),
+                self.prior.mean,
+                self.prior.weight,
+                limit as i64 + 1,
+                offset as i64,
+            )
+            .await?;
+        let next_offset = if rows.len() > limit {
+            rows.truncate(limit);
+            Some((offset + limit) as u64)
+        } else {
+            None
+        };
+        let items = rows
+            .into_iter()
+            .enumerate()
+            .map(|(i, a)| {
+                let (count, sum) = (a.review_count.max(0) as u64, a.rating_sum.max(0) as u64);
+                ProductRanking {
+                    rank: (offset + i + 1) as u64,
+                    product_id: a.product_id,
+                    review_count: count,
+                    avg_rating: if count == 0 {
+                        0.0
+                    } else {
+                        sum as f64 / count as f64
+                    },
+                    bayesian_score: self.prior.weighted_average(count, sum),
+                    histogram: a.histogram(),
+                    latest_review_at: a.latest_review_at,
+                }
+            })
+            .collect();
+        Ok(LeaderboardPage {
+            order,
+            window: query.window.map(|w| w.to_string()),
+            items,
+            next_offset,
+        })
+    }
+
+    /// Compare `product_rating_aggregates` with `reviews`, rebuilding it unless `dry_run`.
+    pub async fn reconcile_aggregates(
+        &self,
+        dry_run: bool,
+    ) -> Result<ReconcileReport, ReviewError> {
+        let (checked, drift) = self.repo.reconcile_aggregates(!dry_run).await?;
+        if !drift.is_empty() {
+            tracing::warn!(
+                products = drift.len(),
+                repaired = !dry_run,
+                "rating aggregates drifted"
+            );
+        }
+        Ok(ReconcileReport {
+            products_checked: checked as u64,
+            drift: drift
+                .into_iter()
+                .map(|(expected, stored)| AggregateDrift {
+                    product_id: expected.product_id,
+                    expected: aggregate_counts(&expected),
+                    stored: aggregate_counts(&stored),
+                })
+                .collect(),
+            repaired: !dry_run,
+        })
+    }
+
+    /// Permanently remove reviews that were soft-deleted longer ago than the retention period.
+    pub async fn purge_deleted(&self) -> Result<PurgeReport, ReviewError> {
+        let purged = self
+            .repo
+            .purge_deleted(Utc::now() - self.deleted_retention)
+            .await?;
+        if purged > 0 {
+            tracing::info!(purged, "purged soft-deleted reviews");
+        }
+        Ok(PurgeReport { purged })
+    }
+
+    /// Remove idempotency keys whose responses are past their TTL. Returns how many were removed.
+    pub async fn purge_idempotency_keys(&self) -> Result<u64, ReviewError> {
+        let purged = self.repo.purge_idempotency_keys(Utc::now()).await?;
+        if purged > 0 {
+            tracing::info!(purged, "purged expired idempotency keys");
+        }
+        Ok(purged)
+    }
+
+    /// Issue an API key for a backend service. Returns the stored key and its secret, which is
+    /// not kept and cannot be shown again.
+    pub async fn create_api_key(&self, input: NewApiKey) -> Result<(ApiKey, String), ReviewError> {
+        let input = validation::validate_api_key(input)?;
+        let secret = auth::generate_api_key();
+        let key = ApiKey {
+            id: Uuid::new_v4(),
+            name: input.name,
+            prefix: secret.chars().take(auth::API_KEY_PREFIX_CHARS).collect(),
+            key_hash: auth::hash_api_key(&secret),
+            scopes: input.scopes,
+            created_at: Utc::now(),
+            expires_at: input.expires_at,
+            revoked_at: None,
+        };
+        self.repo.create_api_key(&key).await?;
+        tracing::info!(key_id = %key.id, name = %key.name, "issued API key");
+        Ok((key, secret))
+    }
+
+    /// Every API key, newest first, including revoked and expired ones.
+    pub async fn list_api_keys(&self) -> Result<Vec<ApiKey>, ReviewError> {
+        self.repo.find_api_keys().await
+    }
+
+    /// Revoke an API key at once. Revoking a revoked key keeps its original revocation time.
+    pub async fn revoke_api_key(&self, id: Uuid) -> Result<ApiKey, ReviewError> {
+        let key = self
+            .repo
+            .revoke_api_key(id)
+            .await?
+            .ok_or(ReviewError::NotFound("API key"))?;
+        tracing::info!(key_id = %key.id, name = %key.name, "revoked API key");
+        Ok(key)
+    }
+
+    /// The backend service presenting `secret`, or `None` if the key is unknown, revoked or expired.
+    pub async fn authenticate_api_key(
+        &self,
+        secret: &str,
+    ) -> Result<Option<ApiClient>, ReviewError> {
+        let key = self
+            .repo
+            .find_api_key_by_hash(&auth::hash_api_key(secret))
+            .await?;
+        Ok(key.filter(|k| k.is_active(Utc::now())).map(|k| ApiClient {
+            key_id: k.id,
+            name: k.name,
+            scopes: k.scopes,
+        }))
+    }
 }
 
-fn review_to_response(r: Review) -> ReviewResponse {
+/// Hex SHA-256 of what a review creation depends on, to tell a retry from a different request.
+fn request_hash(body: &CreateReview) -> String {
+    let canonical = serde_json::json!([body.product_id, body.user_id, body.rating, body.body]);
+    format!("{:x}", Sha256::digest(canonical.to_string().as_bytes()))
+}
+
+/// A rejection reason from `decision`, which must not be blank.
+fn rejection_reason(decision: ModerationDecision) -> Result<String, ReviewError> {
+    decision
+        .reason
+        .filter(|r| !r.trim().is_empty())
+        .ok_or_else(|| {
+            ReviewError::invalid_fields(vec![FieldError::new(
+                "reason",
+                "blank",
+                "is required when rejecting",
+            )])
+        })
+}
+
+fn review_to_response(r: Review, reply: Option<ReviewReply>) -> ReviewResponse {
     ReviewResponse {
         id: r.id,
         product_id: r.product_id,
@@ -52,5 +963,57 @@ fn review_to_response(r: Review) -> ReviewResponse {
         rating: r.rating,
         body: r.body,
         created_at: r.created_at,
+        status: r.status,
+        helpful_count: r.helpful_count,
+        unhelpful_count: r.unhelpful_count,
+        revision: r.revision,
+        edited_at: r.edited_at,
+        moderation: r
+            .moderated_by
+            .zip(r.moderated_at)
+            .map(|(moderated_by, moderated_at)| ModerationInfo {
+                reason: r.moderation_reason,
+                moderated_by,
+                moderated_at,
+            }),
+        reply: reply.map(reply_to_response),
+    }
+}
+
+fn reply_to_response(r: ReviewReply) -> ReplyResponse {
+    ReplyResponse {
+        review_id: r.review_id,
+        merchant_id: r.merchant_id,
+        body: r.body,
+        status: r.status,
+        created_at: r.created_at,
+        updated_at: r.updated_at,
+        moderation: r
+            .moderated_by
+            .zip(r.moderated_at)
+            .map(|(moderated_by, moderated_at)| ModerationInfo {
+                reason: r.moderation_reason,
+                moderated_by,
+                moderated_at,
+            }),
+    }
+}
+
+fn report_to_response(r: ReviewReport) -> ReportResponse {
+    ReportResponse {
+        id: r.id,
+        review_id: r.review_id,
+        reporter_id: r.reporter_id,
+        reason: r.reason,
+        comment: r.comment,
+        created_at: r.created_at,
+    }
+}
+
+fn aggregate_counts(a: &ProductAggregate) -> AggregateCounts {
+    AggregateCounts {
+        review_count: a.review_count,
+        rating_sum: a.rating_sum,
+        histogram: a.histogram(),
     }
 }
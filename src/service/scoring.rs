@@ -0,0 +1,42 @@
+//! Rating math shared by stats endpoints.
+
+/// Prior used to shrink averages of sparsely reviewed products towards a typical rating.
+#[derive(Debug, Clone, Copy)]
+pub struct RatingPrior {
+    /// Rating assumed before any reviews arrive.
+    pub mean: f64,
+    /// How many pseudo-reviews at `mean` the prior is worth.
+    pub weight: f64,
+}
+
+impl Default for RatingPrior {
+    fn default() -> Self {
+        Self {
+            mean: 3.0,
+            weight: 5.0,
+        }
+    }
+}
+
+impl RatingPrior {
+    /// Bayesian average of `count` ratings summing to `sum`. Equals `mean` when `count` is 0.
+    pub fn weighted_average(&self, count: u64, sum: u64) -> f64 {
+        (self.weight * self.mean + sum as f64) / (self.weight + count as f64)
+    }
+}
+
+/// z-score for the 95% confidence level used by [`wilson_lower_bound`].
+const WILSON_Z: f64 = 1.96;
+
+/// Lower bound of the Wilson score interval for the share of helpful votes. Ranks a review
+/// with 40 of 50 helpful above one with 2 of 2, because the former is better evidenced.
+/// Zero without votes.
+pub fn wilson_lower_bound(helpful: u64, unhelpful: u64) -> f64 {
+    let n = (helpful + unhelpful) as f64;
+    if n == 0.0 {
+        return 0.0;
+    }
+    let p = helpful as f64 / n;
+    let z2 = WILSON_Z * WILSON_Z;
+    (p + z2 / (2.0 * n) - WILSON_Z * ((p * (1.0 - p) + z2 / (4.0 * n)) / n).sqrt()) / (1.0 + z2 / n)
+}
@@ -0,0 +1,207 @@
+//! Automatic screening of review text. Each [`Screener`] looks at a body on its own; a
+//! [`ScreeningChain`] runs them in order and keeps the strictest verdict.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use regex::Regex;
+
+/// Outcome of screening one review body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    /// Publish as usual.
+    Accept,
+    /// Hold for a moderator, whatever the auto-approve setting.
+    Flag(String),
+    /// Refuse the write.
+    Reject(String),
+}
+
+/// One check over a review body. Implementations must be cheap; they run on every write.
+pub trait Screener: Send + Sync {
+    fn screen(&self, body: &str) -> Verdict;
+}
+
+/// Rejects bodies containing any listed word, compared case-insensitively as whole words.
+#[derive(Debug, Clone, Default)]
+pub struct BannedWords {
+    words: HashSet<String>,
+}
+
+impl BannedWords {
+    pub fn new<I, S>(words: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Self {
+            words: words
+                .into_iter()
+                .map(|w| w.as_ref().trim().to_lowercase())
+                .filter(|w| !w.is_empty())
+                .collect(),
+        }
+    }
+}
+
+impl Screener for BannedWords {
+    fn screen(&self, body: &str) -> Verdict {
+        let banned = body
+            .split(|c: char| !c.is_alphanumeric())
+            .any(|w| !w.is_empty() && self.words.contains(&w.to_lowercase()));
+        if banned {
+            Verdict::Reject("contains a banned word".to_string())
+        } else {
+            Verdict::Accept
+        }
+    }
+}
+
+/// Flags links, email addresses and phone numbers, which are usually spam or doxxing.
+#[derive(Debug, Clone)]
+pub struct ContactDetails {
+    url: Regex,
+    email: Regex,
+    phone: Regex,
+}
+
+impl Default for ContactDetails {
+    fn default() -> Self {
+        Self {
+            url: Regex::new(r"(?i)\b(?:https?://|www\.)\S+|\b[a-z0-9-]+\.(?:com|net|org|io|co|info|biz|shop|store|xyz)\b")
+                .expect("valid URL pattern"),
+            email: Regex::new(r"(?i)\b[a-z0-9._%+-]+@[a-z0-9.-]+\.[a-z]{2,}\b").expect("valid email pattern"),
+            // Nine or more digits, optionally split by spaces, dots, dashes or parentheses.
+            phone: Regex::new(r"\+?\d(?:[\s().-]*\d){8,}").expect("valid phone pattern"),
+        }
+    }
+}
+
+impl Screener for ContactDetails {
+    fn screen(&self, body: &str) -> Verdict {
+        if self.url.is_match(body) {
+            Verdict::Flag("contains a link".to_string())
+        } else if self.email.is_match(body) {
+            Verdict::Flag("contains an email address".to_string())
+        } else if self.phone.is_match(body) {
+            Verdict::Flag("contains a phone number".to_string())
+        } else {
+            Verdict::Accept
+        }
+    }
+}
+
+/// Flags bodies written mostly in capitals. Short bodies are exempt so "GREAT!" still passes.
+#[derive(Debug, Clone, Copy)]
+pub struct ExcessiveCaps {
+    /// Highest accepted share of uppercase letters, from 0 to 1.
+    pub max_ratio: f64,
+    /// Bodies with fewer letters than this are not checked.
+    pub min_letters: usize,
+}
+
+impl Default for ExcessiveCaps {
+    fn default() -> Self {
+        Self {
+            max_ratio: 0.7,
+            min_letters: 20,
+        }
+    }
+}
+
+impl Screener for ExcessiveCaps {
+    fn screen(&self, body: &str) -> Verdict {
+        let (letters, upper) = body
+            .chars()
+            .filter(|c| c.is_alphabetic())
+            .fold((0usize, 0usize), |(n, u), c| {
+                (n + 1, u + usize::from(c.is_uppercase()))
+            });
+        if letters >= self.min_letters && upper as f64 / letters as f64 > self.max_ratio {
+            Verdict::Flag("mostly capital letters".to_string())
+        } else {
+            Verdict::Accept
+        }
+    }
+}
+
+/// Flags long runs of one repeated character, such as "!!!!!!!!" or "soooooooo".
+#[derive(Debug, Clone, Copy)]
+pub struct RepeatedChars {
+    /// Longest accepted run of the same non-whitespace character.
+    pub max_run: usize,
+}
+
+impl Default for RepeatedChars {
+    fn default() -> Self {
+        Self { max_run: 5 }
+    }
+}
+
+impl Screener for RepeatedChars {
+    fn screen(&self, body: &str) -> Verdict {
+        let mut run = 0;
+        let mut prev = None;
+        for c in body.chars() {
+            run = if Some(c) == prev { run + 1 } else { 1 };
+            prev = Some(c);
+            if !c.is_whitespace() && run > self.max_run {
+                return Verdict::Flag("repeats a character too many times".to_string());
+            }
+        }
+        Verdict::Accept
+    }
+}
+
+/// Settings for the built-in screeners.
+#[derive(Debug, Clone, Default)]
+pub struct ScreeningConfig {
+    /// Words that get a review rejected outright.
+    pub banned_words: Vec<String>,
+    pub caps: ExcessiveCaps,
+    pub repeated: RepeatedChars,
+}
+
+/// Screeners applied in order. A rejection stops the chain; otherwise the first flag wins.
+#[derive(Clone, Default)]
+pub struct ScreeningChain {
+    screeners: Vec<Arc<dyn Screener>>,
+}
+
+impl ScreeningChain {
+    /// A chain that accepts everything. Add screeners with [`then`](Self::then).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The built-in screeners: banned words, contact details, capitals, repeated characters.
+    pub fn from_config(config: &ScreeningConfig) -> Self {
+        Self::new()
+            .then(BannedWords::new(&config.banned_words))
+            .then(ContactDetails::default())
+            .then(config.caps)
+            .then(config.repeated)
+    }
+
+    /// Append a screener to the end of the chain.
+    pub fn then(mut self, screener: impl Screener + 'static) -> Self {
+        self.screeners.push(Arc::new(screener));
+        self
+    }
+
+    pub fn screen(&self, body: &str) -> Verdict {
+        let mut verdict = Verdict::Accept;
+        for screener in &self.screeners {
+            match screener.screen(body) {
+                Verdict::Accept => {}
+                reject @ Verdict::Reject(_) => return reject,
+                flag @ Verdict::Flag(_) => {
+                    if verdict == Verdict::Accept {
+                        verdict = flag;
+                    }
+                }
+            }
+        }
+        verdict
+    }
+}
@@ -0,0 +1,199 @@
+//! Input validation for review writes. Runs before anything reaches the database, so callers get
+//! every field problem at once instead of the first constraint Postgres trips over.
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::error::{FieldError, ReviewError};
+use crate::models::{CreateReport, CreateReview, NewApiKey, ReplyInput, UpdateReview};
+
+/// Lowest accepted star rating; mirrors the `reviews.rating` CHECK constraint.
+pub const MIN_RATING: i32 = 1;
+/// Highest accepted star rating; mirrors the `reviews.rating` CHECK constraint.
+pub const MAX_RATING: i32 = 5;
+
+/// Longest accepted report comment, in characters after trimming.
+pub const MAX_REPORT_COMMENT_CHARS: usize = 1000;
+
+/// Longest accepted `Idempotency-Key`, in characters.
+pub const MAX_IDEMPOTENCY_KEY_CHARS: usize = 255;
+
+/// Tunable limits for review input.
+#[derive(Debug, Clone)]
+pub struct ValidationLimits {
+    /// Maximum body length in characters, measured after trimming.
+    pub max_body_chars: usize,
+}
+
+impl Default for ValidationLimits {
+    fn default() -> Self {
+        Self {
+            max_body_chars: 5000,
+        }
+    }
+}
+
+/// Validate and normalize a new review. The body is trimmed in the returned value.
+pub fn validate_create(
+    mut input: CreateReview,
+    limits: &ValidationLimits,
+) -> Result<CreateReview, ReviewError> {
+    let mut errors = Vec::new();
+    check_id("product_id", input.product_id, &mut errors);
+    check_id("user_id", input.user_id, &mut errors);
+    check_rating(input.rating, &mut errors);
+    input.body = input.body.map(|b| b.trim().to_string());
+    if let Some(body) = &input.body {
+        check_body(body, limits, &mut errors);
+    }
+    finish(input, errors)
+}
+
+/// Validate and normalize a partial update. Only fields present in the patch are checked.
+pub fn validate_update(
+    mut patch: UpdateReview,
+    limits: &ValidationLimits,
+) -> Result<UpdateReview, ReviewError> {
+    let mut errors = Vec::new();
+    if let Some(rating) = patch.rating {
+        check_rating(rating, &mut errors);
+    }
+    if let Some(Some(body)) = &mut patch.body {
+        *body = body.trim().to_string();
+        check_body(body, limits, &mut errors);
+    }
+    finish(patch, errors)
+}
+
+/// Normalize a report. A blank comment is dropped rather than rejected.
+pub fn validate_report(mut input: CreateReport) -> Result<CreateReport, ReviewError> {
+    let mut errors = Vec::new();
+    input.comment = input
+        .comment
+        .map(|c| c.trim().to_string())
+        .filter(|c| !c.is_empty());
+    if input
+        .comment
+        .as_ref()
+        .is_some_and(|c| c.chars().count() > MAX_REPORT_COMMENT_CHARS)
+    {
+        errors.push(FieldError::new(
+            "comment",
+            "too_long",
+            format!("must be at most {} characters", MAX_REPORT_COMMENT_CHARS),
+        ));
+    }
+    finish(input, errors)
+}
+
+/// Validate and normalize a merchant reply. Replies share the review body limit but must not be blank.
+pub fn validate_reply(
+    mut input: ReplyInput,
+    limits: &ValidationLimits,
+) -> Result<ReplyInput, ReviewError> {
+    let mut errors = Vec::new();
+    input.body = input.body.trim().to_string();
+    if input.body.is_empty() {
+        errors.push(FieldError::new("body", "blank", "must not be blank"));
+    } else if input.body.chars().count() > limits.max_body_chars {
+        errors.push(FieldError::new(
+            "body",
+            "too_long",
+            format!("must be at most {} characters", limits.max_body_chars),
+        ));
+    }
+    finish(input, errors)
+}
+
+/// Validate and normalize an API key request. The name is trimmed and repeated scopes dropped.
+pub fn validate_api_key(mut input: NewApiKey) -> Result<NewApiKey, ReviewError> {
+    let mut errors = Vec::new();
+    input.name = input.name.trim().to_string();
+    if input.name.is_empty() {
+        errors.push(FieldError::new("name", "blank", "must not be blank"));
+    }
+    let mut scopes = Vec::new();
+    for scope in input.scopes {
+        if !scopes.contains(&scope) {
+            scopes.push(scope);
+        }
+    }
+    input.scopes = scopes;
+    if input.scopes.is_empty() {
+        errors.push(FieldError::new(
+            "scopes",
+            "empty",
+            "must grant at least one scope",
+        ));
+    }
+    if input.expires_at.is_some_and(|at| at <= Utc::now()) {
+        errors.push(FieldError::new(
+            "expires_at",
+            "in_past",
+            "must be in the future",
+        ));
+    }
+    finish(input, errors)
+}
+
+/// Check an `Idempotency-Key`: 1 to [`MAX_IDEMPOTENCY_KEY_CHARS`] visible ASCII characters.
+pub fn validate_idempotency_key(key: &str) -> Result<&str, ReviewError> {
+    if (1..=MAX_IDEMPOTENCY_KEY_CHARS).contains(&key.len())
+        && key.bytes().all(|b| b.is_ascii_graphic())
+    {
+        return Ok(key);
+    }
+    Err(ReviewError::invalid_fields(vec![FieldError::new(
+        "Idempotency-Key",
+        "invalid",
+        format!(
+            "must be 1 to {} visible ASCII characters",
+            MAX_IDEMPOTENCY_KEY_CHARS
+        ),
+    )]))
+}
+
+fn check_id(field: &str, id: Uuid, errors: &mut Vec<FieldError>) {
+    if id.is_nil() {
+        errors.push(FieldError::new(
+            field,
+            "nil_uuid",
+            "must not be the nil UUID",
+        ));
+    }
+}
+
+fn check_rating(rating: i32, errors: &mut Vec<FieldError>) {
+    if !(MIN_RATING..=MAX_RATING).contains(&rating) {
+        errors.push(FieldError::new(
+            "rating",
+            "out_of_range",
+            format!("must be between {} and {}", MIN_RATING, MAX_RATING),
+        ));
+    }
+}
+
+/// `body` is already trimmed. Omit the field (or send `null`) for a rating-only review.
+fn check_body(body: &str, limits: &ValidationLimits, errors: &mut Vec<FieldError>) {
+    if body.is_empty() {
+        errors.push(FieldError::new(
+            "body",
+            "blank",
+            "must not be blank; omit it for a rating-only review",
+        ));
+    } else if body.chars().count() > limits.max_body_chars {
+        errors.push(FieldError::new(
+            "body",
+            "too_long",
+            format!("must be at most {} characters", limits.max_body_chars),
+        ));
+    }
+}
+
+fn finish<T>(value: T, errors: Vec<FieldError>) -> Result<T, ReviewError> {
+    if errors.is_empty() {
+        Ok(value)
+    } else {
+        Err(ReviewError::invalid_fields(errors))
+    }
+}
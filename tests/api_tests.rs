@@ -10,29 +10,168 @@ fn load_env() {
 }
 
 use axum::body::Body;
-use axum::http::{Request, StatusCode};
+use axum::http::{header, Request, StatusCode};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use hmac::{Hmac, Mac};
 use http_body_util::BodyExt;
-use my_ex_review_service::app;
+use my_ex_review_service::app_with_config;
+use my_ex_review_service::auth::AuthConfig;
+use my_ex_review_service::config::Config;
+use my_ex_review_service::models::{ApiKey, ApiScope, NewApiKey};
+use my_ex_review_service::service::ReviewService;
 use serde_json::{json, Value};
+use sha2::Sha256;
 use sqlx::PgPool;
 use tower::ServiceExt;
 use uuid::Uuid;
 
+const JWT_SECRET: &[u8] = b"api-tests-secret";
+
+/// Helper: default configuration, accepting HS256 tokens signed with `JWT_SECRET`.
+fn config() -> Config {
+    Config {
+        auth: AuthConfig::default().with_hs256_secret(JWT_SECRET),
+        ..Config::default()
+    }
+}
+
+/// Helper: the app with reviews held for moderation.
+fn app(pool: PgPool) -> axum::Router<()> {
+    app_with_config(pool, config())
+}
+
+/// Helper: the app with moderation skipped, so new reviews are visible straight away.
+fn auto_approving_app(pool: PgPool) -> axum::Router<()> {
+    app_with_config(
+        pool,
+        Config {
+            auto_approve: true,
+            ..config()
+        },
+    )
+}
+
+/// Helper: an HS256 bearer token for `user` with `roles`, valid for five minutes.
+fn token_for(user: Uuid, roles: &[&str]) -> String {
+    let encode = |v: Value| URL_SAFE_NO_PAD.encode(serde_json::to_vec(&v).unwrap());
+    let claims =
+        json!({ "sub": user, "exp": chrono::Utc::now().timestamp() + 300, "roles": roles });
+    let signed = format!(
+        "{}.{}",
+        encode(json!({ "alg": "HS256", "typ": "JWT" })),
+        encode(claims)
+    );
+    let mut mac = Hmac::<Sha256>::new_from_slice(JWT_SECRET).unwrap();
+    mac.update(signed.as_bytes());
+    format!(
+        "{}.{}",
+        signed,
+        URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes())
+    )
+}
+
 /// Helper: send request to app and return (status, body as JSON).
 async fn request(
     app: axum::Router<()>,
     method: &str,
     uri: &str,
     body: Option<Value>,
+) -> (StatusCode, Value) {
+    request_as(app, None, method, uri, body).await
+}
+
+/// Helper: like `request`, acting as `user` via a bearer token when given.
+async fn request_as(
+    app: axum::Router<()>,
+    user: Option<Uuid>,
+    method: &str,
+    uri: &str,
+    body: Option<Value>,
+) -> (StatusCode, Value) {
+    send(
+        app,
+        user.map(|u| bearer(&token_for(u, &[]))),
+        method,
+        uri,
+        body,
+    )
+    .await
+}
+
+/// Helper: like `request_as`, for a user holding `role`.
+async fn request_as_role(
+    app: axum::Router<()>,
+    user: Uuid,
+    role: &str,
+    method: &str,
+    uri: &str,
+    body: Option<Value>,
+) -> (StatusCode, Value) {
+    send(
+        app,
+        Some(bearer(&token_for(user, &[role]))),
+        method,
+        uri,
+        body,
+    )
+    .await
+}
+
+/// Helper: like `request`, as an admin.
+async fn as_admin(
+    app: axum::Router<()>,
+    method: &str,
+    uri: &str,
+    body: Option<Value>,
+) -> (StatusCode, Value) {
+    request_as_role(app, Uuid::new_v4(), "admin", method, uri, body).await
+}
+
+/// Helper: like `request`, as a backend service presenting API key `key`.
+async fn request_with_key(
+    app: axum::Router<()>,
+    key: &str,
+    method: &str,
+    uri: &str,
+    body: Option<Value>,
+) -> (StatusCode, Value) {
+    send(app, Some(("x-api-key", key.to_string())), method, uri, body).await
+}
+
+/// Helper: issue an API key with `scopes` straight through the service.
+async fn issue_key(pool: &PgPool, scopes: &[ApiScope]) -> (ApiKey, String) {
+    let input = NewApiKey {
+        name: "order-service".to_string(),
+        scopes: scopes.to_vec(),
+        expires_at: None,
+    };
+    ReviewService::new(pool.clone())
+        .create_api_key(input)
+        .await
+        .unwrap()
+}
+
+fn bearer(token: &str) -> (&'static str, String) {
+    ("authorization", format!("Bearer {}", token))
+}
+
+async fn send(
+    app: axum::Router<()>,
+    credentials: Option<(&str, String)>,
+    method: &str,
+    uri: &str,
+    body: Option<Value>,
 ) -> (StatusCode, Value) {
     let body = body.map(|v| Body::from(serde_json::to_vec(&v).unwrap()));
     let body = body.unwrap_or_else(Body::empty);
-    let req = Request::builder()
+    let mut req = Request::builder()
         .method(method)
         .uri(uri)
-        .header("content-type", "application/json")
-        .body(body)
-        .unwrap();
+        .header("content-type", "application/json");
+    if let Some((name, value)) = credentials {
+        req = req.header(name, value);
+    }
+    let req = req.body(body).unwrap();
     let response = app.oneshot(req).await.unwrap();
     let status = response.status();
     let body = response.into_body().collect().await.unwrap().to_bytes();
@@ -46,7 +185,7 @@ async fn request(
 
 #[sqlx::test]
 async fn health_returns_ok(pool: PgPool) {
-    let app = app(pool);
+    let app = auto_approving_app(pool);
     let (status, body) = request(app, "GET", "/health", None).await;
     assert_eq!(status, StatusCode::OK);
     assert_eq!(body["status"], "ok");
@@ -55,33 +194,136 @@ async fn health_returns_ok(pool: PgPool) {
 
 #[sqlx::test]
 async fn list_reviews_empty(pool: PgPool) {
-    let app = app(pool);
+    let app = auto_approving_app(pool);
     let (status, body) = request(app, "GET", "/reviews", None).await;
     assert_eq!(status, StatusCode::OK);
-    assert!(body.is_array());
-    assert_eq!(body.as_array().unwrap().len(), 0);
+    assert!(body["items"].is_array());
+    assert_eq!(body["items"].as_array().unwrap().len(), 0);
+    assert!(body["next_cursor"].is_null());
 }
 
 #[sqlx::test]
 async fn get_review_not_found(pool: PgPool) {
-    let app = app(pool);
+    let app = auto_approving_app(pool);
     let id = Uuid::new_v4();
-    let (status, _) = request(app, "GET", &format!("/reviews/{}", id), None).await;
+    let (status, body) = request(app, "GET", &format!("/reviews/{}", id), None).await;
     assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["status"], 404);
+    assert_eq!(body["type"], "about:blank");
+    assert_eq!(body["title"], "Not Found");
+}
+
+#[sqlx::test]
+async fn errors_use_problem_json(pool: PgPool) {
+    let app = auto_approving_app(pool);
+    let req = Request::builder()
+        .uri(format!("/reviews/{}", Uuid::new_v4()))
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(req).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(
+        response.headers()["content-type"],
+        "application/problem+json"
+    );
+}
+
+#[sqlx::test]
+async fn create_review_out_of_range_rating_is_unprocessable(pool: PgPool) {
+    let app = auto_approving_app(pool);
+    let body = json!({
+        "product_id": Uuid::new_v4().to_string(),
+        "rating": 0,
+        "body": null
+    });
+    let (status, problem) =
+        request_as(app, Some(Uuid::new_v4()), "POST", "/reviews", Some(body)).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(problem["status"], 422);
+    assert_eq!(problem["errors"][0]["field"], "rating");
+    assert_eq!(problem["errors"][0]["code"], "out_of_range");
+}
+
+#[sqlx::test]
+async fn create_review_takes_author_from_bearer_token(pool: PgPool) {
+    let app = auto_approving_app(pool);
+    let (author, impostor) = (Uuid::new_v4(), Uuid::new_v4());
+    let body = json!({ "product_id": Uuid::new_v4(), "user_id": impostor, "rating": 5 });
+    let post = |authorization: Option<String>, user_header: Option<Uuid>| {
+        let mut req = Request::builder()
+            .method("POST")
+            .uri("/reviews")
+            .header("content-type", "application/json");
+        if let Some(value) = authorization {
+            req = req.header(header::AUTHORIZATION, value);
+        }
+        if let Some(user) = user_header {
+            req = req.header("x-user-id", user.to_string());
+        }
+        app.clone()
+            .oneshot(req.body(Body::from(body.to_string())).unwrap())
+    };
+
+    let response = post(None, Some(author)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let challenges: Vec<_> = response
+        .headers()
+        .get_all(header::WWW_AUTHENTICATE)
+        .iter()
+        .collect();
+    assert_eq!(challenges, ["Bearer", "ApiKey header=\"X-API-Key\""]);
+    let forged = format!("Bearer {}x", token_for(author, &[]));
+    let response = post(Some(forged), None).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(
+        response.headers()["content-type"],
+        "application/problem+json"
+    );
+
+    let response = post(
+        Some(format!("Bearer {}", token_for(author, &[]))),
+        Some(impostor),
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let created: Value =
+        serde_json::from_slice(&response.into_body().collect().await.unwrap().to_bytes()).unwrap();
+    assert_eq!(created["user_id"], author.to_string());
+}
+
+#[sqlx::test]
+async fn update_review_validates_patch(pool: PgPool) {
+    let app = auto_approving_app(pool);
+    let author = Uuid::new_v4();
+    let id = create_review_for(&app, author, 3, Some("fine")).await;
+    let uri = format!("/reviews/{}", id);
+
+    let (status, problem) = request_as(
+        app,
+        Some(author),
+        "PATCH",
+        &uri,
+        Some(json!({ "body": "" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(problem["errors"][0]["field"], "body");
+    assert_eq!(problem["errors"][0]["code"], "blank");
 }
 
 #[sqlx::test]
 async fn create_review_and_fetch(pool: PgPool) {
-    let app = app(pool);
+    let app = auto_approving_app(pool);
     let product_id = Uuid::new_v4();
     let user_id = Uuid::new_v4();
     let body = json!({
         "product_id": product_id.to_string(),
-        "user_id": user_id.to_string(),
         "rating": 4,
         "body": "Great product!"
     });
-    let (status, created) = request(app.clone(), "POST", "/reviews", Some(body)).await;
+    let (status, created) =
+        request_as(app.clone(), Some(user_id), "POST", "/reviews", Some(body)).await;
     assert_eq!(status, StatusCode::CREATED);
     assert_eq!(created["product_id"], product_id.to_string());
     assert_eq!(created["user_id"], user_id.to_string());
@@ -97,29 +339,724 @@ async fn create_review_and_fetch(pool: PgPool) {
 
 #[sqlx::test]
 async fn list_reviews_after_create(pool: PgPool) {
-    let app = app(pool);
+    let app = auto_approving_app(pool);
     let product_id = Uuid::new_v4();
     let user_id = Uuid::new_v4();
     let body = json!({
         "product_id": product_id.to_string(),
-        "user_id": user_id.to_string(),
         "rating": 5,
         "body": null
     });
-    let (status, _) = request(app.clone(), "POST", "/reviews", Some(body)).await;
+    let (status, _) = request_as(app.clone(), Some(user_id), "POST", "/reviews", Some(body)).await;
     assert_eq!(status, StatusCode::CREATED);
 
     let (status, list) = request(app, "GET", "/reviews", None).await;
     assert_eq!(status, StatusCode::OK);
-    let arr = list.as_array().unwrap();
+    let arr = list["items"].as_array().unwrap();
     assert_eq!(arr.len(), 1);
     assert_eq!(arr[0]["rating"], 5);
 }
 
 #[sqlx::test]
-async fn dashboard_stats_empty(pool: PgPool) {
+async fn list_reviews_follows_next_cursor(pool: PgPool) {
+    let app = auto_approving_app(pool);
+    for rating in 1..=5 {
+        let body = json!({
+            "product_id": Uuid::new_v4().to_string(),
+            "rating": rating,
+            "body": null
+        });
+        let (status, _) = request_as(
+            app.clone(),
+            Some(Uuid::new_v4()),
+            "POST",
+            "/reviews",
+            Some(body),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+    }
+
+    let mut seen = Vec::new();
+    let mut uri = "/reviews?limit=2".to_string();
+    loop {
+        let (status, page) = request(app.clone(), "GET", &uri, None).await;
+        assert_eq!(status, StatusCode::OK);
+        let items = page["items"].as_array().unwrap();
+        assert!(items.len() <= 2);
+        seen.extend(items.iter().map(|r| r["id"].as_str().unwrap().to_string()));
+        match page["next_cursor"].as_str() {
+            Some(cursor) => uri = format!("/reviews?limit=2&cursor={}", cursor),
+            None => break,
+        }
+    }
+    assert_eq!(seen.len(), 5);
+    seen.sort();
+    seen.dedup();
+    assert_eq!(seen.len(), 5);
+}
+
+#[sqlx::test]
+async fn list_reviews_filters_and_sorts(pool: PgPool) {
+    let app = auto_approving_app(pool);
+    let product_id = Uuid::new_v4();
+    for (product, rating) in [
+        (product_id, 2),
+        (product_id, 5),
+        (product_id, 4),
+        (Uuid::new_v4(), 5),
+    ] {
+        let body = json!({
+            "product_id": product.to_string(),
+            "rating": rating,
+            "body": null
+        });
+        let (status, _) = request_as(
+            app.clone(),
+            Some(Uuid::new_v4()),
+            "POST",
+            "/reviews",
+            Some(body),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+    }
+
+    let uri = format!(
+        "/reviews?product_id={}&min_rating=3&sort=highest",
+        product_id
+    );
+    let (status, page) = request(app.clone(), "GET", &uri, None).await;
+    assert_eq!(status, StatusCode::OK);
+    let ratings: Vec<i64> = page["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| r["rating"].as_i64().unwrap())
+        .collect();
+    assert_eq!(ratings, vec![5, 4]);
+
+    let (status, _) = request(app, "GET", "/reviews?sort=sideways", None).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[sqlx::test]
+async fn list_reviews_rejects_bad_cursor(pool: PgPool) {
+    let app = auto_approving_app(pool);
+    let (status, _) = request(app, "GET", "/reviews?cursor=not-a-cursor", None).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+/// Helper: create a review by `user_id` and return its id.
+async fn create_review_for(
+    app: &axum::Router<()>,
+    user_id: Uuid,
+    rating: i32,
+    text: Option<&str>,
+) -> String {
+    let body = json!({
+        "product_id": Uuid::new_v4().to_string(),
+        "rating": rating,
+        "body": text
+    });
+    let (status, created) =
+        request_as(app.clone(), Some(user_id), "POST", "/reviews", Some(body)).await;
+    assert_eq!(status, StatusCode::CREATED);
+    created["id"].as_str().unwrap().to_string()
+}
+
+#[sqlx::test]
+async fn create_duplicate_review_conflicts(pool: PgPool) {
+    let app = auto_approving_app(pool);
+    let user = Uuid::new_v4();
+    let body = json!({
+        "product_id": Uuid::new_v4().to_string(),
+        "rating": 4,
+        "body": null
+    });
+    let (status, _) = request_as(
+        app.clone(),
+        Some(user),
+        "POST",
+        "/reviews",
+        Some(body.clone()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, problem) = request_as(app, Some(user), "POST", "/reviews", Some(body)).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(problem["status"], 409);
+}
+
+#[sqlx::test]
+async fn upsert_my_review_creates_then_replaces(pool: PgPool) {
+    let app = auto_approving_app(pool);
+    let user = Uuid::new_v4();
+    let uri = format!("/products/{}/reviews/mine", Uuid::new_v4());
+
+    let (status, created) = request_as(
+        app.clone(),
+        Some(user),
+        "PUT",
+        &uri,
+        Some(json!({ "rating": 2, "body": "meh" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(created["user_id"], user.to_string());
+
+    let (status, replaced) = request_as(
+        app.clone(),
+        Some(user),
+        "PUT",
+        &uri,
+        Some(json!({ "rating": 5, "body": null })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(replaced["id"], created["id"]);
+    assert_eq!(replaced["rating"], 5);
+    assert!(replaced["body"].is_null());
+
+    let (_, page) = request(app, "GET", "/reviews", None).await;
+    assert_eq!(page["items"].as_array().unwrap().len(), 1);
+}
+
+#[sqlx::test]
+async fn update_review_by_author(pool: PgPool) {
+    let app = auto_approving_app(pool);
+    let author = Uuid::new_v4();
+    let id = create_review_for(&app, author, 3, Some("typo")).await;
+
+    let uri = format!("/reviews/{}", id);
+    let (status, updated) = request_as(
+        app.clone(),
+        Some(author),
+        "PATCH",
+        &uri,
+        Some(json!({ "body": "fixed" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(updated["body"], "fixed");
+    assert_eq!(updated["rating"], 3);
+
+    let (status, updated) = request_as(
+        app,
+        Some(author),
+        "PATCH",
+        &uri,
+        Some(json!({ "rating": 4, "body": null })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(updated["rating"], 4);
+    assert!(updated["body"].is_null());
+}
+
+#[sqlx::test]
+async fn update_review_rejects_other_users(pool: PgPool) {
+    let app = auto_approving_app(pool);
+    let id = create_review_for(&app, Uuid::new_v4(), 3, None).await;
+    let uri = format!("/reviews/{}", id);
+
+    let (status, _) = request_as(
+        app.clone(),
+        Some(Uuid::new_v4()),
+        "PATCH",
+        &uri,
+        Some(json!({ "rating": 1 })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = request(app.clone(), "PATCH", &uri, Some(json!({ "rating": 1 }))).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (_, got) = request(app, "GET", &uri, None).await;
+    assert_eq!(got["rating"], 3);
+}
+
+#[sqlx::test]
+async fn update_review_not_found(pool: PgPool) {
+    let app = auto_approving_app(pool);
+    let uri = format!("/reviews/{}", Uuid::new_v4());
+    let (status, _) = request_as(
+        app,
+        Some(Uuid::new_v4()),
+        "PATCH",
+        &uri,
+        Some(json!({ "rating": 2 })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test]
+async fn edits_are_kept_as_revisions(pool: PgPool) {
+    let app = auto_approving_app(pool);
+    let author = Uuid::new_v4();
+    let id = create_review_for(&app, author, 3, Some("fine")).await;
+    let uri = format!("/reviews/{}", id);
+    let (_, review) = request(app.clone(), "GET", &uri, None).await;
+    assert_eq!(review["revision"], 1);
+    assert!(review["edited_at"].is_null());
+
+    let (status, edited) = request_as(
+        app.clone(),
+        Some(author),
+        "PATCH",
+        &uri,
+        Some(json!({ "body": "better than fine" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(edited["revision"], 2);
+    assert!(edited["edited_at"].is_string());
+
+    let (status, history) = request(app.clone(), "GET", &format!("{}/revisions", uri), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(history.as_array().unwrap().len(), 1);
+    assert_eq!(history[0]["revision"], 1);
+    assert_eq!(history[0]["body"], "fine");
+    let (status, _) = request(
+        app,
+        "GET",
+        &format!("/reviews/{}/revisions", Uuid::new_v4()),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test]
+async fn delete_review_by_author(pool: PgPool) {
+    let app = auto_approving_app(pool);
+    let author = Uuid::new_v4();
+    let id = create_review_for(&app, author, 5, None).await;
+    let uri = format!("/reviews/{}", id);
+
+    let (status, _) = request_as(app.clone(), Some(Uuid::new_v4()), "DELETE", &uri, None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _) = request_as(app.clone(), Some(author), "DELETE", &uri, None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (status, _) = request(app.clone(), "GET", &uri, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = request_as(app, Some(author), "DELETE", &uri, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test]
+async fn deleted_review_can_be_restored(pool: PgPool) {
+    let app = auto_approving_app(pool);
+    let author = Uuid::new_v4();
+    let id = create_review_for(&app, author, 3, Some("Fine")).await;
+    let (status, _) = request_as(
+        app.clone(),
+        Some(author),
+        "DELETE",
+        &format!("/reviews/{}", id),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (_, stats) = as_admin(app.clone(), "GET", "/stats/dashboard", None).await;
+    assert_eq!(stats["total_reviews"], 0);
+
+    let restore = format!("/reviews/{}/restore", id);
+    let (status, restored) = as_admin(app.clone(), "POST", &restore, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(restored["id"], id.as_str());
+    assert_eq!(restored["body"], "Fine");
+    let (status, _) = request(app.clone(), "GET", &format!("/reviews/{}", id), None).await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, stats) = as_admin(app.clone(), "GET", "/stats/dashboard", None).await;
+    assert_eq!(stats["total_reviews"], 1);
+
+    let (status, _) = as_admin(app.clone(), "POST", &restore, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    // Deleted just now, so well within the retention period.
+    request_as(
+        app.clone(),
+        Some(author),
+        "DELETE",
+        &format!("/reviews/{}", id),
+        None,
+    )
+    .await;
+    let (status, report) = as_admin(app, "POST", "/admin/reviews/purge", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(report["purged"], 0);
+}
+
+#[sqlx::test]
+async fn staff_routes_require_a_role(pool: PgPool) {
+    let app = auto_approving_app(pool);
+    let id = create_review_for(&app, Uuid::new_v4(), 4, None).await;
+    let restore = format!("/reviews/{}/restore", id);
+    let routes = [
+        ("GET", "/moderation/queue"),
+        ("GET", "/stats/dashboard"),
+        ("POST", "/admin/aggregates/reconcile?dry_run=true"),
+        ("POST", "/admin/reviews/purge"),
+        ("POST", restore.as_str()),
+    ];
+    let user = Uuid::new_v4();
+    for (method, uri) in routes {
+        let (status, problem) = request(app.clone(), method, uri, None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED, "{} {}", method, uri);
+        assert_eq!(problem["status"], 401);
+        let (status, problem) = request_as(app.clone(), Some(user), method, uri, None).await;
+        assert_eq!(status, StatusCode::FORBIDDEN, "{} {}", method, uri);
+        assert_eq!(problem["status"], 403);
+        let (status, _) = request_as_role(app.clone(), user, "shopper", method, uri, None).await;
+        assert_eq!(status, StatusCode::FORBIDDEN, "{} {}", method, uri);
+    }
+
+    let (status, _) = request_as_role(
+        app.clone(),
+        user,
+        "merchant",
+        "GET",
+        "/stats/dashboard",
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = request_as_role(
+        app.clone(),
+        user,
+        "merchant",
+        "GET",
+        "/moderation/queue",
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = request_as_role(
+        app.clone(),
+        user,
+        "moderator",
+        "GET",
+        "/moderation/queue",
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, problem) = request_as_role(
+        app.clone(),
+        user,
+        "moderator",
+        "POST",
+        "/admin/reviews/purge",
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(problem["detail"], "Requires one of the roles: admin");
+    let (status, _) = as_admin(app, "GET", "/moderation/queue", None).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[sqlx::test]
+async fn api_keys_are_limited_to_their_scopes(pool: PgPool) {
+    let app = auto_approving_app(pool.clone());
+    let (reader_key, reader) = issue_key(&pool, &[ApiScope::Read]).await;
+    let (_, writer) = issue_key(&pool, &[ApiScope::Write]).await;
+    let (_, analyst) = issue_key(&pool, &[ApiScope::Stats]).await;
+    let user = Uuid::new_v4();
+    let product_id = Uuid::new_v4();
+
+    let review = json!({ "user_id": user, "product_id": product_id, "rating": 5, "body": "Arrived next day" });
+    let (status, created) = request_with_key(
+        app.clone(),
+        &writer,
+        "POST",
+        "/reviews",
+        Some(review.clone()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(created["user_id"], user.to_string());
+    let anonymous = json!({ "product_id": Uuid::new_v4(), "rating": 4 });
+    let (status, problem) =
+        request_with_key(app.clone(), &writer, "POST", "/reviews", Some(anonymous)).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(problem["errors"][0]["field"], "user_id");
+    let (status, problem) =
+        request_with_key(app.clone(), &reader, "POST", "/reviews", Some(review)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(problem["detail"], "API key lacks the `write` scope");
+
+    let uri = format!("/reviews/{}", created["id"].as_str().unwrap());
+    let (status, _) = request_with_key(app.clone(), &reader, "GET", &uri, None).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = request_with_key(app.clone(), &writer, "GET", "/reviews", None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) =
+        request_with_key(app.clone(), &analyst, "GET", "/stats/dashboard", None).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = request_with_key(app.clone(), &reader, "GET", "/stats/dashboard", None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    // Keys never stand in for staff roles, nor act as a user outside `POST /reviews`.
+    let (status, _) =
+        request_with_key(app.clone(), &analyst, "GET", "/moderation/queue", None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let id = created["id"].as_str().unwrap();
+    let writes = [
+        (
+            "PUT",
+            format!("/products/{}/reviews/mine", product_id),
+            Some(json!({ "rating": 1 })),
+        ),
+        (
+            "PATCH",
+            format!("/reviews/{}", id),
+            Some(json!({ "rating": 1 })),
+        ),
+        ("DELETE", format!("/reviews/{}", id), None),
+        (
+            "POST",
+            format!("/reviews/{}/votes", id),
+            Some(json!({ "vote": "helpful" })),
+        ),
+        ("DELETE", format!("/reviews/{}/votes/mine", id), None),
+        (
+            "POST",
+            format!("/reviews/{}/reports", id),
+            Some(json!({ "reason": "spam" })),
+        ),
+        (
+            "POST",
+            format!("/reviews/{}/reply", id),
+            Some(json!({ "body": "Thanks" })),
+        ),
+        (
+            "PATCH",
+            format!("/reviews/{}/reply", id),
+            Some(json!({ "body": "Thanks!" })),
+        ),
+        ("DELETE", format!("/reviews/{}/reply", id), None),
+    ];
+    for (method, uri, body) in writes {
+        for key in [&reader, &writer] {
+            let (status, problem) =
+                request_with_key(app.clone(), key, method, &uri, body.clone()).await;
+            assert_eq!(status, StatusCode::FORBIDDEN, "{} {}", method, uri);
+            assert_eq!(problem["detail"], "API keys cannot use this route");
+        }
+    }
+
+    let (status, problem) =
+        request_with_key(app.clone(), "rvk_not-a-key", "GET", "/reviews", None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(problem["detail"], "Unknown, revoked or expired API key");
+    let req = Request::builder()
+        .uri("/reviews")
+        .header("x-api-key", "rvk_not-a-key")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(req).await.unwrap();
+    assert_eq!(
+        response.headers()[header::WWW_AUTHENTICATE],
+        "ApiKey header=\"X-API-Key\""
+    );
+    ReviewService::new(pool)
+        .revoke_api_key(reader_key.id)
+        .await
+        .unwrap();
+    let (status, _) = request_with_key(app, &reader, "GET", &uri, None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[sqlx::test]
+async fn x_user_id_header_does_not_identify_the_caller(pool: PgPool) {
     let app = app(pool);
-    let (status, body) = request(app, "GET", "/stats/dashboard", None).await;
+    let author = Uuid::new_v4();
+    let product_id = Uuid::new_v4();
+    let review = json!({ "product_id": product_id, "rating": 4, "body": "Still pending" });
+    let (status, created) =
+        request_as(app.clone(), Some(author), "POST", "/reviews", Some(review)).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let id = created["id"].as_str().unwrap();
+
+    let review_uri = format!("/reviews/{}", id);
+    let votes_uri = format!("/reviews/{}/votes", id);
+    let reply_uri = format!("/reviews/{}/reply", id);
+    let writes = [
+        (
+            "PUT",
+            format!("/products/{}/reviews/mine", product_id),
+            json!({ "rating": 1 }),
+        ),
+        ("PATCH", review_uri.clone(), json!({ "rating": 1 })),
+        ("DELETE", review_uri.clone(), json!(null)),
+        ("POST", votes_uri.clone(), json!({ "vote": "helpful" })),
+        ("DELETE", format!("{}/mine", votes_uri), json!(null)),
+        (
+            "POST",
+            format!("/reviews/{}/reports", id),
+            json!({ "reason": "spam" }),
+        ),
+        ("POST", reply_uri.clone(), json!({ "body": "Thanks" })),
+        ("PATCH", reply_uri.clone(), json!({ "body": "Thanks!" })),
+        ("DELETE", reply_uri, json!(null)),
+    ];
+    for (method, uri, body) in writes {
+        let req = Request::builder()
+            .method(method)
+            .uri(&uri)
+            .header("x-user-id", author.to_string())
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let response = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(
+            response.status(),
+            StatusCode::UNAUTHORIZED,
+            "{} {}",
+            method,
+            uri
+        );
+        assert_eq!(
+            response.headers()[header::WWW_AUTHENTICATE],
+            "Bearer",
+            "{} {}",
+            method,
+            uri
+        );
+    }
+
+    // The header does not reveal the author's unapproved review either.
+    for uri in [review_uri.clone(), format!("{}/revisions", review_uri)] {
+        let (status, _) = send(
+            app.clone(),
+            Some(("x-user-id", author.to_string())),
+            "GET",
+            &uri,
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND, "{}", uri);
+    }
+    let (status, fetched) = request_as(app, Some(author), "GET", &review_uri, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(fetched["rating"], 4);
+}
+
+#[sqlx::test]
+async fn idempotency_key_replays_the_first_response(pool: PgPool) {
+    let app = auto_approving_app(pool.clone());
+    let post = |user: Uuid, key: &str, body: &Value| {
+        Request::builder()
+            .method("POST")
+            .uri("/reviews")
+            .header(
+                header::AUTHORIZATION,
+                format!("Bearer {}", token_for(user, &[])),
+            )
+            .header(header::CONTENT_TYPE, "application/json")
+            .header("idempotency-key", key)
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+    let user = Uuid::new_v4();
+    let review =
+        json!({ "product_id": Uuid::new_v4(), "rating": 4, "body": "Sent twice on a train" });
+
+    let first = app
+        .clone()
+        .oneshot(post(user, "retry-1", &review))
+        .await
+        .unwrap();
+    assert_eq!(first.status(), StatusCode::CREATED);
+    assert!(first.headers().get("idempotent-replayed").is_none());
+    let first: Value =
+        serde_json::from_slice(&first.into_body().collect().await.unwrap().to_bytes()).unwrap();
+    let replay = app
+        .clone()
+        .oneshot(post(user, "retry-1", &review))
+        .await
+        .unwrap();
+    assert_eq!(replay.status(), StatusCode::CREATED);
+    assert_eq!(replay.headers()["idempotent-replayed"], "true");
+    let replay: Value =
+        serde_json::from_slice(&replay.into_body().collect().await.unwrap().to_bytes()).unwrap();
+    assert_eq!(replay, first);
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM reviews")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(count, 1);
+
+    let changed = json!({ "product_id": Uuid::new_v4(), "rating": 2 });
+    let reused = app
+        .clone()
+        .oneshot(post(user, "retry-1", &changed))
+        .await
+        .unwrap();
+    assert_eq!(reused.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let problem: Value =
+        serde_json::from_slice(&reused.into_body().collect().await.unwrap().to_bytes()).unwrap();
+    assert_eq!(problem["errors"][0]["field"], "Idempotency-Key");
+    assert_eq!(problem["errors"][0]["code"], "reused");
+
+    // Keys belong to their caller, and a failed request keeps nothing.
+    let other = app
+        .clone()
+        .oneshot(post(Uuid::new_v4(), "retry-1", &review))
+        .await
+        .unwrap();
+    assert_eq!(other.status(), StatusCode::CREATED);
+    let invalid = json!({ "product_id": Uuid::new_v4(), "rating": 9 });
+    let failed = app
+        .clone()
+        .oneshot(post(user, "retry-2", &invalid))
+        .await
+        .unwrap();
+    assert_eq!(failed.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let fixed = json!({ "product_id": Uuid::new_v4(), "rating": 3 });
+    assert_eq!(
+        app.clone()
+            .oneshot(post(user, "retry-2", &fixed))
+            .await
+            .unwrap()
+            .status(),
+        StatusCode::CREATED
+    );
+    let blank = app.oneshot(post(user, "", &fixed)).await.unwrap();
+    assert_eq!(blank.status(), StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[sqlx::test]
+async fn openapi_declares_bearer_security(pool: PgPool) {
+    let app = auto_approving_app(pool);
+    let (status, doc) = request(app, "GET", "/api-docs/openapi.json", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        doc["components"]["securitySchemes"]["bearer_auth"]["scheme"],
+        "bearer"
+    );
+    assert_eq!(
+        doc["components"]["securitySchemes"]["api_key"]["name"],
+        "X-API-Key"
+    );
+    assert_eq!(
+        doc["paths"]["/moderation/queue"]["get"]["security"],
+        json!([{ "bearer_auth": [] }])
+    );
+    assert_eq!(
+        doc["paths"]["/stats/dashboard"]["get"]["security"],
+        json!([{ "bearer_auth": [] }, { "api_key": [] }])
+    );
+    assert!(doc["paths"]["/health"]["get"].get("security").is_none());
+}
+
+#[sqlx::test]
+async fn dashboard_stats_empty(pool: PgPool) {
+    let app = auto_approving_app(pool);
+    let (status, body) = as_admin(app, "GET", "/stats/dashboard", None).await;
     assert_eq!(status, StatusCode::OK);
     assert_eq!(body["total_reviews"], 0);
     assert_eq!(body["avg_rating"], 0.0);
@@ -127,20 +1064,752 @@ async fn dashboard_stats_empty(pool: PgPool) {
 
 #[sqlx::test]
 async fn dashboard_stats_after_reviews(pool: PgPool) {
-    let app = app(pool);
+    let app = auto_approving_app(pool);
     for (rating, _) in [(3, "a"), (5, "b"), (4, "c")] {
         let body = json!({
             "product_id": Uuid::new_v4().to_string(),
-            "user_id": Uuid::new_v4().to_string(),
             "rating": rating,
             "body": null
         });
-        let (status, _) = request(app.clone(), "POST", "/reviews", Some(body)).await;
+        let (status, _) = request_as(
+            app.clone(),
+            Some(Uuid::new_v4()),
+            "POST",
+            "/reviews",
+            Some(body),
+        )
+        .await;
         assert_eq!(status, StatusCode::CREATED);
     }
-    let (status, body) = request(app, "GET", "/stats/dashboard", None).await;
+    let (status, body) = as_admin(app, "GET", "/stats/dashboard", None).await;
     assert_eq!(status, StatusCode::OK);
     assert_eq!(body["total_reviews"], 3);
     let avg = body["avg_rating"].as_f64().unwrap();
     assert!((avg - 4.0).abs() < 0.01);
 }
+
+#[sqlx::test]
+async fn product_stats_returns_histogram(pool: PgPool) {
+    let app = auto_approving_app(pool);
+    let product_id = Uuid::new_v4();
+    for rating in [4, 4, 2] {
+        let body = json!({
+            "product_id": product_id.to_string(),
+            "rating": rating,
+            "body": null
+        });
+        let (status, _) = request_as(
+            app.clone(),
+            Some(Uuid::new_v4()),
+            "POST",
+            "/reviews",
+            Some(body),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+    }
+
+    let (status, stats) =
+        request(app, "GET", &format!("/products/{}/stats", product_id), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(stats["review_count"], 3);
+    assert_eq!(
+        stats["histogram"],
+        json!({ "1": 0, "2": 1, "3": 0, "4": 2, "5": 0 })
+    );
+    assert!(stats["latest_review_at"].is_string());
+    assert!(stats["bayesian_score"].is_number());
+}
+
+#[sqlx::test]
+async fn reconcile_aggregates_dry_run_reports_drift(pool: PgPool) {
+    let app = auto_approving_app(pool.clone());
+    let id = create_review_for(&app, Uuid::new_v4(), 3, None).await;
+    sqlx::query("UPDATE product_rating_aggregates SET star_3 = 5")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let (status, report) = as_admin(
+        app.clone(),
+        "POST",
+        "/admin/aggregates/reconcile?dry_run=true",
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(report["repaired"], false);
+    assert_eq!(report["products_checked"], 1);
+    assert_eq!(report["drift"][0]["expected"]["histogram"]["3"], 1);
+    assert_eq!(report["drift"][0]["stored"]["histogram"]["3"], 5);
+
+    let (status, report) = as_admin(app.clone(), "POST", "/admin/aggregates/reconcile", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(report["repaired"], true);
+    let (_, report) = as_admin(
+        app.clone(),
+        "POST",
+        "/admin/aggregates/reconcile?dry_run=true",
+        None,
+    )
+    .await;
+    assert_eq!(report["drift"].as_array().unwrap().len(), 0);
+
+    let (_, got) = request(app, "GET", &format!("/reviews/{}", id), None).await;
+    assert_eq!(got["rating"], 3);
+}
+
+#[sqlx::test]
+async fn timeseries_returns_every_bucket(pool: PgPool) {
+    let app = auto_approving_app(pool);
+    create_review_for(&app, Uuid::new_v4(), 4, None).await;
+
+    let (status, series) = as_admin(app.clone(), "GET", "/stats/timeseries", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(series["bucket"], "day");
+    assert_eq!(series["tz"], "UTC");
+    let points = series["points"].as_array().unwrap();
+    assert!(points.len() >= 30);
+    let total: u64 = points
+        .iter()
+        .map(|p| p["review_count"].as_u64().unwrap())
+        .sum();
+    assert_eq!(total, 1);
+
+    // Berlin months January–March 2024; CEST begins on March 31, so April starts at 22:00Z.
+    let uri = "/stats/timeseries?bucket=month&from=2023-12-31T23:00:00Z&to=2024-03-31T22:00:00Z&tz=Europe/Berlin";
+    let (status, series) = as_admin(app, "GET", uri, None).await;
+    assert_eq!(status, StatusCode::OK);
+    let starts: Vec<&str> = series["points"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|p| p["bucket_start"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        starts,
+        vec![
+            "2023-12-31T23:00:00Z",
+            "2024-01-31T23:00:00Z",
+            "2024-02-29T23:00:00Z"
+        ]
+    );
+}
+
+#[sqlx::test]
+async fn timeseries_rejects_bad_range_and_time_zone(pool: PgPool) {
+    let app = auto_approving_app(pool);
+    let uri = "/stats/timeseries?from=2024-02-01T00:00:00Z&to=2024-01-01T00:00:00Z";
+    let (status, problem) = as_admin(app.clone(), "GET", uri, None).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(problem["errors"][0]["field"], "from");
+
+    let (status, _) = as_admin(app, "GET", "/stats/timeseries?tz=Mars/Olympus_Mons", None).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[sqlx::test]
+async fn top_products_accepts_window_and_rejects_bad_one(pool: PgPool) {
+    let app = auto_approving_app(pool);
+    create_review_for(&app, Uuid::new_v4(), 5, None).await;
+
+    let (status, page) = as_admin(
+        app.clone(),
+        "GET",
+        "/stats/products/top?order=best&window=30d&limit=5",
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(page["order"], "best");
+    assert_eq!(page["window"], "30d");
+    assert_eq!(page["items"][0]["rank"], 1);
+    assert_eq!(page["items"][0]["histogram"]["5"], 1);
+    assert!(page["next_offset"].is_null());
+
+    let (status, _) = as_admin(
+        app.clone(),
+        "GET",
+        "/stats/products/top?window=forever",
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    for window in ["3651d", "4294967295w"] {
+        let uri = format!("/stats/products/top?window={}", window);
+        let (status, problem) = as_admin(app.clone(), "GET", &uri, None).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(problem["errors"][0]["field"], "window");
+    }
+    let (status, _) = as_admin(app, "GET", "/stats/products/top?window=3650d", None).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[sqlx::test]
+async fn search_reviews_returns_ranked_hits_with_snippets(pool: PgPool) {
+    let app = auto_approving_app(pool);
+    let hit = create_review_for(&app, Uuid::new_v4(), 5, Some("Great sound, great battery")).await;
+    create_review_for(&app, Uuid::new_v4(), 2, Some("Poor battery")).await;
+    create_review_for(&app, Uuid::new_v4(), 4, Some("Nothing relevant here")).await;
+
+    let (status, page) = request(
+        app.clone(),
+        "GET",
+        "/reviews/search?q=great%20batt*&limit=1",
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(page["items"].as_array().unwrap().len(), 1);
+    assert_eq!(page["items"][0]["id"], hit);
+    assert_eq!(page["items"][0]["rating"], 5);
+    assert!(page["items"][0]["snippet"]
+        .as_str()
+        .unwrap()
+        .contains("<mark>"));
+    assert!(page["next_offset"].is_null());
+
+    let (_, page) = request(
+        app.clone(),
+        "GET",
+        "/reviews/search?q=battery&limit=1",
+        None,
+    )
+    .await;
+    assert_eq!(page["next_offset"], 1);
+
+    let (status, _) = request(app, "GET", "/reviews/search?q=%22%22", None).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[sqlx::test]
+async fn new_reviews_wait_for_moderation(pool: PgPool) {
+    let app = app(pool);
+    let author = Uuid::new_v4();
+    let id = create_review_for(&app, author, 5, Some("great")).await;
+
+    let (_, list) = request(app.clone(), "GET", "/reviews", None).await;
+    assert_eq!(list["items"].as_array().unwrap().len(), 0);
+    let (status, _) = request(app.clone(), "GET", &format!("/reviews/{}", id), None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, own) = request_as(
+        app.clone(),
+        Some(author),
+        "GET",
+        &format!("/reviews/{}", id),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(own["status"], "pending");
+    assert!(own.get("moderation").is_none());
+    let (_, stats) = as_admin(app.clone(), "GET", "/stats/dashboard", None).await;
+    assert_eq!(stats["total_reviews"], 0);
+
+    let (status, queue) = as_admin(app, "GET", "/moderation/queue", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(queue["items"][0]["id"], id.as_str());
+}
+
+#[sqlx::test]
+async fn approving_publishes_and_counts_the_review(pool: PgPool) {
+    let app = app(pool);
+    let moderator = Uuid::new_v4();
+    let id = create_review_for(&app, Uuid::new_v4(), 4, None).await;
+
+    let uri = format!("/moderation/reviews/{}/approve", id);
+    let (status, _) = request(app.clone(), "POST", &uri, None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, approved) =
+        request_as_role(app.clone(), moderator, "moderator", "POST", &uri, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(approved["status"], "approved");
+    assert_eq!(
+        approved["moderation"]["moderated_by"],
+        moderator.to_string()
+    );
+
+    let (_, list) = request(app.clone(), "GET", "/reviews", None).await;
+    assert_eq!(list["items"][0]["id"], id.as_str());
+    let (_, stats) = as_admin(app.clone(), "GET", "/stats/dashboard", None).await;
+    assert_eq!(stats["total_reviews"], 1);
+    let (_, queue) = as_admin(app, "GET", "/moderation/queue", None).await;
+    assert_eq!(queue["items"].as_array().unwrap().len(), 0);
+}
+
+#[sqlx::test]
+async fn rejecting_requires_a_reason_and_uncounts_the_review(pool: PgPool) {
+    let app = app(pool);
+    let moderator = Uuid::new_v4();
+    let id = create_review_for(&app, Uuid::new_v4(), 2, None).await;
+    let approve = format!("/moderation/reviews/{}/approve", id);
+    request_as_role(app.clone(), moderator, "moderator", "POST", &approve, None).await;
+
+    let reject = format!("/moderation/reviews/{}/reject", id);
+    let (status, problem) = request_as_role(
+        app.clone(),
+        moderator,
+        "moderator",
+        "POST",
+        &reject,
+        Some(json!({ "reason": "  " })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(problem["errors"][0]["field"], "reason");
+
+    let (status, rejected) = request_as_role(
+        app.clone(),
+        moderator,
+        "moderator",
+        "POST",
+        &reject,
+        Some(json!({ "reason": "spam" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(rejected["status"], "rejected");
+    assert_eq!(rejected["moderation"]["reason"], "spam");
+    let (_, stats) = as_admin(app.clone(), "GET", "/stats/dashboard", None).await;
+    assert_eq!(stats["total_reviews"], 0);
+
+    let missing = format!("/moderation/reviews/{}/reject", Uuid::new_v4());
+    let (status, _) = request_as_role(
+        app,
+        moderator,
+        "moderator",
+        "POST",
+        &missing,
+        Some(json!({ "reason": "spam" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test]
+async fn author_edits_do_not_overturn_a_rejection(pool: PgPool) {
+    let app = auto_approving_app(pool);
+    let author = Uuid::new_v4();
+    let id = create_review_for(&app, author, 2, Some("meh")).await;
+    let uri = format!("/reviews/{}", id);
+    let reject = format!("/moderation/reviews/{}/reject", id);
+    let (status, _) = request_as_role(
+        app.clone(),
+        Uuid::new_v4(),
+        "moderator",
+        "POST",
+        &reject,
+        Some(json!({ "reason": "spam" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, edited) = request_as(
+        app.clone(),
+        Some(author),
+        "PATCH",
+        &uri,
+        Some(json!({ "rating": 5, "body": "fine, actually" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(edited["status"], "rejected");
+    assert_eq!(edited["moderation"]["reason"], "spam");
+
+    let mine = format!(
+        "/products/{}/reviews/mine",
+        edited["product_id"].as_str().unwrap()
+    );
+    let (status, replaced) = request_as(
+        app.clone(),
+        Some(author),
+        "PUT",
+        &mine,
+        Some(json!({ "rating": 4, "body": "really fine" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(replaced["status"], "rejected");
+
+    let (status, _) = request(app.clone(), "GET", &uri, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (_, stats) = as_admin(app, "GET", "/stats/dashboard", None).await;
+    assert_eq!(stats["total_reviews"], 0);
+}
+
+#[sqlx::test]
+async fn author_edits_keep_an_approved_review_published_unless_flagged(pool: PgPool) {
+    let app = app(pool);
+    let author = Uuid::new_v4();
+    let moderator = Uuid::new_v4();
+    let id = create_review_for(&app, author, 3, Some("decent")).await;
+    let uri = format!("/reviews/{}", id);
+    let approve = format!("/moderation/reviews/{}/approve", id);
+    request_as_role(app.clone(), moderator, "moderator", "POST", &approve, None).await;
+
+    let (status, edited) = request_as(
+        app.clone(),
+        Some(author),
+        "PATCH",
+        &uri,
+        Some(json!({ "rating": 5 })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(edited["status"], "approved");
+    assert_eq!(edited["moderation"]["moderated_by"], moderator.to_string());
+    let (status, _) = request(app.clone(), "GET", &uri, None).await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, stats) = as_admin(app.clone(), "GET", "/stats/dashboard", None).await;
+    assert_eq!(stats["total_reviews"], 1);
+
+    // A flagged body goes back to the queue, and the old approval no longer describes it.
+    let (status, edited) = request_as(
+        app.clone(),
+        Some(author),
+        "PATCH",
+        &uri,
+        Some(json!({ "body": "cheaper at https://example.com" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(edited["status"], "pending");
+    assert!(edited.get("moderation").is_none());
+    let (status, _) = request(app, "GET", &uri, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test]
+async fn reports_hide_a_review_once_the_threshold_is_reached(pool: PgPool) {
+    let app = app_with_config(
+        pool,
+        Config {
+            auto_approve: true,
+            report_threshold: 2,
+            ..config()
+        },
+    );
+    let author = Uuid::new_v4();
+    let id = create_review_for(&app, author, 5, Some("great")).await;
+    let uri = format!("/reviews/{}/reports", id);
+    let report = json!({ "reason": "spam", "comment": "  advert  " });
+
+    let (status, _) = request(app.clone(), "POST", &uri, Some(report.clone())).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = request_as(
+        app.clone(),
+        Some(Uuid::new_v4()),
+        "POST",
+        &uri,
+        Some(json!({ "reason": "boring" })),
+    )
+    .await;
+    assert!(status.is_client_error());
+
+    let first = Uuid::new_v4();
+    let (status, created) =
+        request_as(app.clone(), Some(first), "POST", &uri, Some(report.clone())).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(created["reason"], "spam");
+    assert_eq!(created["comment"], "advert");
+    let (status, _) =
+        request_as(app.clone(), Some(first), "POST", &uri, Some(report.clone())).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let (status, _) = request(app.clone(), "GET", &format!("/reviews/{}", id), None).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = request_as(
+        app.clone(),
+        Some(Uuid::new_v4()),
+        "POST",
+        &uri,
+        Some(report.clone()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, _) = request(app.clone(), "GET", &format!("/reviews/{}", id), None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = request_as(
+        app.clone(),
+        Some(Uuid::new_v4()),
+        "POST",
+        &uri,
+        Some(report),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // Editing does not bring the review back past the reports.
+    let (status, edited) = request_as(
+        app.clone(),
+        Some(author),
+        "PATCH",
+        &format!("/reviews/{}", id),
+        Some(json!({ "body": "great, honestly" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(edited["status"], "hidden");
+    let (status, _) = request(app.clone(), "GET", &format!("/reviews/{}", id), None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (_, queue) = as_admin(app.clone(), "GET", "/moderation/queue?status=hidden", None).await;
+    assert_eq!(queue["items"][0]["id"], id.as_str());
+    let (status, reports) = as_admin(
+        app,
+        "GET",
+        &format!("/moderation/reports?review_id={}&limit=1", id),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(reports["items"].as_array().unwrap().len(), 1);
+    assert_eq!(reports["next_offset"], 1);
+}
+
+#[sqlx::test]
+async fn votes_count_per_user_and_rank_most_helpful_first(pool: PgPool) {
+    let app = auto_approving_app(pool);
+    let author = Uuid::new_v4();
+    let product_id = Uuid::new_v4();
+    let mut ids = Vec::new();
+    for user in [author, Uuid::new_v4()] {
+        let body = json!({ "product_id": product_id, "rating": 4 });
+        let (_, created) =
+            request_as(app.clone(), Some(user), "POST", "/reviews", Some(body)).await;
+        assert_eq!(created["helpful_count"], 0);
+        ids.push(created["id"].as_str().unwrap().to_string());
+    }
+    let votes = format!("/reviews/{}/votes", ids[1]);
+    let voter = Uuid::new_v4();
+
+    let (status, _) = request_as(
+        app.clone(),
+        Some(author),
+        "POST",
+        &format!("/reviews/{}/votes", ids[0]),
+        Some(json!({ "vote": "helpful" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = request(
+        app.clone(),
+        "POST",
+        &votes,
+        Some(json!({ "vote": "helpful" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, voted) = request_as(
+        app.clone(),
+        Some(voter),
+        "POST",
+        &votes,
+        Some(json!({ "vote": "unhelpful" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        (
+            voted["helpful_count"].clone(),
+            voted["unhelpful_count"].clone()
+        ),
+        (json!(0), json!(1))
+    );
+    let (_, voted) = request_as(
+        app.clone(),
+        Some(voter),
+        "POST",
+        &votes,
+        Some(json!({ "vote": "helpful" })),
+    )
+    .await;
+    assert_eq!(
+        (
+            voted["helpful_count"].clone(),
+            voted["unhelpful_count"].clone()
+        ),
+        (json!(1), json!(0))
+    );
+
+    let (_, page) = request(
+        app.clone(),
+        "GET",
+        &format!(
+            "/reviews?product_id={}&sort=most_helpful&limit=1",
+            product_id
+        ),
+        None,
+    )
+    .await;
+    assert_eq!(page["items"][0]["id"], ids[1].as_str());
+    let cursor = page["next_cursor"].as_str().unwrap();
+    let uri = format!(
+        "/reviews?product_id={}&sort=most_helpful&limit=1&cursor={}",
+        product_id, cursor
+    );
+    let (_, page) = request(app.clone(), "GET", &uri, None).await;
+    assert_eq!(page["items"][0]["id"], ids[0].as_str());
+
+    let (status, _) = request_as(
+        app.clone(),
+        Some(voter),
+        "DELETE",
+        &format!("{}/mine", votes),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (_, review) = request(app, "GET", &format!("/reviews/{}", ids[1]), None).await;
+    assert_eq!(review["helpful_count"], 0);
+}
+
+#[sqlx::test]
+async fn merchant_replies_are_moderated_before_they_are_embedded(pool: PgPool) {
+    let app = app(pool);
+    let (author, merchant, moderator) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+    let id = create_review_for(&app, author, 2, Some("arrived late")).await;
+    let uri = format!("/reviews/{}/reply", id);
+    let reply = json!({ "body": "  Sorry about that, we have changed couriers.  " });
+
+    let (status, _) = request_as_role(
+        app.clone(),
+        merchant,
+        "merchant",
+        "POST",
+        &uri,
+        Some(reply.clone()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    request_as_role(
+        app.clone(),
+        moderator,
+        "moderator",
+        "POST",
+        &format!("/moderation/reviews/{}/approve", id),
+        None,
+    )
+    .await;
+
+    // Only merchants reply, and never to their own review.
+    let (status, _) = send(app.clone(), None, "POST", &uri, Some(reply.clone())).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = request_as(
+        app.clone(),
+        Some(Uuid::new_v4()),
+        "POST",
+        &uri,
+        Some(reply.clone()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = request_as_role(
+        app.clone(),
+        author,
+        "merchant",
+        "POST",
+        &uri,
+        Some(reply.clone()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = request_as_role(
+        app.clone(),
+        merchant,
+        "merchant",
+        "POST",
+        &uri,
+        Some(json!({ "body": "   " })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    let (status, created) = request_as_role(
+        app.clone(),
+        merchant,
+        "merchant",
+        "POST",
+        &uri,
+        Some(reply.clone()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(created["status"], "pending");
+    assert_eq!(
+        created["body"],
+        "Sorry about that, we have changed couriers."
+    );
+    let (status, _) = request_as_role(
+        app.clone(),
+        Uuid::new_v4(),
+        "merchant",
+        "POST",
+        &uri,
+        Some(reply),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let (_, review) = request(app.clone(), "GET", &format!("/reviews/{}", id), None).await;
+    assert!(review.get("reply").is_none());
+    let (_, queue) = as_admin(app.clone(), "GET", "/moderation/replies", None).await;
+    assert_eq!(queue["items"][0]["review_id"], id.as_str());
+
+    let reject = format!("/moderation/reviews/{}/reply/reject", id);
+    let (status, _) = request_as_role(
+        app.clone(),
+        moderator,
+        "moderator",
+        "POST",
+        &reject,
+        Some(json!({})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    let approve = format!("/moderation/reviews/{}/reply/approve", id);
+    let (status, approved) =
+        request_as_role(app.clone(), moderator, "moderator", "POST", &approve, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        approved["moderation"]["moderated_by"],
+        moderator.to_string()
+    );
+    let (_, list) = request(app.clone(), "GET", "/reviews", None).await;
+    assert_eq!(
+        list["items"][0]["reply"]["merchant_id"],
+        merchant.to_string()
+    );
+
+    let edit = json!({ "body": "We have refunded your shipping." });
+    let (status, _) = request_as_role(
+        app.clone(),
+        Uuid::new_v4(),
+        "merchant",
+        "PATCH",
+        &uri,
+        Some(edit.clone()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    // A shopper cannot edit or delete a merchant's reply, even their own review's.
+    let (status, _) =
+        request_as(app.clone(), Some(author), "PATCH", &uri, Some(edit.clone())).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = request_as(app.clone(), Some(author), "DELETE", &uri, None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = send(app.clone(), None, "DELETE", &uri, None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, edited) =
+        request_as_role(app.clone(), merchant, "merchant", "PATCH", &uri, Some(edit)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(edited["status"], "pending");
+    let (_, review) = request(app.clone(), "GET", &format!("/reviews/{}", id), None).await;
+    assert!(review.get("reply").is_none());
+
+    let (status, _) =
+        request_as_role(app.clone(), merchant, "merchant", "DELETE", &uri, None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = request_as_role(app, merchant, "merchant", "DELETE", &uri, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,216 @@
+//! Bearer-token verification tests. Tokens are signed here; no database required.
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use my_ex_review_service::auth::{AuthConfig, AuthError, Role};
+use rsa::pkcs8::{DecodePrivateKey, EncodePublicKey, LineEnding};
+use rsa::signature::{SignatureEncoding, Signer};
+use rsa::traits::PublicKeyParts;
+use rsa::RsaPrivateKey;
+use serde_json::{json, Value};
+use sha2::Sha256;
+use uuid::Uuid;
+
+const SECRET: &[u8] = b"auth-tests-secret";
+const RSA_PRIVATE_KEY: &str = include_str!("fixtures/jwt_rs256_private.pem");
+
+fn encode(value: &Value) -> String {
+    URL_SAFE_NO_PAD.encode(serde_json::to_vec(value).unwrap())
+}
+
+fn hs256(claims: Value, secret: &[u8]) -> String {
+    let signed = format!(
+        "{}.{}",
+        encode(&json!({ "alg": "HS256", "typ": "JWT" })),
+        encode(&claims)
+    );
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).unwrap();
+    mac.update(signed.as_bytes());
+    format!(
+        "{}.{}",
+        signed,
+        URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes())
+    )
+}
+
+fn rs256(claims: Value, kid: &str) -> String {
+    let key = RsaPrivateKey::from_pkcs8_pem(RSA_PRIVATE_KEY).unwrap();
+    let signed = format!(
+        "{}.{}",
+        encode(&json!({ "alg": "RS256", "kid": kid })),
+        encode(&claims)
+    );
+    let signature = rsa::pkcs1v15::SigningKey::<Sha256>::new(key).sign(signed.as_bytes());
+    format!(
+        "{}.{}",
+        signed,
+        URL_SAFE_NO_PAD.encode(signature.to_bytes())
+    )
+}
+
+fn claims(user: Uuid) -> Value {
+    json!({ "sub": user.to_string(), "exp": Utc::now().timestamp() + 300 })
+}
+
+fn jwks() -> String {
+    let key = RsaPrivateKey::from_pkcs8_pem(RSA_PRIVATE_KEY)
+        .unwrap()
+        .to_public_key();
+    json!({ "keys": [
+        { "kty": "EC", "kid": "ec", "crv": "P-256", "x": "", "y": "" },
+        {
+            "kty": "RSA",
+            "kid": "k1",
+            "use": "sig",
+            "alg": "RS256",
+            "n": URL_SAFE_NO_PAD.encode(key.n().to_bytes_be()),
+            "e": URL_SAFE_NO_PAD.encode(key.e().to_bytes_be()),
+        },
+    ]})
+    .to_string()
+}
+
+#[test]
+fn hs256_token_names_its_subject() {
+    let auth = AuthConfig::default().with_hs256_secret(SECRET);
+    let user = Uuid::new_v4();
+    assert_eq!(
+        auth.verify(&hs256(claims(user), SECRET)).unwrap().user_id,
+        user
+    );
+
+    let forged = hs256(claims(user), b"someone-else");
+    assert!(matches!(auth.verify(&forged), Err(AuthError::BadSignature)));
+    assert!(matches!(
+        AuthConfig::default().verify(&hs256(claims(user), SECRET)),
+        Err(AuthError::UnknownKey)
+    ));
+    assert!(matches!(
+        auth.verify("not-a-token"),
+        Err(AuthError::Malformed)
+    ));
+}
+
+#[test]
+fn roles_claim_is_read_and_unknown_roles_ignored() {
+    let auth = AuthConfig::default().with_hs256_secret(SECRET);
+    let moderator = json!({ "sub": Uuid::new_v4(), "exp": Utc::now().timestamp() + 300, "roles": ["moderator", "superuser"] });
+    let principal = auth.verify(&hs256(moderator, SECRET)).unwrap();
+    assert_eq!(principal.roles, vec![Role::Moderator]);
+    assert!(principal.has_role(Role::Moderator));
+    assert!(!principal.has_role(Role::Merchant));
+
+    let admin =
+        json!({ "sub": Uuid::new_v4(), "exp": Utc::now().timestamp() + 300, "roles": ["admin"] });
+    assert!(auth
+        .verify(&hs256(admin, SECRET))
+        .unwrap()
+        .has_role(Role::Merchant));
+    assert!(auth
+        .verify(&hs256(claims(Uuid::new_v4()), SECRET))
+        .unwrap()
+        .roles
+        .is_empty());
+}
+
+#[test]
+fn unsigned_tokens_are_refused() {
+    let auth = AuthConfig::default().with_hs256_secret(SECRET);
+    let token = format!(
+        "{}.{}.",
+        encode(&json!({ "alg": "none" })),
+        encode(&claims(Uuid::new_v4()))
+    );
+    assert!(
+        matches!(auth.verify(&token), Err(AuthError::UnsupportedAlgorithm(alg)) if alg == "none")
+    );
+}
+
+#[test]
+fn time_and_audience_claims_are_checked() {
+    let mut auth = AuthConfig::default().with_hs256_secret(SECRET);
+    auth.issuer = Some("https://id.example".to_string());
+    auth.audience = Some("reviews".to_string());
+    let user = Uuid::new_v4();
+    let now = Utc::now().timestamp();
+    let token = |extra: Value| {
+        let mut c = json!({ "sub": user.to_string(), "exp": now + 300, "iss": "https://id.example", "aud": ["shop", "reviews"] });
+        c.as_object_mut()
+            .unwrap()
+            .extend(extra.as_object().unwrap().clone());
+        hs256(c, SECRET)
+    };
+
+    assert!(auth.verify(&token(json!({}))).is_ok());
+    // Within the leeway an expired token still passes.
+    assert!(auth.verify(&token(json!({ "exp": now - 30 }))).is_ok());
+    assert!(matches!(
+        auth.verify(&token(json!({ "exp": now - 600 }))),
+        Err(AuthError::Expired)
+    ));
+    assert!(matches!(
+        auth.verify(&token(json!({ "nbf": now + 600 }))),
+        Err(AuthError::NotYetValid)
+    ));
+    assert!(matches!(
+        auth.verify(&token(json!({ "iss": "https://evil.example" }))),
+        Err(AuthError::WrongIssuer)
+    ));
+    assert!(matches!(
+        auth.verify(&token(json!({ "aud": "shop" }))),
+        Err(AuthError::WrongAudience)
+    ));
+    assert!(matches!(
+        auth.verify(&token(json!({ "sub": "alice" }))),
+        Err(AuthError::InvalidSubject)
+    ));
+}
+
+#[test]
+fn rs256_tokens_verify_against_jwks_and_pem_keys() {
+    let user = Uuid::new_v4();
+    let auth = AuthConfig::default().with_jwks(&jwks()).unwrap();
+    assert_eq!(
+        auth.verify(&rs256(claims(user), "k1")).unwrap().user_id,
+        user
+    );
+    assert!(matches!(
+        auth.verify(&rs256(claims(user), "k2")),
+        Err(AuthError::UnknownKey)
+    ));
+    // An HS256 token cannot be checked with an RSA key.
+    assert!(matches!(
+        auth.verify(&hs256(claims(user), SECRET)),
+        Err(AuthError::UnknownKey)
+    ));
+
+    let pem = RsaPrivateKey::from_pkcs8_pem(RSA_PRIVATE_KEY)
+        .unwrap()
+        .to_public_key()
+        .to_public_key_pem(LineEnding::LF)
+        .unwrap();
+    let auth = AuthConfig::default().with_rs256_pem(&pem).unwrap();
+    assert_eq!(
+        auth.verify(&rs256(claims(user), "any")).unwrap().user_id,
+        user
+    );
+}
+
+#[test]
+fn unusable_keys_are_rejected_at_load() {
+    let only_ec =
+        json!({ "keys": [{ "kty": "EC", "crv": "P-256", "x": "", "y": "" }] }).to_string();
+    assert!(matches!(
+        AuthConfig::default().with_jwks(&only_ec),
+        Err(AuthError::Key(_))
+    ));
+    assert!(matches!(
+        AuthConfig::default().with_jwks("{"),
+        Err(AuthError::Key(_))
+    ));
+    assert!(matches!(
+        AuthConfig::default().with_rs256_pem("not a key"),
+        Err(AuthError::Key(_))
+    ));
+}
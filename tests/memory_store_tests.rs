@@ -0,0 +1,523 @@
+//! Tests against the in-memory store. No database required.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use hmac::{Hmac, Mac};
+use http_body_util::BodyExt;
+use my_ex_review_service::app_with_store;
+use my_ex_review_service::auth::hash_api_key;
+use my_ex_review_service::auth::AuthConfig;
+use my_ex_review_service::config::Config;
+use my_ex_review_service::error::ReviewError;
+use my_ex_review_service::models::{
+    ApiKey, ApiScope, CreateReview, ListReviewsQuery, ModerationDecision, NewApiKey, ReviewFilter,
+    ReviewSort, ReviewStatus, SearchQuery, SearchTerms, TimeseriesQuery, UpdateReview,
+};
+use my_ex_review_service::pagination::Cursor;
+use my_ex_review_service::rate_limit::{
+    InMemoryRateLimitStore, RateLimit, RateLimitStore, RateLimits,
+};
+use my_ex_review_service::repository::{InMemoryReviewStore, ReviewStore};
+use my_ex_review_service::service::ReviewService;
+use serde_json::{json, Value};
+use sha2::Sha256;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+const JWT_SECRET: &[u8] = b"memory-tests-secret";
+
+fn app() -> axum::Router<()> {
+    let config = Config {
+        auto_approve: true,
+        auth: AuthConfig::default().with_hs256_secret(JWT_SECRET),
+        ..Config::default()
+    };
+    app_with_store(Arc::new(InMemoryReviewStore::new()), config)
+}
+
+/// An HS256 bearer token for a fresh user with `roles`, valid for five minutes.
+fn bearer(roles: &[&str]) -> String {
+    let encode = |v: Value| URL_SAFE_NO_PAD.encode(serde_json::to_vec(&v).unwrap());
+    let claims = json!({ "sub": Uuid::new_v4(), "exp": chrono::Utc::now().timestamp() + 300, "roles": roles });
+    let signed = format!("{}.{}", encode(json!({ "alg": "HS256" })), encode(claims));
+    let mut mac = Hmac::<Sha256>::new_from_slice(JWT_SECRET).unwrap();
+    mac.update(signed.as_bytes());
+    format!(
+        "Bearer {}.{}",
+        signed,
+        URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes())
+    )
+}
+
+fn service() -> ReviewService {
+    ReviewService::with_store(Arc::new(InMemoryReviewStore::new())).with_auto_approve(true)
+}
+
+fn new_review(product_id: Uuid, rating: i32) -> CreateReview {
+    CreateReview {
+        product_id,
+        user_id: Uuid::new_v4(),
+        rating,
+        body: None,
+    }
+}
+
+/// Helper: send request to app, with `authorization` if given, and return (status, body as JSON).
+async fn request(
+    app: axum::Router<()>,
+    authorization: Option<&str>,
+    method: &str,
+    uri: &str,
+    body: Option<Value>,
+) -> (StatusCode, Value) {
+    let body = body.map(|v| Body::from(serde_json::to_vec(&v).unwrap()));
+    let mut req = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("content-type", "application/json");
+    if let Some(value) = authorization {
+        req = req.header(header::AUTHORIZATION, value);
+    }
+    let req = req.body(body.unwrap_or_else(Body::empty)).unwrap();
+    let response = app.oneshot(req).await.unwrap();
+    let status = response.status();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let json = if body.is_empty() {
+        json!(null)
+    } else {
+        serde_json::from_slice(&body).unwrap()
+    };
+    (status, json)
+}
+
+#[tokio::test]
+async fn api_create_then_get_and_stats() {
+    let app = app();
+    let product_id = Uuid::new_v4();
+    let (status, created) = request(
+        app.clone(),
+        Some(&bearer(&[])),
+        "POST",
+        "/reviews",
+        Some(json!({ "product_id": product_id, "rating": 4, "body": "Solid" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let id = created["id"].as_str().unwrap();
+    let (status, fetched) =
+        request(app.clone(), None, "GET", &format!("/reviews/{}", id), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(fetched["body"], "Solid");
+
+    let (status, stats) = request(
+        app.clone(),
+        None,
+        "GET",
+        &format!("/products/{}/stats", product_id),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(stats["review_count"], 1);
+    assert_eq!(stats["histogram"]["4"], 1);
+
+    let (_, dashboard) = request(
+        app,
+        Some(&bearer(&["merchant"])),
+        "GET",
+        "/stats/dashboard",
+        None,
+    )
+    .await;
+    assert_eq!(dashboard["total_reviews"], 1);
+    assert_eq!(dashboard["avg_rating"], 4.0);
+}
+
+#[tokio::test]
+async fn api_duplicate_review_conflicts() {
+    let app = app();
+    let payload = json!({ "product_id": Uuid::new_v4(), "rating": 3 });
+    let bearer = bearer(&[]);
+    let (status, _) = request(
+        app.clone(),
+        Some(&bearer),
+        "POST",
+        "/reviews",
+        Some(payload.clone()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, problem) = request(app, Some(&bearer), "POST", "/reviews", Some(payload)).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(
+        problem["detail"],
+        "This user has already reviewed this product"
+    );
+}
+
+#[tokio::test]
+async fn list_reviews_pages_in_sort_order() {
+    let service = service();
+    let product_id = Uuid::new_v4();
+    for rating in [2, 5, 3, 1, 4] {
+        service
+            .create_review(new_review(product_id, rating))
+            .await
+            .unwrap();
+    }
+
+    let mut seen = Vec::new();
+    let mut cursor = None;
+    loop {
+        let query = ListReviewsQuery {
+            cursor,
+            limit: Some(2),
+            sort: Some(ReviewSort::Highest),
+        };
+        let page = service
+            .list_reviews(ReviewFilter::default(), query)
+            .await
+            .unwrap();
+        seen.extend(page.items.iter().map(|r| r.rating));
+        match page.next_cursor {
+            Some(token) => cursor = Some(Cursor::decode(&token).unwrap()),
+            None => break,
+        }
+    }
+    assert_eq!(seen, vec![5, 4, 3, 2, 1]);
+}
+
+#[tokio::test]
+async fn update_moves_rating_between_stars() {
+    let service = service();
+    let product_id = Uuid::new_v4();
+    let body = new_review(product_id, 2);
+    let author = body.user_id;
+    let created = service.create_review(body).await.unwrap();
+
+    let patch = UpdateReview {
+        rating: Some(5),
+        ..Default::default()
+    };
+    service
+        .update_review(created.id, author, patch)
+        .await
+        .unwrap();
+
+    let stats = service.get_product_stats(product_id).await.unwrap();
+    assert_eq!(stats.review_count, 1);
+    assert_eq!(stats.histogram.two, 0);
+    assert_eq!(stats.histogram.five, 1);
+}
+
+#[tokio::test]
+async fn edits_leave_a_rejected_review_rejected() {
+    let service = service();
+    let product_id = Uuid::new_v4();
+    let body = new_review(product_id, 2);
+    let author = body.user_id;
+    let created = service.create_review(body).await.unwrap();
+    let decision = ModerationDecision {
+        reason: Some("spam".to_string()),
+    };
+    service
+        .reject_review(created.id, Uuid::new_v4(), decision)
+        .await
+        .unwrap();
+
+    let patch = UpdateReview {
+        rating: Some(5),
+        body: Some(Some("better now".to_string())),
+    };
+    let edited = service
+        .update_review(created.id, author, patch)
+        .await
+        .unwrap();
+    assert_eq!(edited.status, ReviewStatus::Rejected);
+    assert_eq!(edited.moderation.unwrap().reason.as_deref(), Some("spam"));
+    assert_eq!(
+        service
+            .get_product_stats(product_id)
+            .await
+            .unwrap()
+            .review_count,
+        0
+    );
+}
+
+#[tokio::test]
+async fn delete_removes_review_from_stats_until_restored() {
+    let service = service();
+    let product_id = Uuid::new_v4();
+    let body = new_review(product_id, 4);
+    let author = body.user_id;
+    let created = service.create_review(body).await.unwrap();
+
+    service.delete_review(created.id, author).await.unwrap();
+
+    let err = service.get_review(created.id, None).await.unwrap_err();
+    assert!(matches!(err, ReviewError::NotFound(_)));
+    assert_eq!(
+        service
+            .get_product_stats(product_id)
+            .await
+            .unwrap()
+            .review_count,
+        0
+    );
+
+    let restored = service.restore_review(created.id).await.unwrap();
+    assert_eq!(restored.rating, 4);
+    assert_eq!(
+        service
+            .get_product_stats(product_id)
+            .await
+            .unwrap()
+            .review_count,
+        1
+    );
+
+    service.delete_review(created.id, author).await.unwrap();
+    let service = service.with_deleted_retention(chrono::Duration::zero());
+    assert_eq!(service.purge_deleted().await.unwrap().purged, 1);
+    let err = service.restore_review(created.id).await.unwrap_err();
+    assert!(matches!(err, ReviewError::NotFound(_)));
+}
+
+#[tokio::test]
+async fn timeseries_rejects_non_utc_zone() {
+    let service = service();
+    let query = TimeseriesQuery {
+        tz: Some("Europe/Berlin".to_string()),
+        ..Default::default()
+    };
+    let err = service.get_timeseries(query).await.unwrap_err();
+    assert!(matches!(err, ReviewError::Validation { .. }));
+}
+
+#[tokio::test]
+async fn search_matches_phrase_and_prefix() {
+    let service = service();
+    let product_id = Uuid::new_v4();
+    for text in [
+        "Fast shipping and solid build",
+        "Shipped fast, build feels cheap",
+    ] {
+        let mut body = new_review(product_id, 4);
+        body.body = Some(text.to_string());
+        service.create_review(body).await.unwrap();
+    }
+
+    let query = |q: &str| SearchQuery {
+        q: SearchTerms::try_from(q.to_string()).unwrap(),
+        product_id: None,
+        limit: None,
+        offset: None,
+    };
+    let page = service
+        .search_reviews(query("\"solid build\""))
+        .await
+        .unwrap();
+    assert_eq!(page.items.len(), 1);
+    assert_eq!(
+        page.items[0].snippet,
+        "Fast shipping and <mark>solid</mark> <mark>build</mark>"
+    );
+
+    let page = service.search_reviews(query("ship* build")).await.unwrap();
+    assert_eq!(page.items.len(), 2);
+}
+
+#[tokio::test]
+async fn api_keys_stop_working_once_expired_or_revoked() {
+    let store = Arc::new(InMemoryReviewStore::new());
+    let service = ReviewService::with_store(store.clone());
+    let input = |expires_at| NewApiKey {
+        name: "catalog-service".to_string(),
+        scopes: vec![ApiScope::Stats, ApiScope::Stats],
+        expires_at,
+    };
+    let err = service
+        .create_api_key(input(Some(chrono::Utc::now())))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ReviewError::Validation { .. }));
+
+    let (key, secret) = service.create_api_key(input(None)).await.unwrap();
+    assert!(secret.starts_with(&key.prefix));
+    assert_eq!(key.scopes, vec![ApiScope::Stats]);
+    let client = service
+        .authenticate_api_key(&secret)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        (client.key_id, client.scopes),
+        (key.id, vec![ApiScope::Stats])
+    );
+    service.revoke_api_key(key.id).await.unwrap();
+    assert!(service
+        .authenticate_api_key(&secret)
+        .await
+        .unwrap()
+        .is_none());
+
+    let expired = ApiKey {
+        id: Uuid::new_v4(),
+        name: "order-service".to_string(),
+        prefix: "rvk_expired".to_string(),
+        key_hash: hash_api_key("rvk_expired"),
+        scopes: vec![ApiScope::Read],
+        created_at: chrono::Utc::now() - chrono::Duration::days(2),
+        expires_at: Some(chrono::Utc::now() - chrono::Duration::days(1)),
+        revoked_at: None,
+    };
+    store.create_api_key(&expired).await.unwrap();
+    assert!(service
+        .authenticate_api_key("rvk_expired")
+        .await
+        .unwrap()
+        .is_none());
+
+    let config = Config {
+        auto_approve: true,
+        ..Config::default()
+    };
+    let app = app_with_store(store, config);
+    let req = Request::builder()
+        .uri("/reviews")
+        .header("x-api-key", "rvk_expired")
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(
+        app.oneshot(req).await.unwrap().status(),
+        StatusCode::UNAUTHORIZED
+    );
+}
+
+#[tokio::test]
+async fn review_submissions_are_throttled_per_caller() {
+    let config = Config {
+        auto_approve: true,
+        auth: AuthConfig::default().with_hs256_secret(JWT_SECRET),
+        rate_limits: RateLimits {
+            reviews: Some("2/1h".parse().unwrap()),
+            votes: None,
+        },
+        ..Config::default()
+    };
+    let app = app_with_store(Arc::new(InMemoryReviewStore::new()), config);
+    let submit = |token: &str| {
+        let body = json!({ "product_id": Uuid::new_v4(), "rating": 4 });
+        Request::builder()
+            .method("POST")
+            .uri("/reviews")
+            .header(header::AUTHORIZATION, token)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+
+    let token = bearer(&[]);
+    let first = app.clone().oneshot(submit(&token)).await.unwrap();
+    assert_eq!(first.status(), StatusCode::CREATED);
+    assert_eq!(first.headers()["ratelimit-limit"], "2");
+    assert_eq!(first.headers()["ratelimit-remaining"], "1");
+    assert_eq!(first.headers()["ratelimit-policy"], "2;w=3600");
+    assert_eq!(
+        app.clone().oneshot(submit(&token)).await.unwrap().status(),
+        StatusCode::CREATED
+    );
+
+    let throttled = app.clone().oneshot(submit(&token)).await.unwrap();
+    assert_eq!(throttled.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(throttled.headers()["ratelimit-remaining"], "0");
+    let retry_after: u64 = throttled.headers()[header::RETRY_AFTER]
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!(
+        (1..=1800).contains(&retry_after),
+        "retry after {}s",
+        retry_after
+    );
+
+    // Another user has their own bucket, and reads are not throttled.
+    assert_eq!(
+        app.clone()
+            .oneshot(submit(&bearer(&[])))
+            .await
+            .unwrap()
+            .status(),
+        StatusCode::CREATED
+    );
+    let list = app
+        .oneshot(
+            Request::builder()
+                .uri("/reviews")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(list.status(), StatusCode::OK);
+    assert!(list.headers().get("ratelimit-limit").is_none());
+}
+
+#[tokio::test]
+async fn unverified_user_headers_share_the_client_bucket() {
+    let config = Config {
+        rate_limits: RateLimits {
+            reviews: None,
+            votes: Some("2/1h".parse().unwrap()),
+        },
+        ..Config::default()
+    };
+    let app = app_with_store(Arc::new(InMemoryReviewStore::new()), config);
+    let vote = || {
+        Request::builder()
+            .method("POST")
+            .uri(format!("/reviews/{}/votes", Uuid::new_v4()))
+            .header("x-user-id", Uuid::new_v4().to_string())
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(json!({ "vote": "helpful" }).to_string()))
+            .unwrap()
+    };
+    for _ in 0..2 {
+        assert_ne!(
+            app.clone().oneshot(vote()).await.unwrap().status(),
+            StatusCode::TOO_MANY_REQUESTS
+        );
+    }
+    let throttled = app.oneshot(vote()).await.unwrap();
+    assert_eq!(throttled.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert!(throttled.headers().contains_key(header::RETRY_AFTER));
+}
+
+#[tokio::test]
+async fn rate_limit_buckets_refill_over_the_window() {
+    let store = InMemoryRateLimitStore::new();
+    let limit: RateLimit = "10/1s".parse().unwrap();
+    for _ in 0..10 {
+        assert!(store.acquire("caller", limit).await.allowed);
+    }
+    let denied = store.acquire("caller", limit).await;
+    assert!(!denied.allowed);
+    assert_eq!(denied.remaining, 0);
+    assert!(
+        denied.retry_after > Duration::ZERO && denied.retry_after <= Duration::from_millis(100)
+    );
+    assert!(store.acquire("someone-else", limit).await.allowed);
+
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    assert!(store.acquire("caller", limit).await.allowed);
+    assert!("10/1d".parse::<RateLimit>().is_err());
+    assert!("0/1m".parse::<RateLimit>().is_err());
+    assert!("1/99999999999999999h".parse::<RateLimit>().is_err());
+}
@@ -1,5 +1,6 @@
 //! Repository layer tests. Exercise data access in isolation.
 //!
+//! Every test runs against Postgres, and also against SQLite when built with `--features sqlite`.
 //! Requires DATABASE_URL (from .env or environment). Copy .env.example to .env for `cargo test`.
 
 use ctor::ctor;
@@ -8,28 +9,177 @@ fn load_env() {
     let _ = dotenvy::dotenv();
 }
 
-use my_ex_review_service::models::CreateReview;
-use my_ex_review_service::repository::ReviewRepository;
+use chrono::{DateTime, Utc};
+use my_ex_review_service::error::ReviewError;
+use my_ex_review_service::models::{
+    ApiKey, ApiScope, CreateReport, CreateReview, IdempotencyRecord, LeaderboardOrder,
+    ReportReason, ReviewFilter, ReviewSort, ReviewStatus, Screened, SearchRow, SearchTerms,
+    TimeBucket, UpdateReview,
+};
+use my_ex_review_service::pagination::Cursor;
+use my_ex_review_service::repository::{ReviewRepository, ReviewStore};
 use sqlx::PgPool;
 use uuid::Uuid;
 
-#[sqlx::test]
-async fn find_all_empty(pool: PgPool) {
-    let repo = ReviewRepository::new(pool);
+/// A fresh database for one test, with the raw access some tests need to arrange state.
+enum Db {
+    Postgres(PgPool),
+    #[cfg(feature = "sqlite")]
+    Sqlite(sqlx::SqlitePool),
+}
+
+impl Db {
+    #[cfg(feature = "sqlite")]
+    async fn sqlite() -> Self {
+        // One connection that never expires: each connection to `:memory:` is its own database.
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        my_ex_review_service::repository::SqliteReviewRepository::migrate(&pool)
+            .await
+            .unwrap();
+        Self::Sqlite(pool)
+    }
+
+    fn repo(&self) -> Box<dyn ReviewStore> {
+        match self {
+            Self::Postgres(pool) => Box::new(ReviewRepository::new(pool.clone())),
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(pool) => Box::new(
+                my_ex_review_service::repository::SqliteReviewRepository::new(pool.clone()),
+            ),
+        }
+    }
+
+    fn supports_time_zones(&self) -> bool {
+        match self {
+            Self::Postgres(_) => true,
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(_) => false,
+        }
+    }
+
+    async fn set_created_at(&self, id: Uuid, at: DateTime<Utc>) {
+        match self {
+            Self::Postgres(pool) => {
+                sqlx::query("UPDATE reviews SET created_at = $2 WHERE id = $1")
+                    .bind(id)
+                    .bind(at)
+                    .execute(pool)
+                    .await
+                    .unwrap();
+            }
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(pool) => {
+                sqlx::query("UPDATE reviews SET created_at = ? WHERE id = ?")
+                    .bind(at.timestamp_micros())
+                    .bind(id)
+                    .execute(pool)
+                    .await
+                    .unwrap();
+            }
+        }
+    }
+
+    /// Make the stored aggregate disagree with `reviews`.
+    async fn corrupt_aggregate(&self, product_id: Uuid) {
+        let sql =
+            "UPDATE product_rating_aggregates SET review_count = 7, star_4 = 0 WHERE product_id = ";
+        match self {
+            Self::Postgres(pool) => {
+                sqlx::query(&format!("{sql}$1"))
+                    .bind(product_id)
+                    .execute(pool)
+                    .await
+                    .unwrap();
+            }
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(pool) => {
+                sqlx::query(&format!("{sql}?"))
+                    .bind(product_id)
+                    .execute(pool)
+                    .await
+                    .unwrap();
+            }
+        }
+    }
+}
+
+/// Generate a `#[sqlx::test]` per backend for each `async fn name(db: Db)` listed.
+macro_rules! backend_tests {
+    ($($name:ident),* $(,)?) => {
+        mod postgres {
+            use super::*;
+            $(
+                #[sqlx::test]
+                async fn $name(pool: PgPool) {
+                    super::$name(Db::Postgres(pool)).await;
+                }
+            )*
+        }
+
+        #[cfg(feature = "sqlite")]
+        mod sqlite {
+            use super::*;
+            $(
+                #[tokio::test]
+                async fn $name() {
+                    super::$name(Db::sqlite().await).await;
+                }
+            )*
+        }
+    };
+}
+
+backend_tests!(
+    find_all_empty,
+    find_by_id_not_found,
+    create_and_find_by_id,
+    find_all_returns_created_in_desc_order,
+    find_page_continues_after_cursor,
+    find_page_applies_filters,
+    find_page_sorts_by_rating_across_pages,
+    search_matches_words_phrases_and_prefixes,
+    search_pages_and_follows_edits_and_purges,
+    create_rejects_second_review_of_same_product_by_same_user,
+    upsert_inserts_then_replaces,
+    update_changes_only_given_fields,
+    edits_archive_previous_versions,
+    edits_keep_moderator_decisions,
+    delete_removes_row,
+    deleted_reviews_can_be_restored_until_purged,
+    writes_keep_product_aggregate_in_step,
+    moderation_counts_only_approved_reviews,
+    reports_hide_review_once_threshold_is_reached,
+    votes_update_counts_and_helpful_order,
+    replies_are_one_per_review_and_purged_with_it,
+    reconcile_reports_and_repairs_drift,
+    timeseries_zero_fills_buckets_in_caller_time_zone,
+    top_products_window_ignores_older_reviews,
+    api_keys_are_found_by_hash_and_revoked_once,
+    idempotency_keys_are_claimed_once_until_they_expire,
+    get_stats_empty,
+    get_stats_after_inserts,
+);
+
+async fn find_all_empty(db: Db) {
+    let repo = db.repo();
     let list = repo.find_all().await.unwrap();
     assert!(list.is_empty());
 }
 
-#[sqlx::test]
-async fn find_by_id_not_found(pool: PgPool) {
-    let repo = ReviewRepository::new(pool);
+async fn find_by_id_not_found(db: Db) {
+    let repo = db.repo();
     let got = repo.find_by_id(Uuid::new_v4()).await.unwrap();
     assert!(got.is_none());
 }
 
-#[sqlx::test]
-async fn create_and_find_by_id(pool: PgPool) {
-    let repo = ReviewRepository::new(pool);
+async fn create_and_find_by_id(db: Db) {
+    let repo = db.repo();
     let id = Uuid::new_v4();
     let body = CreateReview {
         product_id: Uuid::new_v4(),
@@ -37,7 +187,10 @@ async fn create_and_find_by_id(pool: PgPool) {
         rating: 4,
         body: Some("nice".to_string()),
     };
-    let created = repo.create(id, &body).await.unwrap();
+    let created = repo
+        .create(id, &body, ReviewStatus::Approved)
+        .await
+        .unwrap();
     assert_eq!(created.id, id);
     assert_eq!(created.rating, 4);
     assert_eq!(created.body.as_deref(), Some("nice"));
@@ -47,48 +200,1231 @@ async fn create_and_find_by_id(pool: PgPool) {
     assert_eq!(found.rating, 4);
 }
 
-#[sqlx::test]
-async fn find_all_returns_created_in_desc_order(pool: PgPool) {
-    let repo = ReviewRepository::new(pool);
+async fn find_all_returns_created_in_desc_order(db: Db) {
+    let repo = db.repo();
+    let product_id = Uuid::new_v4();
+    for _ in 0..2 {
+        let body = CreateReview {
+            product_id,
+            user_id: Uuid::new_v4(),
+            rating: 1,
+            body: None,
+        };
+        repo.create(Uuid::new_v4(), &body, ReviewStatus::Approved)
+            .await
+            .unwrap();
+    }
+
+    let list = repo.find_all().await.unwrap();
+    assert_eq!(list.len(), 2);
+    // Order is created_at DESC, so we just check we got both
+}
+
+async fn find_page_continues_after_cursor(db: Db) {
+    let repo = db.repo();
+    for _ in 0..3 {
+        let body = CreateReview {
+            product_id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            rating: 2,
+            body: None,
+        };
+        repo.create(Uuid::new_v4(), &body, ReviewStatus::Approved)
+            .await
+            .unwrap();
+    }
+
+    let filter = ReviewFilter::default();
+    let first = repo
+        .find_page(&filter, ReviewStatus::Approved, ReviewSort::Newest, None, 2)
+        .await
+        .unwrap();
+    assert_eq!(first.len(), 2);
+    let last = first.last().unwrap();
+    let rest = repo
+        .find_page(
+            &filter,
+            ReviewStatus::Approved,
+            ReviewSort::Newest,
+            Some(Cursor::new(last.rating, last.created_at, last.id)),
+            2,
+        )
+        .await
+        .unwrap();
+    assert_eq!(rest.len(), 1);
+    assert!(first.iter().all(|r| r.id != rest[0].id));
+}
+
+async fn find_page_applies_filters(db: Db) {
+    let repo = db.repo();
+    let product_id = Uuid::new_v4();
+    for (product, rating, text) in [
+        (product_id, 5, Some("loved it")),
+        (product_id, 2, None),
+        (product_id, 4, Some("")),
+        (Uuid::new_v4(), 5, Some("other product")),
+    ] {
+        let body = CreateReview {
+            product_id: product,
+            user_id: Uuid::new_v4(),
+            rating,
+            body: text.map(str::to_string),
+        };
+        repo.create(Uuid::new_v4(), &body, ReviewStatus::Approved)
+            .await
+            .unwrap();
+    }
+
+    let filter = ReviewFilter {
+        product_id: Some(product_id),
+        min_rating: Some(3),
+        ..Default::default()
+    };
+    let rows = repo
+        .find_page(
+            &filter,
+            ReviewStatus::Approved,
+            ReviewSort::Newest,
+            None,
+            10,
+        )
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 2);
+    assert!(rows
+        .iter()
+        .all(|r| r.product_id == product_id && r.rating >= 3));
+
+    let filter = ReviewFilter {
+        product_id: Some(product_id),
+        has_body: Some(false),
+        ..Default::default()
+    };
+    let rows = repo
+        .find_page(
+            &filter,
+            ReviewStatus::Approved,
+            ReviewSort::Newest,
+            None,
+            10,
+        )
+        .await
+        .unwrap();
+    let mut ratings: Vec<i32> = rows.iter().map(|r| r.rating).collect();
+    ratings.sort();
+    assert_eq!(ratings, vec![2, 4]);
+}
+
+async fn find_page_sorts_by_rating_across_pages(db: Db) {
+    let repo = db.repo();
+    for rating in [3, 1, 5, 3, 4] {
+        let body = CreateReview {
+            product_id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            rating,
+            body: None,
+        };
+        repo.create(Uuid::new_v4(), &body, ReviewStatus::Approved)
+            .await
+            .unwrap();
+    }
+
+    let filter = ReviewFilter::default();
+    for (sort, expected) in [
+        (ReviewSort::Highest, vec![5, 4, 3, 3, 1]),
+        (ReviewSort::Lowest, vec![1, 3, 3, 4, 5]),
+    ] {
+        let mut ratings = Vec::new();
+        let mut after = None;
+        loop {
+            let page = repo
+                .find_page(&filter, ReviewStatus::Approved, sort, after, 2)
+                .await
+                .unwrap();
+            if page.is_empty() {
+                break;
+            }
+            let last = page.last().unwrap();
+            after = Some(Cursor::new(last.rating, last.created_at, last.id));
+            ratings.extend(page.iter().map(|r| r.rating));
+        }
+        assert_eq!(ratings, expected);
+    }
+}
+
+async fn search_matches_words_phrases_and_prefixes(db: Db) {
+    let repo = db.repo();
+    let product_id = Uuid::new_v4();
+    let mut ids = Vec::new();
+    for (product, text) in [
+        (
+            product_id,
+            "The battery life is excellent and the battery charges fast",
+        ),
+        (product_id, "Excellent screen, but battery drains quickly"),
+        (product_id, "Life is too short for slow chargers"),
+        (Uuid::new_v4(), "battery battery battery"),
+    ] {
+        let id = Uuid::new_v4();
+        let body = CreateReview {
+            product_id: product,
+            user_id: Uuid::new_v4(),
+            rating: 4,
+            body: Some(text.to_string()),
+        };
+        repo.create(id, &body, ReviewStatus::Approved)
+            .await
+            .unwrap();
+        ids.push(id);
+    }
+    let search = |q: &str, product: Option<Uuid>| {
+        let terms = SearchTerms::try_from(q.to_string()).unwrap();
+        let repo = &repo;
+        async move {
+            let rows = repo.search(&terms, product, 10, 0).await.unwrap();
+            rows.into_iter()
+                .map(|r| (r.review.id, r.snippet))
+                .collect::<Vec<_>>()
+        }
+    };
+
+    let hits = search("Battery", Some(product_id)).await;
+    assert_eq!(
+        hits.iter().map(|h| h.0).collect::<Vec<_>>(),
+        vec![ids[0], ids[1]]
+    );
+    assert!(hits[0].1.contains("<mark>battery</mark>"));
+    assert_eq!(search("battery", None).await.len(), 3);
+
+    let hits = search("\"battery life\"", None).await;
+    assert_eq!(hits.iter().map(|h| h.0).collect::<Vec<_>>(), vec![ids[0]]);
+
+    let mut hits: Vec<Uuid> = search("charg*", None)
+        .await
+        .into_iter()
+        .map(|h| h.0)
+        .collect();
+    hits.sort();
+    let mut expected = vec![ids[0], ids[2]];
+    expected.sort();
+    assert_eq!(hits, expected);
+
+    assert!(search("battery screen", None)
+        .await
+        .iter()
+        .all(|h| h.0 == ids[1]));
+}
+
+async fn search_pages_and_follows_edits_and_purges(db: Db) {
+    let repo = db.repo();
+    let product_id = Uuid::new_v4();
+    let mut ids = Vec::new();
+    for _ in 0..3 {
+        let id = Uuid::new_v4();
+        let body = CreateReview {
+            product_id,
+            user_id: Uuid::new_v4(),
+            rating: 4,
+            body: Some("Sturdy hinge".to_string()),
+        };
+        repo.create(id, &body, ReviewStatus::Approved)
+            .await
+            .unwrap();
+        ids.push(id);
+    }
+    let terms = |q: &str| SearchTerms::try_from(q.to_string()).unwrap();
+    let found = |rows: Vec<SearchRow>| rows.into_iter().map(|r| r.review.id).collect::<Vec<_>>();
+
+    // Equal matches come newest first, and pages do not overlap.
+    let first = found(repo.search(&terms("hinge"), None, 2, 0).await.unwrap());
+    let second = found(repo.search(&terms("hinge"), None, 2, 2).await.unwrap());
+    assert_eq!(first, vec![ids[2], ids[1]]);
+    assert_eq!(second, vec![ids[0]]);
+
+    let patch = UpdateReview {
+        rating: None,
+        body: Some(Some("Wobbly lid".to_string())),
+    };
+    repo.update(ids[0], &patch, Screened::accepted(ReviewStatus::Approved))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        found(repo.search(&terms("hinge"), None, 10, 0).await.unwrap()),
+        vec![ids[2], ids[1]]
+    );
+    assert_eq!(
+        found(repo.search(&terms("wobbly"), None, 10, 0).await.unwrap()),
+        vec![ids[0]]
+    );
+
+    repo.delete(ids[1]).await.unwrap();
+    assert_eq!(
+        found(repo.search(&terms("hinge"), None, 10, 0).await.unwrap()),
+        vec![ids[2]]
+    );
+    repo.purge_deleted(Utc::now() + chrono::Duration::seconds(1))
+        .await
+        .unwrap();
+    assert_eq!(
+        found(repo.search(&terms("hinge"), None, 10, 0).await.unwrap()),
+        vec![ids[2]]
+    );
+}
+
+async fn create_rejects_second_review_of_same_product_by_same_user(db: Db) {
+    let repo = db.repo();
+    let body = CreateReview {
+        product_id: Uuid::new_v4(),
+        user_id: Uuid::new_v4(),
+        rating: 3,
+        body: None,
+    };
+    repo.create(Uuid::new_v4(), &body, ReviewStatus::Approved)
+        .await
+        .unwrap();
+    let err = repo
+        .create(Uuid::new_v4(), &body, ReviewStatus::Approved)
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(err, ReviewError::Conflict(detail) if detail == "This user has already reviewed this product")
+    );
+}
+
+async fn upsert_inserts_then_replaces(db: Db) {
+    let repo = db.repo();
+    let mut body = CreateReview {
+        product_id: Uuid::new_v4(),
+        user_id: Uuid::new_v4(),
+        rating: 2,
+        body: Some("first take".to_string()),
+    };
+    let (first, inserted) = repo
+        .upsert(
+            Uuid::new_v4(),
+            &body,
+            Screened::accepted(ReviewStatus::Approved),
+        )
+        .await
+        .unwrap();
+    assert!(inserted);
+
+    body.rating = 5;
+    body.body = None;
+    let (second, inserted) = repo
+        .upsert(
+            Uuid::new_v4(),
+            &body,
+            Screened::accepted(ReviewStatus::Approved),
+        )
+        .await
+        .unwrap();
+    assert!(!inserted);
+    assert_eq!(second.id, first.id);
+    assert_eq!(second.created_at, first.created_at);
+    assert_eq!(second.rating, 5);
+    assert!(second.body.is_none());
+}
+
+async fn update_changes_only_given_fields(db: Db) {
+    let repo = db.repo();
+    let id = Uuid::new_v4();
+    let body = CreateReview {
+        product_id: Uuid::new_v4(),
+        user_id: Uuid::new_v4(),
+        rating: 2,
+        body: Some("meh".to_string()),
+    };
+    repo.create(id, &body, ReviewStatus::Approved)
+        .await
+        .unwrap();
+
+    let patch = UpdateReview {
+        rating: Some(4),
+        body: None,
+    };
+    let updated = repo
+        .update(id, &patch, Screened::accepted(ReviewStatus::Approved))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(updated.rating, 4);
+    assert_eq!(updated.body.as_deref(), Some("meh"));
+
+    let patch = UpdateReview {
+        rating: None,
+        body: Some(None),
+    };
+    let updated = repo
+        .update(id, &patch, Screened::accepted(ReviewStatus::Approved))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(updated.rating, 4);
+    assert!(updated.body.is_none());
+
+    assert!(repo
+        .update(
+            Uuid::new_v4(),
+            &patch,
+            Screened::accepted(ReviewStatus::Approved)
+        )
+        .await
+        .unwrap()
+        .is_none());
+}
+
+async fn edits_archive_previous_versions(db: Db) {
+    let repo = db.repo();
+    let id = Uuid::new_v4();
+    let body = CreateReview {
+        product_id: Uuid::new_v4(),
+        user_id: Uuid::new_v4(),
+        rating: 2,
+        body: Some("meh".to_string()),
+    };
+    let created = repo
+        .create(id, &body, ReviewStatus::Approved)
+        .await
+        .unwrap();
+    assert_eq!((created.revision, created.edited_at), (1, None));
+
+    let patch = UpdateReview {
+        rating: Some(4),
+        ..UpdateReview::default()
+    };
+    let edited = repo
+        .update(id, &patch, Screened::accepted(ReviewStatus::Approved))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(edited.revision, 2);
+    assert!(edited.edited_at.is_some());
+    // Writing the same values again is not an edit.
+    let same = repo
+        .update(id, &patch, Screened::accepted(ReviewStatus::Approved))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!((same.revision, same.edited_at), (2, edited.edited_at));
+
+    let replacement = CreateReview {
+        body: Some("grew on me".to_string()),
+        rating: 4,
+        ..body
+    };
+    let (replaced, _) = repo
+        .upsert(
+            Uuid::new_v4(),
+            &replacement,
+            Screened::accepted(ReviewStatus::Approved),
+        )
+        .await
+        .unwrap();
+    assert_eq!(replaced.revision, 3);
+
+    let history = repo.find_revisions(id).await.unwrap();
+    let versions: Vec<_> = history
+        .iter()
+        .map(|r| (r.revision, r.rating, r.body.clone()))
+        .collect();
+    assert_eq!(
+        versions,
+        vec![
+            (2, 4, Some("meh".to_string())),
+            (1, 2, Some("meh".to_string()))
+        ]
+    );
+    assert_eq!(history[1].created_at, created.created_at);
+    assert_eq!(history[0].created_at, edited.edited_at.unwrap());
+
+    repo.delete(id).await.unwrap();
+    repo.purge_deleted(Utc::now() + chrono::Duration::seconds(1))
+        .await
+        .unwrap();
+    assert!(repo.find_revisions(id).await.unwrap().is_empty());
+}
+
+async fn edits_keep_moderator_decisions(db: Db) {
+    let repo = db.repo();
+    let (id, moderator) = (Uuid::new_v4(), Uuid::new_v4());
+    let body = CreateReview {
+        product_id: Uuid::new_v4(),
+        user_id: Uuid::new_v4(),
+        rating: 2,
+        body: Some("meh".to_string()),
+    };
+    repo.create(id, &body, ReviewStatus::Approved)
+        .await
+        .unwrap();
+    repo.moderate(id, ReviewStatus::Rejected, Some("spam"), moderator)
+        .await
+        .unwrap();
+
+    let patch = UpdateReview {
+        rating: Some(5),
+        body: Some(Some("great".to_string())),
+    };
+    let edited = repo
+        .update(id, &patch, Screened::accepted(ReviewStatus::Approved))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(edited.status, ReviewStatus::Rejected);
+    assert_eq!(edited.moderated_by, Some(moderator));
+    assert!(repo
+        .find_aggregate(body.product_id)
+        .await
+        .unwrap()
+        .is_none_or(|a| a.review_count == 0));
+
+    repo.moderate(id, ReviewStatus::Approved, None, moderator)
+        .await
+        .unwrap();
+    let patch = UpdateReview {
+        rating: Some(4),
+        body: None,
+    };
+    let edited = repo
+        .update(id, &patch, Screened::accepted(ReviewStatus::Pending))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(edited.status, ReviewStatus::Approved);
+    assert_eq!(edited.moderated_by, Some(moderator));
+
+    let patch = UpdateReview {
+        rating: None,
+        body: Some(Some("see my link".to_string())),
+    };
+    let edited = repo
+        .update(id, &patch, Screened::flagged())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(edited.status, ReviewStatus::Pending);
+    assert!(edited.moderated_by.is_none() && edited.moderated_at.is_none());
+    assert_eq!(
+        repo.find_aggregate(body.product_id)
+            .await
+            .unwrap()
+            .unwrap()
+            .review_count,
+        0
+    );
+}
+
+async fn delete_removes_row(db: Db) {
+    let repo = db.repo();
+    let id = Uuid::new_v4();
     let body = CreateReview {
         product_id: Uuid::new_v4(),
         user_id: Uuid::new_v4(),
         rating: 1,
         body: None,
     };
-    repo.create(Uuid::new_v4(), &body).await.unwrap();
-    repo.create(Uuid::new_v4(), &body).await.unwrap();
+    repo.create(id, &body, ReviewStatus::Approved)
+        .await
+        .unwrap();
 
-    let list = repo.find_all().await.unwrap();
-    assert_eq!(list.len(), 2);
-    // Order is created_at DESC, so we just check we got both
+    assert!(repo.delete(id).await.unwrap());
+    assert!(repo.find_by_id(id).await.unwrap().is_none());
+    assert!(!repo.delete(id).await.unwrap());
+}
+
+async fn deleted_reviews_can_be_restored_until_purged(db: Db) {
+    let repo = db.repo();
+    let (id, kept) = (Uuid::new_v4(), Uuid::new_v4());
+    let body = CreateReview {
+        product_id: Uuid::new_v4(),
+        user_id: Uuid::new_v4(),
+        rating: 2,
+        body: None,
+    };
+    repo.create(id, &body, ReviewStatus::Approved)
+        .await
+        .unwrap();
+    let other = CreateReview {
+        product_id: body.product_id,
+        user_id: Uuid::new_v4(),
+        rating: 4,
+        body: None,
+    };
+    repo.create(kept, &other, ReviewStatus::Approved)
+        .await
+        .unwrap();
+
+    assert!(repo.delete(id).await.unwrap());
+    assert_eq!(
+        repo.find_all()
+            .await
+            .unwrap()
+            .iter()
+            .map(|r| r.id)
+            .collect::<Vec<_>>(),
+        vec![kept]
+    );
+    assert_eq!(repo.get_stats().await.unwrap(), (1, 4.0));
+    assert_eq!(
+        repo.find_aggregate(body.product_id)
+            .await
+            .unwrap()
+            .unwrap()
+            .review_count,
+        1
+    );
+    assert!(
+        repo.restore(kept).await.unwrap().is_none(),
+        "only deleted reviews can be restored"
+    );
+
+    let restored = repo.restore(id).await.unwrap().unwrap();
+    assert_eq!((restored.id, restored.rating), (id, 2));
+    assert!(repo.find_by_id(id).await.unwrap().is_some());
+    assert_eq!(
+        repo.find_aggregate(body.product_id)
+            .await
+            .unwrap()
+            .unwrap()
+            .review_count,
+        2
+    );
+
+    // Once the author reviews the product again, the deleted review cannot come back.
+    repo.delete(id).await.unwrap();
+    let (second, created) = repo
+        .upsert(
+            Uuid::new_v4(),
+            &body,
+            Screened::accepted(ReviewStatus::Approved),
+        )
+        .await
+        .unwrap();
+    assert!(created);
+    let err = repo.restore(id).await.unwrap_err();
+    assert!(matches!(err, ReviewError::Conflict(_)), "{:?}", err);
+
+    assert_eq!(
+        repo.purge_deleted(Utc::now() - chrono::Duration::hours(1))
+            .await
+            .unwrap(),
+        0
+    );
+    assert_eq!(
+        repo.purge_deleted(Utc::now() + chrono::Duration::seconds(1))
+            .await
+            .unwrap(),
+        1
+    );
+    assert!(repo.restore(id).await.unwrap().is_none());
+    assert!(repo.find_by_id(second.id).await.unwrap().is_some());
+}
+
+async fn writes_keep_product_aggregate_in_step(db: Db) {
+    let repo = db.repo();
+    let product_id = Uuid::new_v4();
+    let mut ids = Vec::new();
+    for rating in [5, 5, 2] {
+        let id = Uuid::new_v4();
+        let body = CreateReview {
+            product_id,
+            user_id: Uuid::new_v4(),
+            rating,
+            body: None,
+        };
+        repo.create(id, &body, ReviewStatus::Approved)
+            .await
+            .unwrap();
+        ids.push(id);
+    }
+    let agg = repo.find_aggregate(product_id).await.unwrap().unwrap();
+    assert_eq!(agg.counts(), [3, 12, 0, 1, 0, 0, 2]);
+
+    let patch = UpdateReview {
+        rating: Some(3),
+        body: None,
+    };
+    repo.update(ids[0], &patch, Screened::accepted(ReviewStatus::Approved))
+        .await
+        .unwrap();
+    repo.delete(ids[2]).await.unwrap();
+    let agg = repo.find_aggregate(product_id).await.unwrap().unwrap();
+    assert_eq!(agg.counts(), [2, 8, 0, 0, 1, 0, 1]);
+    assert!(agg.latest_review_at.is_some());
+
+    let (_, drift) = repo.reconcile_aggregates(false).await.unwrap();
+    assert!(drift.is_empty());
+}
+
+async fn moderation_counts_only_approved_reviews(db: Db) {
+    let repo = db.repo();
+    let product_id = Uuid::new_v4();
+    let moderator = Uuid::new_v4();
+    let id = Uuid::new_v4();
+    let body = CreateReview {
+        product_id,
+        user_id: Uuid::new_v4(),
+        rating: 4,
+        body: None,
+    };
+    repo.create(id, &body, ReviewStatus::Pending).await.unwrap();
+    assert!(repo
+        .find_aggregate(product_id)
+        .await
+        .unwrap()
+        .is_none_or(|a| a.review_count == 0));
+    let filter = ReviewFilter::default();
+    let queue = repo
+        .find_page(&filter, ReviewStatus::Pending, ReviewSort::Oldest, None, 10)
+        .await
+        .unwrap();
+    assert_eq!(queue.len(), 1);
+
+    let approved = repo
+        .moderate(id, ReviewStatus::Approved, None, moderator)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(approved.status, ReviewStatus::Approved);
+    assert_eq!(approved.moderated_by, Some(moderator));
+    let agg = repo.find_aggregate(product_id).await.unwrap().unwrap();
+    assert_eq!(agg.counts(), [1, 4, 0, 0, 0, 1, 0]);
+    assert!(agg.latest_review_at.is_some());
+
+    let rejected = repo
+        .moderate(id, ReviewStatus::Rejected, Some("spam"), moderator)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(rejected.moderation_reason.as_deref(), Some("spam"));
+    let agg = repo.find_aggregate(product_id).await.unwrap().unwrap();
+    assert_eq!(agg.counts(), [0, 0, 0, 0, 0, 0, 0]);
+    assert!(agg.latest_review_at.is_none());
+    assert!(repo
+        .moderate(Uuid::new_v4(), ReviewStatus::Approved, None, moderator)
+        .await
+        .unwrap()
+        .is_none());
+
+    let (_, drift) = repo.reconcile_aggregates(false).await.unwrap();
+    assert!(drift.is_empty());
+}
+
+async fn reports_hide_review_once_threshold_is_reached(db: Db) {
+    let repo = db.repo();
+    let product_id = Uuid::new_v4();
+    let id = Uuid::new_v4();
+    let body = CreateReview {
+        product_id,
+        user_id: Uuid::new_v4(),
+        rating: 5,
+        body: None,
+    };
+    repo.create(id, &body, ReviewStatus::Approved)
+        .await
+        .unwrap();
+    let report = CreateReport {
+        reason: ReportReason::Spam,
+        comment: Some("ad".to_string()),
+    };
+
+    let first = Uuid::new_v4();
+    let (saved, hidden) = repo
+        .report(Uuid::new_v4(), id, first, &report, 2)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(!hidden);
+    assert_eq!(saved.reporter_id, first);
+    assert_eq!(saved.reason, ReportReason::Spam);
+    let err = repo
+        .report(Uuid::new_v4(), id, first, &report, 2)
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(err, ReviewError::Conflict(ref msg) if msg.contains("already reported")),
+        "{:?}",
+        err
+    );
+
+    let (_, hidden) = repo
+        .report(Uuid::new_v4(), id, Uuid::new_v4(), &report, 2)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(hidden);
+    assert_eq!(
+        repo.find_by_id(id).await.unwrap().unwrap().status,
+        ReviewStatus::Hidden
+    );
+    assert_eq!(
+        repo.find_aggregate(product_id)
+            .await
+            .unwrap()
+            .unwrap()
+            .review_count,
+        0
+    );
+    assert!(repo
+        .report(Uuid::new_v4(), Uuid::new_v4(), first, &report, 2)
+        .await
+        .unwrap()
+        .is_none());
+
+    let reports = repo.find_reports(Some(id), 10, 0).await.unwrap();
+    assert_eq!(reports.len(), 2);
+    assert_eq!(
+        repo.find_reports(None, 1, 1).await.unwrap()[0].reporter_id,
+        first
+    );
+    assert!(repo
+        .find_reports(Some(Uuid::new_v4()), 10, 0)
+        .await
+        .unwrap()
+        .is_empty());
+
+    repo.delete(id).await.unwrap();
+    repo.purge_deleted(Utc::now() + chrono::Duration::seconds(1))
+        .await
+        .unwrap();
+    assert!(repo.find_reports(None, 10, 0).await.unwrap().is_empty());
+}
+
+async fn votes_update_counts_and_helpful_order(db: Db) {
+    let repo = db.repo();
+    let product_id = Uuid::new_v4();
+    let mut ids = Vec::new();
+    for _ in 0..3 {
+        let id = Uuid::new_v4();
+        let body = CreateReview {
+            product_id,
+            user_id: Uuid::new_v4(),
+            rating: 4,
+            body: None,
+        };
+        repo.create(id, &body, ReviewStatus::Approved)
+            .await
+            .unwrap();
+        ids.push(id);
+    }
+    // ids[1]: 3 helpful; ids[2]: 1 helpful, 1 unhelpful; ids[0]: no votes.
+    for _ in 0..3 {
+        repo.vote(ids[1], Uuid::new_v4(), Some(true)).await.unwrap();
+    }
+    let voter = Uuid::new_v4();
+    repo.vote(ids[2], voter, Some(true)).await.unwrap();
+    let changed = repo
+        .vote(ids[2], voter, Some(false))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!((changed.helpful_count, changed.unhelpful_count), (0, 1));
+    let r = repo
+        .vote(ids[2], Uuid::new_v4(), Some(true))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!((r.helpful_count, r.unhelpful_count), (1, 1));
+    assert!(r.helpful_score > 0.0);
+    assert!(repo
+        .vote(Uuid::new_v4(), voter, Some(true))
+        .await
+        .unwrap()
+        .is_none());
+
+    let filter = ReviewFilter {
+        product_id: Some(product_id),
+        ..ReviewFilter::default()
+    };
+    let first = repo
+        .find_page(
+            &filter,
+            ReviewStatus::Approved,
+            ReviewSort::MostHelpful,
+            None,
+            2,
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        first.iter().map(|r| r.id).collect::<Vec<_>>(),
+        vec![ids[1], ids[2]]
+    );
+    let last = &first[1];
+    let after =
+        Cursor::new(last.rating, last.created_at, last.id).with_helpful_score(last.helpful_score);
+    let rest = repo
+        .find_page(
+            &filter,
+            ReviewStatus::Approved,
+            ReviewSort::MostHelpful,
+            Some(after),
+            2,
+        )
+        .await
+        .unwrap();
+    assert_eq!(rest.iter().map(|r| r.id).collect::<Vec<_>>(), vec![ids[0]]);
+
+    let retracted = repo.vote(ids[2], voter, None).await.unwrap().unwrap();
+    assert_eq!((retracted.helpful_count, retracted.unhelpful_count), (1, 0));
+    repo.delete(ids[1]).await.unwrap();
+}
+
+async fn replies_are_one_per_review_and_purged_with_it(db: Db) {
+    let repo = db.repo();
+    let (id, other) = (Uuid::new_v4(), Uuid::new_v4());
+    for review_id in [id, other] {
+        let body = CreateReview {
+            product_id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            rating: 3,
+            body: None,
+        };
+        repo.create(review_id, &body, ReviewStatus::Approved)
+            .await
+            .unwrap();
+    }
+    let merchant = Uuid::new_v4();
+    let reply = repo
+        .create_reply(id, merchant, "Thanks!", ReviewStatus::Pending)
+        .await
+        .unwrap();
+    assert_eq!(
+        (reply.merchant_id, reply.status),
+        (merchant, ReviewStatus::Pending)
+    );
+    let err = repo
+        .create_reply(id, Uuid::new_v4(), "Again", ReviewStatus::Pending)
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(err, ReviewError::Conflict(ref msg) if msg.contains("already has a reply")),
+        "{:?}",
+        err
+    );
+    assert!(repo
+        .create_reply(Uuid::new_v4(), merchant, "Nobody", ReviewStatus::Pending)
+        .await
+        .is_err());
+    repo.create_reply(other, merchant, "Cheers", ReviewStatus::Approved)
+        .await
+        .unwrap();
+
+    let queue = repo
+        .find_reply_queue(ReviewStatus::Pending, 10, 0)
+        .await
+        .unwrap();
+    assert_eq!(
+        queue.iter().map(|r| r.review_id).collect::<Vec<_>>(),
+        vec![id]
+    );
+    let edited = repo
+        .update_reply(id, "Thank you!", ReviewStatus::Pending)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(edited.body, "Thank you!");
+    assert!(edited.updated_at >= reply.updated_at);
+    let moderator = Uuid::new_v4();
+    let approved = repo
+        .moderate_reply(id, ReviewStatus::Approved, None, moderator)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        (approved.status, approved.moderated_by),
+        (ReviewStatus::Approved, Some(moderator))
+    );
+    assert!(repo
+        .update_reply(Uuid::new_v4(), "x", ReviewStatus::Pending)
+        .await
+        .unwrap()
+        .is_none());
+
+    assert_eq!(
+        repo.find_replies(&[id, other, Uuid::new_v4()])
+            .await
+            .unwrap()
+            .len(),
+        2
+    );
+    assert!(repo.delete_reply(other).await.unwrap());
+    assert!(!repo.delete_reply(other).await.unwrap());
+    repo.delete(id).await.unwrap();
+    repo.purge_deleted(Utc::now() + chrono::Duration::seconds(1))
+        .await
+        .unwrap();
+    assert!(repo.find_replies(&[id, other]).await.unwrap().is_empty());
+}
+
+async fn reconcile_reports_and_repairs_drift(db: Db) {
+    let repo = db.repo();
+    let product_id = Uuid::new_v4();
+    let body = CreateReview {
+        product_id,
+        user_id: Uuid::new_v4(),
+        rating: 4,
+        body: None,
+    };
+    repo.create(Uuid::new_v4(), &body, ReviewStatus::Approved)
+        .await
+        .unwrap();
+    db.corrupt_aggregate(product_id).await;
+
+    let (checked, drift) = repo.reconcile_aggregates(false).await.unwrap();
+    assert_eq!(checked, 1);
+    assert_eq!(drift.len(), 1);
+    let (expected, stored) = &drift[0];
+    assert_eq!(expected.product_id, product_id);
+    assert_eq!(expected.review_count, 1);
+    assert_eq!(stored.review_count, 7);
+
+    let (_, drift) = repo.reconcile_aggregates(true).await.unwrap();
+    assert_eq!(drift.len(), 1);
+    let (_, drift) = repo.reconcile_aggregates(false).await.unwrap();
+    assert!(drift.is_empty());
+    let agg = repo.find_aggregate(product_id).await.unwrap().unwrap();
+    assert_eq!(agg.counts(), [1, 4, 0, 0, 0, 1, 0]);
+}
+
+fn ts(s: &str) -> DateTime<Utc> {
+    s.parse().unwrap()
+}
+
+async fn timeseries_zero_fills_buckets_in_caller_time_zone(db: Db) {
+    let repo = db.repo();
+    for (rating, at) in [
+        (4, "2024-03-01T10:00:00Z"),
+        (2, "2024-03-01T23:30:00Z"),
+        (5, "2024-03-03T12:00:00Z"),
+    ] {
+        let id = Uuid::new_v4();
+        let body = CreateReview {
+            product_id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            rating,
+            body: None,
+        };
+        repo.create(id, &body, ReviewStatus::Approved)
+            .await
+            .unwrap();
+        db.set_created_at(id, ts(at)).await;
+    }
+    let (from, to) = (ts("2024-03-01T00:00:00Z"), ts("2024-03-04T00:00:00Z"));
+
+    let utc = repo
+        .timeseries(TimeBucket::Day, "UTC", from, to, None)
+        .await
+        .unwrap();
+    let counts: Vec<i64> = utc.iter().map(|r| r.review_count).collect();
+    assert_eq!(counts, vec![2, 0, 1]);
+    assert_eq!(utc[0].bucket_start, from);
+    assert_eq!(utc[0].avg_rating, Some(3.0));
+    assert_eq!(utc[1].avg_rating, None);
+
+    if !db.supports_time_zones() {
+        return;
+    }
+    // UTC-5: the range starts on Feb 29 local time and the 23:30Z review still falls on Mar 1.
+    let ny = repo
+        .timeseries(TimeBucket::Day, "America/New_York", from, to, None)
+        .await
+        .unwrap();
+    let counts: Vec<i64> = ny.iter().map(|r| r.review_count).collect();
+    assert_eq!(counts, vec![0, 2, 0, 1]);
+    assert_eq!(ny[1].bucket_start, ts("2024-03-01T05:00:00Z"));
+}
+
+async fn top_products_window_ignores_older_reviews(db: Db) {
+    let repo = db.repo();
+    let (old, recent) = (Uuid::new_v4(), Uuid::new_v4());
+    for (product_id, rating) in [(old, 5), (old, 5), (recent, 3)] {
+        let id = Uuid::new_v4();
+        let body = CreateReview {
+            product_id,
+            user_id: Uuid::new_v4(),
+            rating,
+            body: None,
+        };
+        repo.create(id, &body, ReviewStatus::Approved)
+            .await
+            .unwrap();
+        if product_id == old {
+            db.set_created_at(id, Utc::now() - chrono::Duration::days(90))
+                .await;
+        }
+    }
+
+    let all_time = repo
+        .top_products(LeaderboardOrder::MostReviewed, None, 1, 3.0, 5.0, 10, 0)
+        .await
+        .unwrap();
+    assert_eq!(
+        all_time.iter().map(|a| a.product_id).collect::<Vec<_>>(),
+        vec![old, recent]
+    );
+
+    let since = Utc::now() - chrono::Duration::days(30);
+    let windowed = repo
+        .top_products(
+            LeaderboardOrder::MostReviewed,
+            Some(since),
+            1,
+            3.0,
+            5.0,
+            10,
+            0,
+        )
+        .await
+        .unwrap();
+    assert_eq!(windowed.len(), 1);
+    assert_eq!(windowed[0].product_id, recent);
+    assert_eq!(windowed[0].star_3, 1);
+}
+
+async fn api_keys_are_found_by_hash_and_revoked_once(db: Db) {
+    let repo = db.repo();
+    let key = |name: &str, hash: &str| ApiKey {
+        id: Uuid::new_v4(),
+        name: name.to_string(),
+        prefix: "rvk_abcdefgh".to_string(),
+        key_hash: hash.to_string(),
+        scopes: vec![ApiScope::Read, ApiScope::Stats],
+        created_at: Utc::now(),
+        expires_at: Some(Utc::now() + chrono::Duration::days(30)),
+        revoked_at: None,
+    };
+    let orders = key("order-service", "aa11");
+    repo.create_api_key(&orders).await.unwrap();
+    let catalog = key("catalog-service", "bb22");
+    repo.create_api_key(&catalog).await.unwrap();
+
+    let found = repo.find_api_key_by_hash("aa11").await.unwrap().unwrap();
+    assert_eq!(found.id, orders.id);
+    assert_eq!(found.name, "order-service");
+    assert_eq!(found.scopes, vec![ApiScope::Read, ApiScope::Stats]);
+    assert!(found.expires_at.is_some());
+    assert!(repo.find_api_key_by_hash("cc33").await.unwrap().is_none());
+    let names: Vec<String> = repo
+        .find_api_keys()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|k| k.name)
+        .collect();
+    assert_eq!(names, vec!["catalog-service", "order-service"]);
+
+    let revoked = repo.revoke_api_key(orders.id).await.unwrap().unwrap();
+    let revoked_at = revoked.revoked_at.expect("revocation is stamped");
+    let again = repo.revoke_api_key(orders.id).await.unwrap().unwrap();
+    assert_eq!(again.revoked_at, Some(revoked_at));
+    assert!(repo
+        .find_api_key_by_hash("aa11")
+        .await
+        .unwrap()
+        .unwrap()
+        .revoked_at
+        .is_some());
+    assert!(repo.revoke_api_key(Uuid::new_v4()).await.unwrap().is_none());
+}
+
+async fn idempotency_keys_are_claimed_once_until_they_expire(db: Db) {
+    let repo = db.repo();
+    let now = Utc::now();
+    let record = |scope: &str, hash: &str, expires_in: chrono::Duration| IdempotencyRecord {
+        scope: scope.to_string(),
+        key: "retry-1".to_string(),
+        request_hash: hash.to_string(),
+        response_status: None,
+        response_body: None,
+        created_at: now,
+        expires_at: now + expires_in,
+    };
+    let day = chrono::Duration::days(1);
+    assert!(repo
+        .claim_idempotency_key(&record("user:a", "aa11", day))
+        .await
+        .unwrap()
+        .is_none());
+    let held = repo
+        .claim_idempotency_key(&record("user:a", "bb22", day))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        (held.request_hash.as_str(), held.response_status),
+        ("aa11", None)
+    );
+    assert!(repo
+        .claim_idempotency_key(&record("user:b", "bb22", day))
+        .await
+        .unwrap()
+        .is_none());
+
+    repo.complete_idempotency_key("user:a", "retry-1", 201, r#"{"id":1}"#)
+        .await
+        .unwrap();
+    let done = repo
+        .claim_idempotency_key(&record("user:a", "aa11", day))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(done.response_status, Some(201));
+    assert_eq!(done.response_body.as_deref(), Some(r#"{"id":1}"#));
+
+    repo.release_idempotency_key("user:b", "retry-1")
+        .await
+        .unwrap();
+    assert!(repo
+        .claim_idempotency_key(&record("user:b", "cc33", -day))
+        .await
+        .unwrap()
+        .is_none());
+    // An expired record gives way to the next claim, and is purged.
+    assert!(repo
+        .claim_idempotency_key(&record("user:b", "dd44", day))
+        .await
+        .unwrap()
+        .is_none());
+    repo.complete_idempotency_key("user:b", "retry-1", 201, "{}")
+        .await
+        .unwrap();
+    assert_eq!(repo.purge_idempotency_keys(now + day * 2).await.unwrap(), 2);
+    assert!(repo
+        .claim_idempotency_key(&record("user:a", "ee55", day))
+        .await
+        .unwrap()
+        .is_none());
 }
 
-#[sqlx::test]
-async fn get_stats_empty(pool: PgPool) {
-    let repo = ReviewRepository::new(pool);
+async fn get_stats_empty(db: Db) {
+    let repo = db.repo();
     let (total, avg) = repo.get_stats().await.unwrap();
     assert_eq!(total, 0);
     assert!((avg - 0.0).abs() < 1e-9);
 }
 
-#[sqlx::test]
-async fn get_stats_after_inserts(pool: PgPool) {
-    let repo = ReviewRepository::new(pool);
+async fn get_stats_after_inserts(db: Db) {
+    let repo = db.repo();
     let body = CreateReview {
         product_id: Uuid::new_v4(),
         user_id: Uuid::new_v4(),
         rating: 3,
         body: None,
     };
-    repo.create(Uuid::new_v4(), &body).await.unwrap();
+    repo.create(Uuid::new_v4(), &body, ReviewStatus::Approved)
+        .await
+        .unwrap();
     let body5 = CreateReview {
         product_id: Uuid::new_v4(),
         user_id: Uuid::new_v4(),
         rating: 5,
         body: None,
     };
-    repo.create(Uuid::new_v4(), &body5).await.unwrap();
+    repo.create(Uuid::new_v4(), &body5, ReviewStatus::Approved)
+        .await
+        .unwrap();
 
     let (total, avg) = repo.get_stats().await.unwrap();
     assert_eq!(total, 2);
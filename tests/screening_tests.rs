@@ -0,0 +1,81 @@
+//! Screener tests. Pure functions over review text; no database needed.
+
+use my_ex_review_service::service::{
+    BannedWords, ContactDetails, ExcessiveCaps, RepeatedChars, Screener, ScreeningChain,
+    ScreeningConfig, Verdict,
+};
+
+fn is_flag(v: &Verdict) -> bool {
+    matches!(v, Verdict::Flag(_))
+}
+
+#[test]
+fn banned_words_match_whole_words_ignoring_case() {
+    let screener = BannedWords::new(["scam", " "]);
+    assert!(matches!(
+        screener.screen("Total SCAM, avoid"),
+        Verdict::Reject(_)
+    ));
+    assert_eq!(screener.screen("scampi was lovely"), Verdict::Accept);
+    assert_eq!(screener.screen("nothing to see"), Verdict::Accept);
+}
+
+#[test]
+fn contact_details_flag_links_emails_and_phone_numbers() {
+    let screener = ContactDetails::default();
+    assert!(is_flag(
+        &screener.screen("cheaper at https://example.test/deal")
+    ));
+    assert!(is_flag(&screener.screen("see shop-deals.com")));
+    assert!(is_flag(&screener.screen("mail me at jo@example.org")));
+    assert!(is_flag(&screener.screen("call +1 (555) 123-4567")));
+    assert_eq!(
+        screener.screen("arrived in 3 days, 10/10. Worth it."),
+        Verdict::Accept
+    );
+}
+
+#[test]
+fn excessive_caps_ignores_short_bodies() {
+    let screener = ExcessiveCaps::default();
+    assert_eq!(screener.screen("GREAT!"), Verdict::Accept);
+    assert!(is_flag(
+        &screener.screen("THIS IS THE WORST PRODUCT I HAVE EVER BOUGHT")
+    ));
+    assert_eq!(
+        screener.screen("This is the best product I have ever bought, TBH"),
+        Verdict::Accept
+    );
+}
+
+#[test]
+fn repeated_chars_allow_short_runs() {
+    let screener = RepeatedChars { max_run: 3 };
+    assert_eq!(screener.screen("sooo good!!!"), Verdict::Accept);
+    assert!(is_flag(&screener.screen("soooo good")));
+    assert_eq!(screener.screen("line\n\n\n\n\nbreaks"), Verdict::Accept);
+}
+
+#[test]
+fn chain_prefers_rejection_over_earlier_flags() {
+    let config = ScreeningConfig {
+        banned_words: vec!["scam".to_string()],
+        ..ScreeningConfig::default()
+    };
+    let chain = ScreeningChain::new()
+        .then(ContactDetails::default())
+        .then(BannedWords::new(&config.banned_words));
+    assert!(matches!(
+        chain.screen("scam, see example.com"),
+        Verdict::Reject(_)
+    ));
+    assert!(is_flag(&chain.screen("see example.com")));
+    assert_eq!(
+        ScreeningChain::new().screen("anything at all"),
+        Verdict::Accept
+    );
+    assert_eq!(
+        ScreeningChain::from_config(&config).screen("Solid kettle, boils fast."),
+        Verdict::Accept
+    );
+}
@@ -8,28 +8,39 @@ fn load_env() {
     let _ = dotenvy::dotenv();
 }
 
-use my_ex_review_service::models::CreateReview;
-use my_ex_review_service::service::ReviewService;
+use my_ex_review_service::error::ReviewError;
+use my_ex_review_service::models::ReviewStatus;
+use my_ex_review_service::models::{
+    CreateReview, LeaderboardOrder, LeaderboardQuery, ListReviewsQuery, ReviewFilter, UpdateReview,
+};
+use my_ex_review_service::service::{
+    RatingPrior, ReviewService, Screener, ScreeningChain, ScreeningConfig, ValidationLimits,
+    Verdict,
+};
 use sqlx::PgPool;
 use uuid::Uuid;
 
 #[sqlx::test]
 async fn list_reviews_empty(pool: PgPool) {
-    let service = ReviewService::new(pool);
-    let list = service.list_reviews().await.unwrap();
-    assert!(list.is_empty());
+    let service = ReviewService::new(pool).with_auto_approve(true);
+    let page = service
+        .list_reviews(ReviewFilter::default(), ListReviewsQuery::default())
+        .await
+        .unwrap();
+    assert!(page.items.is_empty());
+    assert!(page.next_cursor.is_none());
 }
 
 #[sqlx::test]
 async fn get_review_not_found(pool: PgPool) {
-    let service = ReviewService::new(pool);
-    let err = service.get_review(Uuid::new_v4()).await.unwrap_err();
-    assert_eq!(err, "Not found");
+    let service = ReviewService::new(pool).with_auto_approve(true);
+    let err = service.get_review(Uuid::new_v4(), None).await.unwrap_err();
+    assert!(matches!(err, ReviewError::NotFound(_)));
 }
 
 #[sqlx::test]
 async fn create_review_returns_response(pool: PgPool) {
-    let service = ReviewService::new(pool);
+    let service = ReviewService::new(pool).with_auto_approve(true);
     let body = CreateReview {
         product_id: Uuid::new_v4(),
         user_id: Uuid::new_v4(),
@@ -43,7 +54,7 @@ async fn create_review_returns_response(pool: PgPool) {
 
 #[sqlx::test]
 async fn create_then_get_review(pool: PgPool) {
-    let service = ReviewService::new(pool);
+    let service = ReviewService::new(pool).with_auto_approve(true);
     let body = CreateReview {
         product_id: Uuid::new_v4(),
         user_id: Uuid::new_v4(),
@@ -51,14 +62,14 @@ async fn create_then_get_review(pool: PgPool) {
         body: None,
     };
     let created = service.create_review(body).await.unwrap();
-    let got = service.get_review(created.id).await.unwrap();
+    let got = service.get_review(created.id, None).await.unwrap();
     assert_eq!(got.id, created.id);
     assert_eq!(got.rating, 4);
 }
 
 #[sqlx::test]
 async fn list_reviews_after_create(pool: PgPool) {
-    let service = ReviewService::new(pool);
+    let service = ReviewService::new(pool).with_auto_approve(true);
     let body = CreateReview {
         product_id: Uuid::new_v4(),
         user_id: Uuid::new_v4(),
@@ -66,14 +77,167 @@ async fn list_reviews_after_create(pool: PgPool) {
         body: None,
     };
     service.create_review(body).await.unwrap();
-    let list = service.list_reviews().await.unwrap();
-    assert_eq!(list.len(), 1);
-    assert_eq!(list[0].rating, 3);
+    let page = service
+        .list_reviews(ReviewFilter::default(), ListReviewsQuery::default())
+        .await
+        .unwrap();
+    assert_eq!(page.items.len(), 1);
+    assert_eq!(page.items[0].rating, 3);
+}
+
+#[sqlx::test]
+async fn list_reviews_sets_next_cursor_only_when_more_remain(pool: PgPool) {
+    let service = ReviewService::new(pool).with_auto_approve(true);
+    for rating in [1, 2, 3] {
+        let body = CreateReview {
+            product_id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            rating,
+            body: None,
+        };
+        service.create_review(body).await.unwrap();
+    }
+    let first = service
+        .list_reviews(
+            ReviewFilter::default(),
+            ListReviewsQuery {
+                limit: Some(2),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(first.items.len(), 2);
+    let cursor = first.next_cursor.expect("more reviews remain");
+
+    let second = service
+        .list_reviews(
+            ReviewFilter::default(),
+            ListReviewsQuery {
+                cursor: Some(cursor.try_into().unwrap()),
+                limit: Some(2),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(second.items.len(), 1);
+    assert!(second.next_cursor.is_none());
+}
+
+#[sqlx::test]
+async fn update_review_requires_author(pool: PgPool) {
+    let service = ReviewService::new(pool).with_auto_approve(true);
+    let author = Uuid::new_v4();
+    let body = CreateReview {
+        product_id: Uuid::new_v4(),
+        user_id: author,
+        rating: 2,
+        body: None,
+    };
+    let created = service.create_review(body).await.unwrap();
+
+    let patch = UpdateReview {
+        rating: Some(5),
+        body: None,
+    };
+    let err = service
+        .update_review(created.id, Uuid::new_v4(), patch)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ReviewError::Forbidden(_)));
+
+    let patch = UpdateReview {
+        rating: Some(5),
+        body: Some(Some("better now".to_string())),
+    };
+    let updated = service
+        .update_review(created.id, author, patch)
+        .await
+        .unwrap();
+    assert_eq!(updated.rating, 5);
+    assert_eq!(updated.body.as_deref(), Some("better now"));
+}
+
+#[sqlx::test]
+async fn delete_review_requires_author(pool: PgPool) {
+    let service = ReviewService::new(pool).with_auto_approve(true);
+    let author = Uuid::new_v4();
+    let body = CreateReview {
+        product_id: Uuid::new_v4(),
+        user_id: author,
+        rating: 2,
+        body: None,
+    };
+    let created = service.create_review(body).await.unwrap();
+
+    let err = service
+        .delete_review(created.id, Uuid::new_v4())
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ReviewError::Forbidden(_)));
+    service.delete_review(created.id, author).await.unwrap();
+    let err = service.get_review(created.id, None).await.unwrap_err();
+    assert!(matches!(err, ReviewError::NotFound(_)));
+}
+
+#[sqlx::test]
+async fn create_review_reports_every_invalid_field(pool: PgPool) {
+    let service = ReviewService::new(pool).with_auto_approve(true);
+    let body = CreateReview {
+        product_id: Uuid::nil(),
+        user_id: Uuid::new_v4(),
+        rating: 9,
+        body: Some("   ".to_string()),
+    };
+    let err = service.create_review(body).await.unwrap_err();
+    let ReviewError::Validation { errors, .. } = err else {
+        panic!("expected validation error, got {:?}", err);
+    };
+    let mut fields: Vec<(&str, &str)> = errors
+        .iter()
+        .map(|e| (e.field.as_str(), e.code.as_str()))
+        .collect();
+    fields.sort();
+    assert_eq!(
+        fields,
+        vec![
+            ("body", "blank"),
+            ("product_id", "nil_uuid"),
+            ("rating", "out_of_range")
+        ]
+    );
+}
+
+#[sqlx::test]
+async fn create_review_trims_body_and_applies_configured_limit(pool: PgPool) {
+    let service = ReviewService::new(pool)
+        .with_auto_approve(true)
+        .with_limits(ValidationLimits { max_body_chars: 5 });
+    let body = CreateReview {
+        product_id: Uuid::new_v4(),
+        user_id: Uuid::new_v4(),
+        rating: 4,
+        body: Some("  short  ".to_string()),
+    };
+    let r = service.create_review(body).await.unwrap();
+    assert_eq!(r.body.as_deref(), Some("short"));
+
+    let body = CreateReview {
+        product_id: Uuid::new_v4(),
+        user_id: Uuid::new_v4(),
+        rating: 4,
+        body: Some("too long".to_string()),
+    };
+    let err = service.create_review(body).await.unwrap_err();
+    assert!(
+        matches!(err, ReviewError::Validation { ref errors, .. } if errors[0].code == "too_long")
+    );
 }
 
 #[sqlx::test]
 async fn get_dashboard_stats_empty(pool: PgPool) {
-    let service = ReviewService::new(pool);
+    let service = ReviewService::new(pool).with_auto_approve(true);
     let stats = service.get_dashboard_stats().await.unwrap();
     assert_eq!(stats.total_reviews, 0);
     assert!((stats.avg_rating - 0.0).abs() < 1e-9);
@@ -81,7 +245,7 @@ async fn get_dashboard_stats_empty(pool: PgPool) {
 
 #[sqlx::test]
 async fn get_dashboard_stats_after_reviews(pool: PgPool) {
-    let service = ReviewService::new(pool);
+    let service = ReviewService::new(pool).with_auto_approve(true);
     for rating in [3, 5, 4] {
         let body = CreateReview {
             product_id: Uuid::new_v4(),
@@ -95,3 +259,197 @@ async fn get_dashboard_stats_after_reviews(pool: PgPool) {
     assert_eq!(stats.total_reviews, 3);
     assert!((stats.avg_rating - 4.0).abs() < 0.01);
 }
+
+#[sqlx::test]
+async fn get_product_stats_summarizes_one_product(pool: PgPool) {
+    let service = ReviewService::new(pool)
+        .with_auto_approve(true)
+        .with_prior(RatingPrior {
+            mean: 3.0,
+            weight: 2.0,
+        });
+    let product_id = Uuid::new_v4();
+    for rating in [5, 5, 4, 1] {
+        let body = CreateReview {
+            product_id,
+            user_id: Uuid::new_v4(),
+            rating,
+            body: None,
+        };
+        service.create_review(body).await.unwrap();
+    }
+
+    let stats = service.get_product_stats(product_id).await.unwrap();
+    assert_eq!(stats.review_count, 4);
+    assert!((stats.avg_rating - 3.75).abs() < 1e-9);
+    assert_eq!(
+        (
+            stats.histogram.one,
+            stats.histogram.four,
+            stats.histogram.five
+        ),
+        (1, 1, 2)
+    );
+    assert!(stats.latest_review_at.is_some());
+    // (2 * 3.0 + 15) / (2 + 4)
+    assert!((stats.bayesian_score - 3.5).abs() < 1e-9);
+}
+
+#[sqlx::test]
+async fn get_product_stats_without_reviews_uses_prior(pool: PgPool) {
+    let service = ReviewService::new(pool).with_auto_approve(true);
+    let stats = service.get_product_stats(Uuid::new_v4()).await.unwrap();
+    assert_eq!(stats.review_count, 0);
+    assert!(stats.latest_review_at.is_none());
+    assert!((stats.bayesian_score - RatingPrior::default().mean).abs() < 1e-9);
+}
+
+/// Create one review per rating for a fresh product and return its id.
+async fn product_with_ratings(service: &ReviewService, ratings: &[i32]) -> Uuid {
+    let product_id = Uuid::new_v4();
+    for &rating in ratings {
+        let body = CreateReview {
+            product_id,
+            user_id: Uuid::new_v4(),
+            rating,
+            body: None,
+        };
+        service.create_review(body).await.unwrap();
+    }
+    product_id
+}
+
+#[sqlx::test]
+async fn get_leaderboard_ranks_by_order(pool: PgPool) {
+    let service = ReviewService::new(pool)
+        .with_auto_approve(true)
+        .with_prior(RatingPrior {
+            mean: 3.0,
+            weight: 2.0,
+        });
+    // A single 5-star review should not beat a product with many 5-star reviews.
+    let lone = product_with_ratings(&service, &[5]).await;
+    let loved = product_with_ratings(&service, &[5, 5, 5, 4]).await;
+    let hated = product_with_ratings(&service, &[1, 1, 2]).await;
+
+    let best = service
+        .get_leaderboard(LeaderboardQuery::default())
+        .await
+        .unwrap();
+    let ids: Vec<Uuid> = best.items.iter().map(|r| r.product_id).collect();
+    assert_eq!(ids, vec![loved, lone, hated]);
+    assert_eq!(best.items[0].rank, 1);
+    assert_eq!(best.items[0].histogram.five, 3);
+
+    let query = LeaderboardQuery {
+        order: Some(LeaderboardOrder::Worst),
+        ..Default::default()
+    };
+    let worst = service.get_leaderboard(query).await.unwrap();
+    assert_eq!(worst.items[0].product_id, hated);
+
+    let query = LeaderboardQuery {
+        order: Some(LeaderboardOrder::MostReviewed),
+        min_reviews: Some(3),
+        ..Default::default()
+    };
+    let busiest = service.get_leaderboard(query).await.unwrap();
+    let ids: Vec<Uuid> = busiest.items.iter().map(|r| r.product_id).collect();
+    assert_eq!(ids, vec![loved, hated]);
+}
+
+#[sqlx::test]
+async fn get_leaderboard_pages_by_offset(pool: PgPool) {
+    let service = ReviewService::new(pool).with_auto_approve(true);
+    for rating in [1, 2, 3] {
+        product_with_ratings(&service, &[rating]).await;
+    }
+    let query = LeaderboardQuery {
+        limit: Some(2),
+        ..Default::default()
+    };
+    let first = service.get_leaderboard(query).await.unwrap();
+    assert_eq!(first.items.len(), 2);
+    assert_eq!(first.next_offset, Some(2));
+
+    let query = LeaderboardQuery {
+        limit: Some(2),
+        offset: first.next_offset.map(|o| o as u32),
+        ..Default::default()
+    };
+    let second = service.get_leaderboard(query).await.unwrap();
+    assert_eq!(second.items.len(), 1);
+    assert_eq!(second.items[0].rank, 3);
+    assert!(second.next_offset.is_none());
+}
+
+#[sqlx::test]
+async fn screening_rejects_banned_words_and_flags_links(pool: PgPool) {
+    let config = ScreeningConfig {
+        banned_words: vec!["scam".to_string()],
+        ..ScreeningConfig::default()
+    };
+    let service = ReviewService::new(pool)
+        .with_auto_approve(true)
+        .with_screening(ScreeningChain::from_config(&config));
+    let review = |body: &str| CreateReview {
+        product_id: Uuid::new_v4(),
+        user_id: Uuid::new_v4(),
+        rating: 1,
+        body: Some(body.to_string()),
+    };
+
+    let err = service
+        .create_review(review("what a scam"))
+        .await
+        .unwrap_err();
+    let ReviewError::Validation { errors, .. } = err else {
+        panic!("expected Validation, got {:?}", err);
+    };
+    assert_eq!(errors[0].field, "body");
+    assert_eq!(errors[0].code, "rejected");
+
+    let flagged = service
+        .create_review(review("better at www.example.test"))
+        .await
+        .unwrap();
+    assert_eq!(flagged.status, ReviewStatus::Pending);
+    let clean = service.create_review(review("fine")).await.unwrap();
+    assert_eq!(clean.status, ReviewStatus::Approved);
+
+    // Editing only the rating still screens the stored body.
+    let patch = UpdateReview {
+        rating: Some(2),
+        body: None,
+    };
+    let edited = service
+        .update_review(flagged.id, flagged.user_id, patch)
+        .await
+        .unwrap();
+    assert_eq!(edited.status, ReviewStatus::Pending);
+}
+
+#[sqlx::test]
+async fn custom_screeners_plug_into_the_chain(pool: PgPool) {
+    struct NoShouting;
+    impl Screener for NoShouting {
+        fn screen(&self, body: &str) -> Verdict {
+            if body.contains('!') {
+                Verdict::Flag("exclamation".to_string())
+            } else {
+                Verdict::Accept
+            }
+        }
+    }
+    let service = ReviewService::new(pool)
+        .with_auto_approve(true)
+        .with_screening(ScreeningChain::new().then(NoShouting));
+    let body = CreateReview {
+        product_id: Uuid::new_v4(),
+        user_id: Uuid::new_v4(),
+        rating: 5,
+        body: Some("wow!".to_string()),
+    };
+    let r = service.create_review(body).await.unwrap();
+    assert_eq!(r.status, ReviewStatus::Pending);
+}